@@ -0,0 +1,49 @@
+//! Compares converting many tiny CSVs with and without reusing buffers.
+//!
+//! Run with `cargo bench --bench converter`.
+
+use csvmd::{csv_to_markdown, Config, Converter};
+use std::hint::black_box;
+use std::io::Cursor;
+use std::time::Instant;
+
+const CONVERSIONS: usize = 10_000;
+
+fn tiny_csv(i: usize) -> String {
+    format!(
+        "Name,Age,City\nPerson {},{},City {}\n",
+        i,
+        20 + i % 50,
+        i % 7
+    )
+}
+
+fn main() {
+    let inputs: Vec<String> = (0..CONVERSIONS).map(tiny_csv).collect();
+
+    let start = Instant::now();
+    for input in &inputs {
+        let markdown = csv_to_markdown(Cursor::new(input), Config::default()).unwrap();
+        black_box(markdown);
+    }
+    let fresh = start.elapsed();
+
+    let start = Instant::now();
+    let mut converter = Converter::new(Config::default());
+    for input in &inputs {
+        let markdown = converter.convert(Cursor::new(input)).unwrap();
+        black_box(markdown);
+    }
+    let reused = start.elapsed();
+
+    println!(
+        "{} conversions with csv_to_markdown: {:.2}ms",
+        CONVERSIONS,
+        fresh.as_secs_f64() * 1000.0
+    );
+    println!(
+        "{} conversions with a reused Converter: {:.2}ms",
+        CONVERSIONS,
+        reused.as_secs_f64() * 1000.0
+    );
+}
@@ -0,0 +1,81 @@
+//! A self-test benchmark comparing the conversion modes on synthetic data.
+
+use csvmd::error::Result;
+use csvmd::{csv_to_markdown, csv_to_markdown_streaming, Config};
+use std::fmt::Write as _;
+use std::io::{self, Cursor};
+use std::time::{Duration, Instant};
+
+/// Numbers of rows to generate for each benchmark run.
+const SIZES: [usize; 3] = [1_000, 10_000, 100_000];
+
+const FIRST_NAMES: [&str; 6] = ["John", "Jane", "Alex", "Maria", "Wei", "Olu"];
+const LAST_NAMES: [&str; 6] = ["Smith", "García", "Chen", "O'Connor", "Müller", "Okafor"];
+const DEPARTMENTS: [&str; 4] = ["Engineering", "Sales", "Marketing", "Support"];
+
+/// Generate employee records resembling real-world data, including quoted fields,
+/// embedded commas and pipes.
+fn generate_csv(rows: usize) -> String {
+    let mut csv = String::from("ID,Name,Email,Department,Salary,Notes\n");
+
+    for i in 0..rows {
+        let first = FIRST_NAMES[i % FIRST_NAMES.len()];
+        let last = LAST_NAMES[(i / FIRST_NAMES.len()) % LAST_NAMES.len()];
+        let department = DEPARTMENTS[i % DEPARTMENTS.len()];
+        let _ = writeln!(
+            csv,
+            "{},{} {},{}.{}{}@example.com,{},{},\"Joined in {}, team | {}\"",
+            i + 1,
+            first,
+            last,
+            first.to_lowercase(),
+            i,
+            last.len(),
+            department,
+            30_000 + (i * 37) % 90_000,
+            2000 + i % 25,
+            department
+        );
+    }
+
+    csv
+}
+
+/// Format a duration with the throughput it represents for `bytes` of input.
+fn format_run(elapsed: Duration, bytes: usize) -> String {
+    let seconds = elapsed.as_secs_f64().max(f64::EPSILON);
+    let throughput = bytes as f64 / (1024.0 * 1024.0) / seconds;
+    format!(
+        "{:.1}ms ({:.1} MB/s)",
+        elapsed.as_secs_f64() * 1000.0,
+        throughput
+    )
+}
+
+/// Run the benchmark and print the results to stdout as a Markdown table.
+pub fn run() -> Result<()> {
+    println!("| Rows | Input Size | Standard Mode | Streaming Mode |");
+    println!("| --- | --- | --- | --- |");
+
+    for rows in SIZES {
+        let csv = generate_csv(rows);
+
+        let start = Instant::now();
+        csv_to_markdown(Cursor::new(csv.as_bytes()), Config::default())?;
+        let standard = start.elapsed();
+
+        let start = Instant::now();
+        csv_to_markdown_streaming(Cursor::new(csv.as_bytes()), io::sink(), Config::default())?;
+        let streaming = start.elapsed();
+
+        println!(
+            "| {} | {:.1}KB | {} | {} |",
+            rows,
+            csv.len() as f64 / 1024.0,
+            format_run(standard, csv.len()),
+            format_run(streaming, csv.len())
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,1523 @@
+//! Command-line argument parsing shared by the `csvmd` binary.
+//!
+//! Tools that embed csvmd can reuse these arguments and build a [`Config`] from
+//! them with [`Config::from_args`], so they behave exactly like the CLI.
+
+use crate::error::CsvMdError;
+use crate::formats::OutputFormat;
+use crate::{
+    CalloutKind, ColumnRef, Config, HeaderAlignment, NumberFormat, PadSide, PipeEscape, RowFilter,
+    SummaryKind, TrimMode,
+};
+use clap::builder::BoolishValueParser;
+use clap::error::{ContextKind, ContextValue};
+use clap::parser::ValueSource;
+use clap::{Arg, ArgMatches, CommandFactory, FromArgMatches, Parser, ValueEnum};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Parse a `KEY=VALUE` pair.
+fn parse_key_value(s: &str) -> Result<(String, String), String> {
+    s.split_once('=')
+        .map(|(key, value)| (key.trim().to_string(), value.to_string()))
+        .filter(|(key, _)| !key.is_empty())
+        .ok_or_else(|| format!("expected KEY=VALUE, got `{}`", s))
+}
+
+/// Parse a `HEAD,TAIL` pair of row counts.
+fn parse_preview(s: &str) -> Result<(usize, usize), String> {
+    let counts = s.split_once(',').and_then(|(head, tail)| {
+        let head = head.trim().parse().ok()?;
+        let tail = tail.trim().parse().ok()?;
+        Some((head, tail))
+    });
+    counts.ok_or_else(|| format!("expected HEAD,TAIL row counts, like `3,3`, got `{}`", s))
+}
+
+/// Parse a `COLUMN:PATTERN` number format for a zero-based column.
+fn parse_column_number_format(s: &str) -> Result<(usize, NumberFormat), String> {
+    let (column, pattern) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected COLUMN:PATTERN, like `1:0.00`, got `{}`", s))?;
+    let column = column
+        .trim()
+        .parse()
+        .map_err(|_| format!("expected a zero-based column index, got `{}`", column))?;
+    let format = pattern.parse().map_err(|err| format!("{}", err))?;
+    Ok((column, format))
+}
+
+/// Parse a delimiter, either a single ASCII character or one of the names
+/// `tab`, `semicolon`, `pipe`, `comma` or `space`.
+fn parse_delimiter(s: &str) -> Result<u8, String> {
+    match s.to_ascii_lowercase().as_str() {
+        "tab" => return Ok(b'\t'),
+        "semicolon" => return Ok(b';'),
+        "pipe" => return Ok(b'|'),
+        "comma" => return Ok(b','),
+        "space" => return Ok(b' '),
+        _ => {}
+    }
+
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii() => Ok(c as u8),
+        (Some(c), None) => Err(format!(
+            "`{}` takes {} bytes, but only single-byte ASCII delimiters are supported, because CSV is split on a single byte",
+            c,
+            c.len_utf8()
+        )),
+        _ => Err(format!(
+            "expected a single ASCII character or one of `tab`, `semicolon`, `pipe`, `comma` or `space`, got `{}`",
+            s
+        )),
+    }
+}
+
+/// Alignment values accepted by `--align`.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ClapAlignment {
+    /// Left-aligned headers
+    Left,
+    /// Center-aligned headers
+    Center,
+    /// Center-aligned headers (British spelling)
+    Centre,
+    /// Right-aligned headers
+    Right,
+}
+
+impl From<ClapAlignment> for HeaderAlignment {
+    fn from(clap_align: ClapAlignment) -> Self {
+        match clap_align {
+            ClapAlignment::Left => HeaderAlignment::Left,
+            ClapAlignment::Center | ClapAlignment::Centre => HeaderAlignment::Center,
+            ClapAlignment::Right => HeaderAlignment::Right,
+        }
+    }
+}
+
+/// Output formats accepted by `--format`.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ClapFormat {
+    /// A Markdown table
+    Markdown,
+    /// An Emacs Org-mode table
+    Org,
+    /// A reStructuredText grid table
+    Rst,
+    /// An HTML table
+    Html,
+    /// A JSON array with an object for each row
+    Json,
+    /// JSON Lines, with an object for each row on its own line
+    Jsonl,
+    /// A Mermaid class diagram, from rows like `from,relation,to`
+    Mermaid,
+}
+
+impl From<ClapFormat> for OutputFormat {
+    fn from(format: ClapFormat) -> Self {
+        match format {
+            ClapFormat::Markdown => OutputFormat::Markdown,
+            ClapFormat::Org => OutputFormat::OrgMode,
+            ClapFormat::Rst => OutputFormat::Rst,
+            ClapFormat::Html => OutputFormat::Html,
+            ClapFormat::Json => OutputFormat::Json,
+            ClapFormat::Jsonl => OutputFormat::JsonLines,
+            ClapFormat::Mermaid => OutputFormat::Mermaid,
+        }
+    }
+}
+
+/// Summaries accepted by `--summary`.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ClapSummary {
+    /// The sum of each numeric column, labelled `Total`
+    Sum,
+    /// The mean of each numeric column, labelled `Mean`
+    Mean,
+    /// The number of values in each numeric column, labelled `Count`
+    Count,
+}
+
+impl From<ClapSummary> for SummaryKind {
+    fn from(summary: ClapSummary) -> Self {
+        match summary {
+            ClapSummary::Sum => SummaryKind::Sum,
+            ClapSummary::Mean => SummaryKind::Mean,
+            ClapSummary::Count => SummaryKind::Count,
+        }
+    }
+}
+
+/// Trim modes accepted by `--trim-mode`.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ClapTrimMode {
+    /// Leave cells as they are
+    None,
+    /// Trim whitespace from both ends of each cell
+    Both,
+    /// Trim whitespace from the end of each cell, keeping any indentation
+    Trailing,
+    /// Trim whitespace from the start of each cell
+    Leading,
+}
+
+impl From<ClapTrimMode> for TrimMode {
+    fn from(trim_mode: ClapTrimMode) -> Self {
+        match trim_mode {
+            ClapTrimMode::None => TrimMode::None,
+            ClapTrimMode::Both => TrimMode::Both,
+            ClapTrimMode::Trailing => TrimMode::Trailing,
+            ClapTrimMode::Leading => TrimMode::Leading,
+        }
+    }
+}
+
+/// Pipe escapes accepted by `--pipe-escape`.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ClapPipeEscape {
+    /// Escape pipes with a backslash, as `\|`
+    Backslash,
+    /// Write pipes as the HTML entity `&#124;`
+    Entity,
+}
+
+impl From<ClapPipeEscape> for PipeEscape {
+    fn from(pipe_escape: ClapPipeEscape) -> Self {
+        match pipe_escape {
+            ClapPipeEscape::Backslash => PipeEscape::Backslash,
+            ClapPipeEscape::Entity => PipeEscape::Entity,
+        }
+    }
+}
+
+/// Callout kinds accepted by `--callout`.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ClapCallout {
+    /// `> [!NOTE]`
+    Note,
+    /// `> [!WARNING]`
+    Warning,
+    /// `> [!TIP]`
+    Tip,
+}
+
+impl From<ClapCallout> for CalloutKind {
+    fn from(callout: ClapCallout) -> Self {
+        match callout {
+            ClapCallout::Note => CalloutKind::Note,
+            ClapCallout::Warning => CalloutKind::Warning,
+            ClapCallout::Tip => CalloutKind::Tip,
+        }
+    }
+}
+
+/// Command-line arguments accepted by the `csvmd` binary.
+#[derive(Debug, Parser)]
+#[command(name = "csvmd")]
+#[command(about = "Convert a CSV to a Markdown table, outputted to stdout")]
+#[command(version)]
+pub struct Args {
+    /// The paths of the files to read - if not provided or `-`, reads from stdin
+    #[arg(value_name = "FILE")]
+    pub files: Vec<PathBuf>,
+
+    /// The delimiter between columns, as a single character or `tab`, `semicolon`, `pipe`, `comma` or `space`
+    #[arg(short, long, default_value = ",", value_parser = parse_delimiter)]
+    pub delimiter: u8,
+
+    /// Pass input which already looks like a Markdown table through unchanged
+    #[arg(long)]
+    pub detect_markdown: bool,
+
+    /// Warn when a file gives a single column but looks like it uses another delimiter (on by default when stderr is a terminal)
+    #[arg(long)]
+    pub suggest_delimiter: bool,
+
+    /// Choose the delimiter from the first rows of the input, falling back to a comma
+    #[arg(long, conflicts_with = "delimiter")]
+    pub detect_delimiter: bool,
+
+    /// Whether the delimiter was given on the command line or in a config
+    /// file, rather than left as the default. Set by
+    /// [`Args::parse_with_config_file`].
+    #[arg(skip)]
+    pub delimiter_given: bool,
+
+    /// Fail if any field is larger than this many bytes, like a quote that's never closed swallowing the rest of the file
+    #[arg(long, value_name = "BYTES")]
+    pub max_field_bytes: Option<usize>,
+
+    /// Remove byte order marks from anywhere in the input, like those left in the middle by joining files together
+    #[arg(long)]
+    pub strip_all_bom: bool,
+
+    /// Treat each line of the input as a single cell, without splitting it into columns
+    #[arg(long)]
+    pub raw_lines: bool,
+
+    /// Treat runs of the delimiter as a single separator, for columns lined up with spaces. Quotes aren't handled
+    #[arg(long, conflicts_with = "raw_lines")]
+    pub collapse_delimiters: bool,
+
+    /// Treat the first row as data, not headers
+    #[arg(long)]
+    pub no_headers: bool,
+
+    /// With `--no-headers`, add a header row naming the columns `Column 1`, `Column 2` and so on
+    #[arg(long, requires = "no_headers")]
+    pub synthetic_headers: bool,
+
+    /// Decide whether the first row is a header from whether its cells are all different, non-empty and not numbers
+    #[arg(long, conflicts_with = "no_headers")]
+    pub detect_header: bool,
+
+    /// With `--detect-header`, print whether the first row was taken as a header to stderr
+    #[arg(long, requires = "detect_header")]
+    pub explain: bool,
+
+    /// The zero-based row holding the header, not counting blank lines, leaving out any rows before it
+    #[arg(long, value_name = "N", conflicts_with = "no_headers")]
+    pub header_row: Option<usize>,
+
+    /// Merge this many rows at the start of the input into a single header row
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 1,
+        conflicts_with = "no_headers"
+    )]
+    pub header_rows: usize,
+
+    /// Enable streaming mode to improve performance for large inputs
+    #[arg(long)]
+    pub stream: bool,
+
+    /// Customise the alignment of values within cells
+    #[arg(long, default_value = "left")]
+    pub align: ClapAlignment,
+
+    /// Make the text of the header cells bold
+    #[arg(long, conflicts_with = "no_headers")]
+    pub bold_headers: bool,
+
+    /// Make the text of the first cell of each data row bold, keeping the first column left-aligned
+    #[arg(long)]
+    pub bold_first_column: bool,
+
+    /// Output the smallest valid table, without padding, extra dashes or a trailing newline
+    #[arg(long)]
+    pub minify: bool,
+
+    /// Read the row after the header as alignment markers for each column, like `:-,:-:,-:`, instead of as data
+    #[arg(long, conflicts_with = "no_headers")]
+    pub alignment_row: bool,
+
+    /// The zero-based row holding a type for each column, like `int` or `string`, which right-aligns numbers and dates instead of being output as data
+    #[arg(long, value_name = "N", conflicts_with = "alignment_row")]
+    pub types_row: Option<usize>,
+
+    /// Right-align columns where every value is a number
+    #[arg(long)]
+    pub auto_align_numeric: bool,
+
+    /// In columns of numbers, group the digits into thousands with this separator, like `1,234,567`
+    #[arg(long, value_name = "CHAR")]
+    pub thousands_separator: Option<char>,
+
+    /// Format the numbers in a zero-based column with a pattern, like `1:0.00` or `2:#,##0` (repeatable)
+    #[arg(long, value_name = "COLUMN:PATTERN", value_parser = parse_column_number_format)]
+    pub number_format: Vec<(usize, NumberFormat)>,
+
+    /// Follow the numbers in these zero-based columns with a bar showing where they fall between the column's smallest and largest
+    #[arg(long, value_delimiter = ',', value_name = "COLUMNS")]
+    pub sparkline_columns: Vec<usize>,
+
+    /// Reformat columns of dates with a strftime pattern, like `%Y-%m-%d`, reading `01/02/2024` day first
+    #[cfg(feature = "dates")]
+    #[arg(long, value_name = "PATTERN")]
+    pub date_format: Option<String>,
+
+    /// Only include the columns with these header names, in the given order
+    #[arg(long, value_delimiter = ',')]
+    pub columns: Option<Vec<String>>,
+
+    /// When a selected column name appears more than once, only include the first match
+    #[arg(long, requires = "columns")]
+    pub first_match_only: bool,
+
+    /// Only include the columns whose header name matches this regular expression, like `^metric_`
+    #[cfg(feature = "regex")]
+    #[arg(long, value_name = "REGEX", conflicts_with = "columns")]
+    pub columns_regex: Option<String>,
+
+    /// Rename a header cell, like `usr_id=User ID` (repeatable)
+    #[arg(long, value_name = "OLD=NEW", value_parser = parse_key_value)]
+    pub rename: Vec<(String, String)>,
+
+    /// Trim whitespace from the start and end of each cell
+    #[arg(long, value_enum, value_name = "MODE", default_value = "none")]
+    pub trim_mode: ClapTrimMode,
+
+    /// Escape a `>`, `#`, `-`, `+` or `1.` at the start of a cell so it isn't read as a blockquote, heading or list
+    #[arg(long)]
+    pub escape_block_markers: bool,
+
+    /// How to escape pipe characters inside cells; `entity` works in renderers that don't honour `\|`
+    #[arg(long, value_enum, value_name = "STYLE", default_value = "backslash")]
+    pub pipe_escape: ClapPipeEscape,
+
+    /// Write cells with this value, like `\N`, `NULL` or `NA`, as empty cells (repeatable)
+    #[arg(long, value_name = "VALUE")]
+    pub null_string: Vec<String>,
+
+    /// Match `--null-string` values ignoring case
+    #[arg(long, requires = "null_string")]
+    pub null_case_insensitive: bool,
+
+    /// In HTML output, give empty and null cells a class, like `<td class="empty"></td>`, so they can be styled
+    #[arg(long)]
+    pub mark_empty_cells: bool,
+
+    /// Expand tab characters inside cells to this many spaces
+    #[arg(long, value_name = "WIDTH")]
+    pub tab_width: Option<usize>,
+
+    /// Only include data rows matching a filter, like `status==active`, `2!=0` or `name contains Smith`
+    #[arg(long)]
+    pub filter: Option<RowFilter>,
+
+    /// Move data rows whose first cell is this value, like `Total`, to the bottom of the table (repeatable)
+    #[arg(long, value_name = "VALUE")]
+    pub pin_to_bottom: Vec<String>,
+
+    /// Only output the first HEAD and last TAIL data rows, with a row of `…` between them when rows are left out
+    #[arg(long, value_name = "HEAD,TAIL", value_parser = parse_preview)]
+    pub preview: Option<(usize, usize)>,
+
+    /// Pad the table with empty columns so it has at least this many
+    #[arg(long, value_name = "N")]
+    pub min_columns: Option<usize>,
+
+    /// Add the empty cells of short rows at the start instead of the end, like `|  |  | X | Y |`
+    #[arg(long)]
+    pub pad_start: bool,
+
+    /// Add a column numbering the rows
+    #[arg(long)]
+    pub row_numbers: bool,
+
+    /// The number of the first row when using `--row-numbers`
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 1,
+        allow_negative_numbers = true,
+        requires = "row_numbers"
+    )]
+    pub row_numbers_start: i64,
+
+    /// Pad row numbers with leading zeros to the width of the largest, like `001`
+    #[arg(long, requires = "row_numbers")]
+    pub zero_pad_row_numbers: bool,
+
+    /// Split the output into tables of at most this many rows, each with the header
+    #[arg(long, value_name = "N", conflicts_with = "column_tables")]
+    pub paginate: Option<usize>,
+
+    /// The line written between tables when using `--paginate`
+    #[arg(
+        long,
+        value_name = "TEXT",
+        default_value = "---",
+        requires = "paginate"
+    )]
+    pub page_divider: String,
+
+    /// Add a final row summarising each numeric column
+    #[arg(long, value_enum, value_name = "KIND", conflicts_with_all = ["column_tables", "group_by"])]
+    pub summary: Option<ClapSummary>,
+
+    /// Render booleans in these zero-based columns as checkboxes, like `[x]` and `[ ]`
+    #[arg(long, value_delimiter = ',', value_name = "COLUMNS")]
+    pub checkbox_columns: Option<Vec<usize>>,
+
+    /// Add a leading column of checkboxes, like `[x]` and `[ ]`, from the booleans in this zero-based column
+    #[arg(long, value_name = "COLUMN")]
+    pub checklist_from: Option<usize>,
+
+    /// Leave the `--checklist-from` column itself out of the table
+    #[arg(long, requires = "checklist_from")]
+    pub checklist_hide_source: bool,
+
+    /// Output a separate `Row | Value` table for each column, under a heading
+    #[arg(long)]
+    pub column_tables: bool,
+
+    /// Output a `Column | Inferred Type` table describing each column, instead of the data
+    #[arg(long, conflicts_with_all = ["column_tables", "group_by"])]
+    pub schema_table: bool,
+
+    /// Output a separate table for each distinct value in this column, given by header name or zero-based index
+    #[arg(long, value_name = "COLUMN", conflicts_with = "column_tables")]
+    pub group_by: Option<ColumnRef>,
+
+    /// Start the output with a list of links to each group's table
+    #[arg(long, requires = "group_by")]
+    pub toc: bool,
+
+    /// Output a separate table for each input file, under a heading with the file's name
+    #[arg(long)]
+    pub separate_tables: bool,
+
+    /// With `--separate-tables`, convert up to this many files at once
+    #[cfg(feature = "rayon")]
+    #[arg(long, value_name = "N", requires = "separate_tables")]
+    pub jobs: Option<std::num::NonZeroUsize>,
+
+    /// Output the rows added, removed or changed between two files, with a status column of `+`, `-` or `~`
+    #[arg(
+        long,
+        num_args = 2,
+        value_names = ["OLD", "NEW"],
+        conflicts_with_all = ["files", "separate_tables", "stream", "format"]
+    )]
+    pub diff: Option<Vec<PathBuf>>,
+
+    /// With `--diff`, match rows by the cell in this zero-based column, showing changed cells like `old → new`, instead of by the whole row
+    #[arg(long, value_name = "COLUMN", requires = "diff")]
+    pub diff_key: Option<usize>,
+
+    /// Move cells longer than this many characters into footnotes below the table
+    #[arg(long, value_name = "LENGTH")]
+    pub footnote_over: Option<usize>,
+
+    /// Output this message instead of a table when the input is empty, like `_No data_`
+    #[arg(long, value_name = "MESSAGE")]
+    pub empty_message: Option<String>,
+
+    /// Wrap the output in a callout block, like `> [!NOTE]`
+    #[arg(long, value_enum, value_name = "KIND")]
+    pub callout: Option<ClapCallout>,
+
+    /// Add a YAML front matter block with this key, like `title="My Data"` (repeatable)
+    #[arg(long, value_name = "KEY=VALUE", value_parser = parse_key_value)]
+    pub front_matter: Vec<(String, String)>,
+
+    /// Add a caption to the table, in bold above it or as a `<caption>` in HTML
+    #[arg(long, value_name = "TEXT", visible_alias = "title")]
+    pub caption: Option<String>,
+
+    /// Add a line after the table saying which file it was generated from and when, like `_Generated from data.csv on 2024-01-01T12:00:00Z_`
+    #[arg(long)]
+    pub source_footer: bool,
+
+    /// Print the time taken and peak memory usage to stderr after converting
+    #[arg(long)]
+    pub measure: bool,
+
+    /// Turn off everything that depends on whether stdin, stdout or stderr is a terminal, like the waiting message, delimiter suggestions and `--tty-row-limit`, so the output is the same everywhere
+    #[arg(long, env = "CSVMD_PLAIN", value_parser = BoolishValueParser::new())]
+    pub plain: bool,
+
+    /// The format to output, instead of a Markdown table. Without it, the format is chosen from the `--output` file's extension, like `.html` or `.json`
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub format: Option<ClapFormat>,
+
+    /// Check the output follows the rules for GitHub Flavored Markdown tables, and fail if it doesn't
+    #[arg(long)]
+    pub strict_gfm: bool,
+
+    /// Pad cells to the width of their column, so the columns line up in the raw Markdown
+    #[arg(long)]
+    pub pretty: bool,
+
+    /// With `--pretty`, right-justify numbers within their padding
+    #[arg(long, requires = "pretty")]
+    pub numeric_pad_right: bool,
+
+    /// Fit tables within this many columns, cutting long cells short with `…`
+    #[arg(long, value_name = "COLUMNS")]
+    pub max_table_width: Option<usize>,
+
+    /// Write the output to this file instead of stdout, replacing it once the conversion has succeeded
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// Add the output to the end of the `--output` file, after a blank line, instead of overwriting it
+    #[arg(long, requires = "output")]
+    pub append: bool,
+
+    /// Fail instead of writing more than this many data rows to a terminal
+    #[arg(long, value_name = "ROWS", default_value_t = 1000)]
+    pub tty_row_limit: usize,
+
+    /// Write to a terminal however many rows there are, ignoring `--tty-row-limit`
+    #[arg(long)]
+    pub force: bool,
+
+    /// Copy the output to the system clipboard instead of writing it to stdout
+    #[cfg(feature = "clipboard")]
+    #[arg(long, conflicts_with = "output")]
+    pub clipboard: bool,
+
+    /// Read default options from this TOML file, instead of `csvmd.toml` in the current directory, with the long options as keys, like `bold-headers = true`
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Print the configuration built from the other arguments to stderr, then exit without reading any input
+    #[arg(long)]
+    pub pretty_print_config: bool,
+
+    /// Benchmark the conversion modes on synthetic data instead of converting input
+    #[arg(long, hide = true)]
+    pub self_benchmark: bool,
+}
+
+impl Args {
+    /// Parse the command line, using the options in a config file for any
+    /// which aren't given. See [`ConfigFile::discover`] for where the file is
+    /// found.
+    ///
+    /// Like [`Parser::parse`], this exits with a usage message if the command
+    /// line is invalid.
+    pub fn parse_with_config_file() -> crate::error::Result<Self> {
+        let command_line: Vec<OsString> = std::env::args_os().collect();
+        let args = Self::from_matches(&Self::command().get_matches_from(&command_line));
+
+        match ConfigFile::discover(args.config.as_deref())? {
+            Some(file) => Self::parse_with_defaults(&command_line, &file),
+            None => Ok(args),
+        }
+    }
+
+    /// Parse `command_line`, using the options in `file` for any which it
+    /// doesn't give.
+    ///
+    /// Like [`Parser::parse`], this exits with a usage message if the command
+    /// line is invalid.
+    pub fn parse_with_defaults(
+        command_line: &[OsString],
+        file: &ConfigFile,
+    ) -> crate::error::Result<Self> {
+        let command = Self::command();
+        let matches = command.clone().get_matches_from(command_line);
+
+        // The options from the file go in front of the ones given, so they're
+        // parsed and checked just like them
+        let mut arguments = command_line[..1].to_vec();
+        for (key, value) in &file.options {
+            let arg = command
+                .get_arguments()
+                .find(|arg| arg.get_long() == Some(key.as_str()))
+                .filter(|arg| !["help", "version", "config"].contains(&arg.get_id().as_str()))
+                .ok_or_else(|| {
+                    CsvMdError::InvalidConfig(format!(
+                        "unknown option `{}` in config file; keys are the long command-line options, like `bold-headers`",
+                        key
+                    ))
+                })?;
+            // Leave out options given on the command line, or conflicting with
+            // one that was, like `delimiter` with `--detect-delimiter`
+            let given = |arg: &Arg| {
+                matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+            };
+            let conflicts = |other: &Arg| {
+                command.get_arg_conflicts_with(arg).contains(&other)
+                    || command.get_arg_conflicts_with(other).contains(&arg)
+            };
+            if !command
+                .get_arguments()
+                .any(|other| given(other) && (other == arg || conflicts(other)))
+            {
+                arguments.extend(config_arguments(arg, value)?);
+            }
+        }
+        arguments.extend_from_slice(&command_line[1..]);
+
+        let matches = command.try_get_matches_from(arguments).map_err(|err| {
+            // Name the key when the error is about a value from the file
+            let key = match err.get(ContextKind::InvalidArg) {
+                Some(ContextValue::String(arg)) => arg
+                    .trim_start_matches('-')
+                    .split([' ', '='])
+                    .next()
+                    .filter(|key| file.options.contains_key(*key)),
+                _ => None,
+            };
+            CsvMdError::InvalidConfig(match key {
+                Some(key) => format!("invalid `{}` in config file: {}", key, clap_message(&err)),
+                None => format!("invalid config file: {}", clap_message(&err)),
+            })
+        })?;
+        Ok(Self::from_matches(&matches))
+    }
+
+    /// Build the arguments from parsed `matches`, exiting with a usage message
+    /// if they're invalid.
+    fn from_matches(matches: &ArgMatches) -> Self {
+        let mut args = Self::from_arg_matches(matches).unwrap_or_else(|err| err.exit());
+        args.delimiter_given = matches.value_source("delimiter") == Some(ValueSource::CommandLine);
+        args
+    }
+
+    /// The paths of the files to read, in order, with `None` meaning stdin.
+    ///
+    /// Without any files, this reads from stdin. Following the usual
+    /// convention, a path of `-` also means stdin.
+    pub fn input_files(&self) -> Vec<Option<&Path>> {
+        if self.files.is_empty() {
+            return vec![None];
+        }
+
+        self.files
+            .iter()
+            .map(|path| Some(path.as_path()).filter(|path| path.as_os_str() != "-"))
+            .collect()
+    }
+}
+
+/// Default options read from a TOML config file, like `csvmd.toml`.
+///
+/// Keys are the long command-line options without their leading `--`, and
+/// options given on the command line take precedence. Flags are set with
+/// `true`, and options which can be given more than once take an array:
+///
+/// ```toml
+/// delimiter = "tab"
+/// align = "center"
+/// bold-headers = true
+/// columns = ["Name", "Age"]
+/// ```
+#[derive(Debug, Default)]
+pub struct ConfigFile {
+    options: toml::Table,
+}
+
+/// The command-line arguments for `value`, given for `arg` in a config file.
+fn config_arguments(arg: &Arg, value: &toml::Value) -> crate::error::Result<Vec<OsString>> {
+    let key = arg.get_long().unwrap_or_default();
+    let invalid = |err: String| {
+        CsvMdError::InvalidConfig(format!("invalid `{}` in config file: {}", key, err))
+    };
+    let flag = format!("--{}", key);
+
+    if !arg.get_action().takes_values() {
+        return match value {
+            toml::Value::Boolean(true) => Ok(vec![flag.into()]),
+            toml::Value::Boolean(false) => Ok(Vec::new()),
+            _ => Err(invalid("expected `true` or `false`".to_string())),
+        };
+    }
+
+    let values = match value {
+        toml::Value::Array(values) => values.iter().collect(),
+        value => vec![value],
+    };
+    let mut strings = Vec::with_capacity(values.len());
+    for value in values {
+        let string = match value {
+            toml::Value::String(string) => string.clone(),
+            toml::Value::Integer(number) => number.to_string(),
+            toml::Value::Float(number) => number.to_string(),
+            toml::Value::Boolean(boolean) => boolean.to_string(),
+            _ => return Err(invalid("expected a string, number or array".to_string())),
+        };
+        strings.push(string);
+    }
+
+    // Options taking several values at once, like `--diff OLD NEW`, take
+    // them together, and others are repeated for each value
+    let takes_several = arg
+        .get_num_args()
+        .is_some_and(|range| range.max_values() > 1);
+    if takes_several {
+        Ok(std::iter::once(flag)
+            .chain(strings)
+            .map(OsString::from)
+            .collect())
+    } else {
+        Ok(strings
+            .into_iter()
+            .map(|string| format!("{}={}", flag, string).into())
+            .collect())
+    }
+}
+
+/// The message of a clap error on one line, without its `error: ` prefix or
+/// usage.
+fn clap_message(err: &clap::Error) -> String {
+    let rendered = err.to_string();
+    let lines: Vec<&str> = rendered
+        .lines()
+        .map_while(|line| Some(line.trim()).filter(|line| !line.is_empty()))
+        .collect();
+    let message = lines.join(" ");
+    message
+        .strip_prefix("error: ")
+        .unwrap_or(&message)
+        .to_string()
+}
+
+impl ConfigFile {
+    /// The name of the config file looked for in the current directory.
+    pub const FILE_NAME: &'static str = "csvmd.toml";
+
+    /// Read a config file.
+    pub fn read(path: &Path) -> crate::error::Result<Self> {
+        std::fs::read_to_string(path)?
+            .parse()
+            .map_err(|err| match err {
+                CsvMdError::InvalidConfig(message) => {
+                    CsvMdError::InvalidConfig(format!("{}: {}", path.display(), message))
+                }
+                err => err,
+            })
+    }
+
+    /// Read the config file at `path` if one is given, or otherwise
+    /// `csvmd.toml` in the current directory if it exists.
+    pub fn discover(path: Option<&Path>) -> crate::error::Result<Option<Self>> {
+        match path {
+            Some(path) => Self::read(path).map(Some),
+            None if Path::new(Self::FILE_NAME).is_file() => {
+                Self::read(Path::new(Self::FILE_NAME)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl FromStr for ConfigFile {
+    type Err = CsvMdError;
+
+    fn from_str(s: &str) -> crate::error::Result<Self> {
+        let options = toml::from_str(s)
+            .map_err(|err| CsvMdError::InvalidConfig(err.message().to_string()))?;
+        Ok(Self { options })
+    }
+}
+
+/// Lay out `--number-format` arguments by column, with later arguments for the
+/// same column taking precedence.
+fn column_number_format(formats: &[(usize, NumberFormat)]) -> Vec<Option<NumberFormat>> {
+    let mut by_column = Vec::new();
+    for &(column, format) in formats {
+        if by_column.len() <= column {
+            by_column.resize(column + 1, None);
+        }
+        by_column[column] = Some(format);
+    }
+    by_column
+}
+
+impl Config {
+    /// Build a conversion configuration from parsed command-line arguments.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use clap::Parser;
+    /// use csvmd::cli::Args;
+    /// use csvmd::{Config, HeaderAlignment};
+    ///
+    /// let args = Args::parse_from(["csvmd", "--delimiter", ";", "--align", "centre"]);
+    /// let config = Config::from_args(&args);
+    /// assert_eq!(config.delimiter, b';');
+    /// assert_eq!(config.header_alignment, HeaderAlignment::Center);
+    /// ```
+    pub fn from_args(args: &Args) -> Self {
+        Self {
+            has_headers: !args.no_headers,
+            detect_header: args.detect_header,
+            synthetic_headers: args.synthetic_headers,
+            minify: args.minify,
+            detect_markdown: args.detect_markdown,
+            flexible: true,
+            header_rows: args.header_rows,
+            header_row_index: args.header_row,
+            delimiter: args.delimiter,
+            detect_delimiter: args.detect_delimiter,
+            honor_sep_directive: !args.delimiter_given,
+            max_field_bytes: args.max_field_bytes,
+            strip_all_bom: args.strip_all_bom,
+            single_column_mode: args.raw_lines,
+            collapse_delimiters: args.collapse_delimiters,
+            header_alignment: args.align.into(),
+            bold_headers: args.bold_headers,
+            bold_first_column: args.bold_first_column,
+            alignment_row: args.alignment_row,
+            types_row: args.types_row,
+            auto_align_numeric: args.auto_align_numeric,
+            thousands_separator: args.thousands_separator,
+            column_number_format: column_number_format(&args.number_format),
+            sparkline_columns: args.sparkline_columns.clone(),
+            #[cfg(feature = "dates")]
+            date_format: args.date_format.clone(),
+            columns: args.columns.clone(),
+            first_match_only: args.first_match_only,
+            #[cfg(feature = "regex")]
+            select_columns_regex: args.columns_regex.clone(),
+            rename_headers: args.rename.iter().cloned().collect(),
+            trim_mode: args.trim_mode.into(),
+            null_values: args.null_string.clone(),
+            null_case_insensitive: args.null_case_insensitive,
+            mark_empty_cells: args.mark_empty_cells,
+            tab_width: args.tab_width,
+            escape_block_markers: args.escape_block_markers,
+            pipe_escape: args.pipe_escape.into(),
+            filter: args.filter.clone(),
+            pin_rows_to_bottom: args.pin_to_bottom.clone(),
+            preview: args.preview,
+            min_columns: args.min_columns,
+            pad_side: if args.pad_start {
+                PadSide::Start
+            } else {
+                PadSide::End
+            },
+            row_numbers: args.row_numbers,
+            row_number_start: args.row_numbers_start,
+            zero_pad_row_numbers: args.zero_pad_row_numbers,
+            rows_per_table: args.paginate,
+            summary_row: args.summary.map(SummaryKind::from),
+            page_divider: args.page_divider.clone(),
+            boolean_columns: args.checkbox_columns.clone().unwrap_or_default(),
+            boolean_as_checkbox: args.checkbox_columns.is_some(),
+            checklist_from: args.checklist_from,
+            checklist_hide_source: args.checklist_hide_source,
+            column_tables: args.column_tables,
+            schema_table: args.schema_table,
+            group_by: args.group_by.clone(),
+            toc: args.toc,
+            footnote_over: args.footnote_over,
+            empty_input_message: args.empty_message.clone(),
+            callout: args.callout.map(CalloutKind::from),
+            source_footer: args.source_footer,
+            validate_output: args.strict_gfm,
+            pretty: args.pretty,
+            numeric_pad_right: args.numeric_pad_right,
+            max_table_width: args.max_table_width,
+            front_matter: (!args.front_matter.is_empty())
+                .then(|| args.front_matter.iter().cloned().collect()),
+            caption: args.caption.clone(),
+            ..Config::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_from(args: &[&str]) -> Config {
+        let args = Args::parse_from(std::iter::once("csvmd").chain(args.iter().copied()));
+        Config::from_args(&args)
+    }
+
+    fn config_with_file(args: &[&str], file: &str) -> crate::error::Result<Config> {
+        let command_line: Vec<OsString> = std::iter::once("csvmd")
+            .chain(args.iter().copied())
+            .map(OsString::from)
+            .collect();
+        let args = Args::parse_with_defaults(&command_line, &file.parse()?)?;
+        Ok(Config::from_args(&args))
+    }
+
+    #[test]
+    fn test_defaults_match_config_default() {
+        let config = config_from(&[]);
+        let default = Config::default();
+
+        assert_eq!(config.has_headers, default.has_headers);
+        assert_eq!(config.flexible, default.flexible);
+        assert_eq!(config.delimiter, default.delimiter);
+        assert_eq!(config.header_alignment, default.header_alignment);
+    }
+
+    #[test]
+    fn test_no_headers_is_inverted() {
+        assert!(!config_from(&["--no-headers"]).has_headers);
+    }
+
+    #[test]
+    fn test_british_spelling_alignment() {
+        let config = config_from(&["--align", "centre"]);
+        assert_eq!(config.header_alignment, HeaderAlignment::Center);
+
+        let config = config_from(&["--align", "center"]);
+        assert_eq!(config.header_alignment, HeaderAlignment::Center);
+    }
+
+    #[test]
+    fn test_delimiter_byte_cast() {
+        assert_eq!(config_from(&["-d", ";"]).delimiter, b';');
+        assert_eq!(config_from(&["--delimiter", "\t"]).delimiter, b'\t');
+        assert_eq!(config_from(&["--delimiter", "|"]).delimiter, b'|');
+    }
+
+    #[test]
+    fn test_named_delimiters() {
+        assert_eq!(config_from(&["--delimiter", "tab"]).delimiter, b'\t');
+        assert_eq!(config_from(&["--delimiter", "semicolon"]).delimiter, b';');
+        assert_eq!(config_from(&["--delimiter", "PIPE"]).delimiter, b'|');
+        assert_eq!(config_from(&["--delimiter", "comma"]).delimiter, b',');
+        assert_eq!(config_from(&["--delimiter", "space"]).delimiter, b' ');
+    }
+
+    #[test]
+    fn test_invalid_delimiters() {
+        for delimiter in ["tabs", ";;", "é", ""] {
+            let result = Args::try_parse_from(["csvmd", "--delimiter", delimiter]);
+            assert!(result.is_err(), "{:?} should be rejected", delimiter);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn test_columns_regex() {
+        let config = config_from(&["--columns-regex", "^metric_"]);
+        assert_eq!(config.select_columns_regex.as_deref(), Some("^metric_"));
+        assert_eq!(config_from(&[]).select_columns_regex, None);
+    }
+
+    #[test]
+    fn test_schema_table() {
+        assert!(config_from(&["--schema-table"]).schema_table);
+        assert!(!config_from(&[]).schema_table);
+        assert!(Args::try_parse_from(["csvmd", "--schema-table", "--column-tables"]).is_err());
+    }
+
+    #[test]
+    fn test_pin_to_bottom() {
+        let config = config_from(&["--pin-to-bottom", "Total", "--pin-to-bottom", "Mean"]);
+        assert_eq!(config.pin_rows_to_bottom, ["Total", "Mean"]);
+        assert!(config_from(&[]).pin_rows_to_bottom.is_empty());
+    }
+
+    #[test]
+    fn test_max_field_bytes() {
+        let config = config_from(&["--max-field-bytes", "1048576"]);
+        assert_eq!(config.max_field_bytes, Some(1_048_576));
+        assert_eq!(config_from(&[]).max_field_bytes, None);
+    }
+
+    #[test]
+    fn test_strip_all_bom() {
+        assert!(config_from(&["--strip-all-bom"]).strip_all_bom);
+        assert!(!config_from(&[]).strip_all_bom);
+    }
+
+    #[test]
+    fn test_rename() {
+        let config = config_from(&["--rename", "usr_id=User ID", "--rename", "n=Name"]);
+        assert_eq!(config.rename_headers.len(), 2);
+        assert_eq!(config.rename_headers["usr_id"], "User ID");
+        assert!(config_from(&[]).rename_headers.is_empty());
+        assert!(Args::try_parse_from(["csvmd", "--rename", "usr_id"]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "dates")]
+    fn test_date_format() {
+        let config = config_from(&["--date-format", "%Y-%m-%d"]);
+        assert_eq!(config.date_format.as_deref(), Some("%Y-%m-%d"));
+        assert_eq!(config_from(&[]).date_format, None);
+    }
+
+    #[test]
+    fn test_types_row() {
+        assert_eq!(config_from(&["--types-row", "1"]).types_row, Some(1));
+        assert_eq!(config_from(&[]).types_row, None);
+        assert!(Args::try_parse_from(["csvmd", "--types-row", "1", "--alignment-row"]).is_err());
+    }
+
+    #[test]
+    fn test_alignment_row() {
+        assert!(config_from(&["--alignment-row"]).alignment_row);
+        assert!(!config_from(&[]).alignment_row);
+        assert!(Args::try_parse_from(["csvmd", "--alignment-row", "--no-headers"]).is_err());
+    }
+
+    #[test]
+    fn test_group_by() {
+        let config = config_from(&["--group-by", "Team", "--toc"]);
+        assert_eq!(config.group_by, Some(ColumnRef::Name("Team".to_string())));
+        assert!(config.toc);
+        assert_eq!(
+            config_from(&["--group-by", "2"]).group_by,
+            Some(ColumnRef::Index(2))
+        );
+        assert!(Args::try_parse_from(["csvmd", "--toc"]).is_err());
+    }
+
+    #[test]
+    fn test_numeric_formatting() {
+        let config = config_from(&["--auto-align-numeric", "--thousands-separator", "_"]);
+        assert!(config.auto_align_numeric);
+        assert_eq!(config.thousands_separator, Some('_'));
+        assert_eq!(config_from(&[]).thousands_separator, None);
+    }
+
+    #[test]
+    fn test_bold_headers() {
+        assert!(config_from(&["--bold-headers"]).bold_headers);
+        assert!(!config_from(&[]).bold_headers);
+    }
+
+    #[test]
+    fn test_bold_first_column() {
+        assert!(config_from(&["--bold-first-column"]).bold_first_column);
+        assert!(!config_from(&[]).bold_first_column);
+    }
+
+    #[test]
+    fn test_row_numbers() {
+        let config = config_from(&["--row-numbers", "--row-numbers-start", "0"]);
+        assert!(config.row_numbers);
+        assert_eq!(config.row_number_start, 0);
+        let config = config_from(&["--row-numbers", "--row-numbers-start", "-5"]);
+        assert_eq!(config.row_number_start, -5);
+        assert_eq!(config_from(&[]).row_number_start, 1);
+    }
+
+    #[test]
+    fn test_raw_lines() {
+        assert!(config_from(&["--raw-lines"]).single_column_mode);
+        assert!(!config_from(&[]).single_column_mode);
+    }
+
+    #[test]
+    fn test_diff() {
+        let args =
+            Args::try_parse_from(["csvmd", "--diff", "old.csv", "new.csv", "--diff-key", "0"])
+                .unwrap();
+        assert_eq!(
+            args.diff,
+            Some(vec![PathBuf::from("old.csv"), PathBuf::from("new.csv")])
+        );
+        assert_eq!(args.diff_key, Some(0));
+
+        assert!(Args::try_parse_from(["csvmd", "--diff", "old.csv"]).is_err());
+        assert!(Args::try_parse_from(["csvmd", "--diff-key", "0"]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn test_jobs() {
+        let args = Args::try_parse_from([
+            "csvmd",
+            "--separate-tables",
+            "--jobs",
+            "2",
+            "a.csv",
+            "b.csv",
+        ])
+        .unwrap();
+        assert_eq!(args.jobs.map(|jobs| jobs.get()), Some(2));
+
+        assert!(Args::try_parse_from(["csvmd", "--jobs", "2", "a.csv"]).is_err());
+        assert!(Args::try_parse_from(["csvmd", "--separate-tables", "--jobs", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_zero_pad_row_numbers() {
+        assert!(config_from(&["--row-numbers", "--zero-pad-row-numbers"]).zero_pad_row_numbers);
+        assert!(!config_from(&["--row-numbers"]).zero_pad_row_numbers);
+        assert!(Args::try_parse_from(["csvmd", "--zero-pad-row-numbers"]).is_err());
+    }
+
+    #[test]
+    fn test_caption() {
+        let config = config_from(&["--caption", "Sales"]);
+        assert_eq!(config.caption.as_deref(), Some("Sales"));
+        let config = config_from(&["--title", "Sales"]);
+        assert_eq!(config.caption.as_deref(), Some("Sales"));
+        assert_eq!(config_from(&[]).caption, None);
+    }
+
+    #[test]
+    fn test_detect_header() {
+        assert!(config_from(&["--detect-header"]).detect_header);
+        assert!(!config_from(&[]).detect_header);
+
+        let args = Args::try_parse_from(["csvmd", "--detect-header", "--explain"]).unwrap();
+        assert!(args.explain);
+        assert!(Args::try_parse_from(["csvmd", "--explain"]).is_err());
+        assert!(Args::try_parse_from(["csvmd", "--detect-header", "--no-headers"]).is_err());
+    }
+
+    #[test]
+    fn test_collapse_delimiters() {
+        let config = config_from(&["-d", " ", "--collapse-delimiters"]);
+        assert!(config.collapse_delimiters);
+        assert_eq!(config.delimiter, b' ');
+        assert!(!config_from(&[]).collapse_delimiters);
+    }
+
+    #[test]
+    fn test_sparkline_columns() {
+        let config = config_from(&["--sparkline-columns", "1,3"]);
+        assert_eq!(config.sparkline_columns, vec![1, 3]);
+        assert!(config_from(&[]).sparkline_columns.is_empty());
+    }
+
+    #[test]
+    fn test_number_format() {
+        let config = config_from(&["--number-format", "1:0.00", "--number-format", "3:#,##0"]);
+        assert_eq!(
+            config.column_number_format,
+            vec![
+                None,
+                Some(NumberFormat {
+                    decimals: 2,
+                    thousands_separator: None
+                }),
+                None,
+                Some(NumberFormat {
+                    decimals: 0,
+                    thousands_separator: Some(',')
+                }),
+            ]
+        );
+        assert!(config_from(&[]).column_number_format.is_empty());
+
+        for invalid in ["0.00", "a:0.00", "1:abc"] {
+            assert!(Args::try_parse_from(["csvmd", "--number-format", invalid]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_summary() {
+        let config = config_from(&["--summary", "sum"]);
+        assert_eq!(config.summary_row, Some(SummaryKind::Sum));
+        assert_eq!(config_from(&[]).summary_row, None);
+    }
+
+    #[test]
+    fn test_format() {
+        let args = Args::try_parse_from(["csvmd", "--format", "jsonl"]).unwrap();
+        assert_eq!(
+            args.format.map(OutputFormat::from),
+            Some(OutputFormat::JsonLines)
+        );
+        let args = Args::try_parse_from(["csvmd", "--format", "org"]).unwrap();
+        assert_eq!(
+            args.format.map(OutputFormat::from),
+            Some(OutputFormat::OrgMode)
+        );
+        let args = Args::try_parse_from(["csvmd", "--format", "mermaid"]).unwrap();
+        assert_eq!(
+            args.format.map(OutputFormat::from),
+            Some(OutputFormat::Mermaid)
+        );
+        assert!(Args::try_parse_from(["csvmd"]).unwrap().format.is_none());
+    }
+
+    #[test]
+    fn test_paginate() {
+        let config = config_from(&["--paginate", "3"]);
+        assert_eq!(config.rows_per_table, Some(3));
+        assert_eq!(config.page_divider, "---");
+
+        let config = config_from(&["--paginate", "3", "--page-divider", "<!-- pagebreak -->"]);
+        assert_eq!(config.page_divider, "<!-- pagebreak -->");
+
+        assert_eq!(config_from(&[]).rows_per_table, None);
+    }
+
+    #[test]
+    fn test_escape_block_markers() {
+        assert!(config_from(&["--escape-block-markers"]).escape_block_markers);
+        assert!(!config_from(&[]).escape_block_markers);
+    }
+
+    #[test]
+    fn test_pipe_escape() {
+        let config = config_from(&["--pipe-escape", "entity"]);
+        assert_eq!(config.pipe_escape, PipeEscape::Entity);
+        assert_eq!(config_from(&[]).pipe_escape, PipeEscape::Backslash);
+    }
+
+    #[test]
+    fn test_pretty() {
+        let config = config_from(&["--pretty", "--numeric-pad-right"]);
+        assert!(config.pretty && config.numeric_pad_right);
+        assert!(!config_from(&[]).pretty);
+        assert!(Args::try_parse_from(["csvmd", "--numeric-pad-right"]).is_err());
+    }
+
+    #[test]
+    fn test_max_table_width() {
+        assert_eq!(
+            config_from(&["--max-table-width", "80"]).max_table_width,
+            Some(80)
+        );
+        assert_eq!(config_from(&[]).max_table_width, None);
+    }
+
+    #[test]
+    fn test_strict_gfm() {
+        assert!(config_from(&["--strict-gfm"]).validate_output);
+        assert!(!config_from(&[]).validate_output);
+    }
+
+    #[test]
+    fn test_null_string() {
+        let config = config_from(&["--null-string", "\\N", "--null-string", "NULL"]);
+        assert_eq!(config.null_values, ["\\N", "NULL"]);
+        assert!(!config.null_case_insensitive);
+
+        let config = config_from(&["--null-string", "NA", "--null-case-insensitive"]);
+        assert!(config.null_case_insensitive);
+        assert!(Args::try_parse_from(["csvmd", "--null-case-insensitive"]).is_err());
+    }
+
+    #[test]
+    fn test_mark_empty_cells() {
+        assert!(config_from(&["--format", "html", "--mark-empty-cells"]).mark_empty_cells);
+        assert!(!config_from(&[]).mark_empty_cells);
+    }
+
+    #[test]
+    fn test_trim_mode() {
+        let config = config_from(&["--trim-mode", "trailing"]);
+        assert_eq!(config.trim_mode, TrimMode::Trailing);
+        assert_eq!(config_from(&[]).trim_mode, TrimMode::None);
+    }
+
+    #[test]
+    fn test_callout() {
+        let config = config_from(&["--callout", "warning"]);
+        assert_eq!(config.callout, Some(CalloutKind::Warning));
+        assert_eq!(config_from(&[]).callout, None);
+    }
+
+    #[test]
+    fn test_source_footer() {
+        assert!(config_from(&["--source-footer"]).source_footer);
+        assert!(!config_from(&[]).source_footer);
+    }
+
+    #[test]
+    fn test_empty_message() {
+        let config = config_from(&["--empty-message", "_No data_"]);
+        assert_eq!(config.empty_input_message.as_deref(), Some("_No data_"));
+        assert_eq!(config_from(&[]).empty_input_message, None);
+    }
+
+    #[test]
+    fn test_config_file() {
+        let file = "delimiter = \"tab\"\nalign = \"center\"\nno-headers = true\nmin-columns = 3";
+
+        let config = config_with_file(&[], file).unwrap();
+        assert_eq!(config.delimiter, b'\t');
+        assert_eq!(config.header_alignment, HeaderAlignment::Center);
+        assert!(!config.has_headers);
+        assert_eq!(config.min_columns, Some(3));
+
+        // Options on the command line take precedence over the file
+        let config = config_with_file(&["--align", "right", "-d", ";"], file).unwrap();
+        assert_eq!(config.header_alignment, HeaderAlignment::Right);
+        assert_eq!(config.delimiter, b';');
+        assert!(!config.has_headers);
+    }
+
+    #[test]
+    fn test_config_file_takes_every_long_option() {
+        let file = "pretty = true\nrow-numbers = true\nrow-numbers-start = 5\n\
+                    columns = [\"Name\", \"Age\"]\npreview = \"1,1\"";
+
+        let config = config_with_file(&[], file).unwrap();
+        assert!(config.pretty);
+        assert!(config.row_numbers);
+        assert_eq!(config.row_number_start, 5);
+        assert_eq!(
+            config.columns,
+            Some(vec!["Name".to_string(), "Age".to_string()])
+        );
+        assert_eq!(config.preview, Some((1, 1)));
+
+        // A flag set to `false` is left as it is
+        assert!(!config_with_file(&[], "pretty = false").unwrap().pretty);
+    }
+
+    #[test]
+    fn test_config_file_skips_options_conflicting_with_command_line() {
+        let config = config_with_file(&["--detect-delimiter"], "delimiter = \"tab\"").unwrap();
+        assert!(config.detect_delimiter);
+        assert_eq!(config.delimiter, b',');
+    }
+
+    #[test]
+    fn test_config_file_defaults_are_not_overrides() {
+        // `--align left` matches the default, but was still given explicitly
+        let config = config_with_file(&["--align", "left"], "align = \"center\"").unwrap();
+        assert_eq!(config.header_alignment, HeaderAlignment::Left);
+
+        let config = config_with_file(&[], "").unwrap();
+        assert_eq!(config.header_alignment, HeaderAlignment::Left);
+    }
+
+    #[test]
+    fn test_invalid_config_file() {
+        for file in [
+            "align = \"sideways\"",
+            "delimiter = \"tabs\"",
+            "unknown = 1",
+            "align =",
+            "config = \"other.toml\"",
+            "pretty = \"yes\"",
+            "row-numbers-start = \"first\"",
+        ] {
+            let result = config_with_file(&[], file);
+            assert!(
+                matches!(result, Err(CsvMdError::InvalidConfig(_))),
+                "{:?} should be rejected",
+                file
+            );
+        }
+
+        let err = config_with_file(&[], "unknown = 1").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("unknown option `unknown` in config file"));
+        let err = config_with_file(&[], "row-numbers-start = \"first\"").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("invalid `row-numbers-start` in config file"));
+    }
+
+    #[test]
+    fn test_min_columns() {
+        assert_eq!(config_from(&["--min-columns", "5"]).min_columns, Some(5));
+        assert_eq!(config_from(&[]).min_columns, None);
+    }
+
+    #[test]
+    fn test_preview() {
+        assert_eq!(config_from(&["--preview", "3,2"]).preview, Some((3, 2)));
+        assert_eq!(config_from(&[]).preview, None);
+        assert!(Args::try_parse_from(["csvmd", "--preview", "3"]).is_err());
+        assert!(Args::try_parse_from(["csvmd", "--preview", "3,x"]).is_err());
+    }
+
+    #[test]
+    fn test_pad_start() {
+        assert_eq!(config_from(&["--pad-start"]).pad_side, PadSide::Start);
+        assert_eq!(config_from(&[]).pad_side, PadSide::End);
+    }
+
+    #[test]
+    fn test_checklist_from() {
+        let config = config_from(&["--checklist-from", "2"]);
+        assert_eq!(config.checklist_from, Some(2));
+        assert!(!config.checklist_hide_source);
+
+        let config = config_from(&["--checklist-from", "2", "--checklist-hide-source"]);
+        assert!(config.checklist_hide_source);
+        assert!(Args::try_parse_from(["csvmd", "--checklist-hide-source"]).is_err());
+    }
+
+    #[test]
+    fn test_checkbox_columns() {
+        let config = config_from(&["--checkbox-columns", "1,3"]);
+        assert_eq!(config.boolean_columns, vec![1, 3]);
+        assert!(config.boolean_as_checkbox);
+
+        assert!(!config_from(&[]).boolean_as_checkbox);
+    }
+
+    #[test]
+    fn test_front_matter() {
+        let config = config_from(&[
+            "--front-matter",
+            "title=My Data",
+            "--front-matter",
+            "layout=page=wide",
+        ]);
+        let front_matter = config.front_matter.unwrap();
+        assert_eq!(front_matter["title"], "My Data");
+        assert_eq!(front_matter["layout"], "page=wide");
+
+        assert!(config_from(&[]).front_matter.is_none());
+        assert!(Args::try_parse_from(["csvmd", "--front-matter", "novalue"]).is_err());
+    }
+
+    #[test]
+    fn test_minify() {
+        assert!(config_from(&["--minify"]).minify);
+        assert!(!config_from(&[]).minify);
+    }
+
+    #[test]
+    fn test_detect_delimiter() {
+        assert!(config_from(&["--detect-delimiter"]).detect_delimiter);
+        assert!(!config_from(&[]).detect_delimiter);
+
+        let result = Args::try_parse_from(["csvmd", "--detect-delimiter", "-d", ";"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_plain() {
+        assert!(Args::parse_from(["csvmd", "--plain"]).plain);
+    }
+
+    #[test]
+    fn test_honor_sep_directive() {
+        assert!(config_from(&[]).honor_sep_directive);
+
+        let mut args = Args::parse_from(["csvmd", "-d", ";"]);
+        args.delimiter_given = true;
+        assert!(!Config::from_args(&args).honor_sep_directive);
+    }
+
+    #[test]
+    fn test_detect_markdown() {
+        assert!(config_from(&["--detect-markdown"]).detect_markdown);
+        assert!(!config_from(&[]).detect_markdown);
+    }
+
+    #[test]
+    fn test_synthetic_headers() {
+        let config = config_from(&["--no-headers", "--synthetic-headers"]);
+        assert!(config.synthetic_headers);
+        assert!(!config.has_headers);
+
+        let result = Args::try_parse_from(["csvmd", "--synthetic-headers"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_header_row() {
+        assert_eq!(
+            config_from(&["--header-row", "2"]).header_row_index,
+            Some(2)
+        );
+        assert_eq!(config_from(&[]).header_row_index, None);
+    }
+
+    #[test]
+    fn test_header_rows() {
+        assert_eq!(config_from(&["--header-rows", "2"]).header_rows, 2);
+        assert_eq!(config_from(&[]).header_rows, 1);
+    }
+
+    #[test]
+    fn test_dash_means_stdin() {
+        let args = Args::parse_from(["csvmd", "-"]);
+        assert_eq!(args.input_files(), [None]);
+
+        let args = Args::parse_from(["csvmd", "data.csv"]);
+        assert_eq!(args.input_files(), [Some(Path::new("data.csv"))]);
+
+        let args = Args::parse_from(["csvmd"]);
+        assert_eq!(args.input_files(), [None]);
+
+        let args = Args::parse_from(["csvmd", "a.csv", "-"]);
+        assert_eq!(args.input_files(), [Some(Path::new("a.csv")), None]);
+    }
+}
@@ -0,0 +1,132 @@
+//! Transparent decompression of gzip and zstd input.
+//!
+//! Compressed input is detected from the magic bytes at its start rather than
+//! from a file extension, so files without a `.gz` or `.zst` name still work.
+
+use std::io::{self, Cursor, Read};
+
+/// The magic bytes at the start of a gzip stream.
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+/// The magic bytes at the start of a zstd frame.
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compression formats which can be detected and decompressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Gzip, starting with `1f 8b`.
+    Gzip,
+    /// Zstandard, starting with `28 b5 2f fd`.
+    Zstd,
+}
+
+impl Compression {
+    /// Detect the compression format from the first bytes of the input, if
+    /// it's compressed.
+    pub fn detect(prefix: &[u8]) -> Option<Self> {
+        if prefix.starts_with(GZIP_MAGIC) {
+            Some(Compression::Gzip)
+        } else if prefix.starts_with(ZSTD_MAGIC) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+}
+
+/// Wrap `input` in a decompressor if it starts with the magic bytes of a
+/// supported format, or return it unchanged otherwise.
+///
+/// # Example
+///
+/// ```rust
+/// use csvmd::compression::decompress;
+/// use std::io::Read;
+///
+/// let mut csv = String::new();
+/// decompress(&b"Name\nJohn"[..])?.read_to_string(&mut csv)?;
+/// assert_eq!(csv, "Name\nJohn");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn decompress<'a, R: Read + 'a>(mut input: R) -> io::Result<Box<dyn Read + 'a>> {
+    // Read the prefix in a loop, as a single read may return fewer bytes
+    let mut prefix = [0; 4];
+    let mut len = 0;
+    while len < prefix.len() {
+        match input.read(&mut prefix[len..])? {
+            0 => break,
+            n => len += n,
+        }
+    }
+
+    let input = Cursor::new(prefix[..len].to_vec()).chain(input);
+    Ok(match Compression::detect(&prefix[..len]) {
+        Some(Compression::Gzip) => Box::new(flate2::read::MultiGzDecoder::new(input)),
+        Some(Compression::Zstd) => Box::new(
+            ruzstd::decoding::StreamingDecoder::new(input)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+        ),
+        None => Box::new(input),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{csv_to_markdown, Config};
+    use std::io::Write;
+
+    const CSV: &str = "Name,Age\nJohn,25\n";
+    const MARKDOWN: &str = "| Name | Age |\n| --- | --- |\n| John | 25 |\n";
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            Compression::detect(&[0x1f, 0x8b, 0x08]),
+            Some(Compression::Gzip)
+        );
+        assert_eq!(
+            Compression::detect(&[0x28, 0xb5, 0x2f, 0xfd]),
+            Some(Compression::Zstd)
+        );
+        assert_eq!(Compression::detect(b"Name"), None);
+        assert_eq!(Compression::detect(&[0x1f]), None);
+    }
+
+    #[test]
+    fn test_decompress_gzip() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        encoder.write_all(CSV.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let input = decompress(compressed.as_slice()).unwrap();
+        let result = csv_to_markdown(input, Config::default()).unwrap();
+
+        assert_eq!(result, MARKDOWN);
+    }
+
+    #[test]
+    fn test_decompress_zstd() {
+        let compressed = ruzstd::encoding::compress_to_vec(
+            CSV.as_bytes(),
+            ruzstd::encoding::CompressionLevel::Fastest,
+        );
+
+        let input = decompress(compressed.as_slice()).unwrap();
+        let result = csv_to_markdown(input, Config::default()).unwrap();
+
+        assert_eq!(result, MARKDOWN);
+    }
+
+    #[test]
+    fn test_decompress_passes_through_plain_input() {
+        for csv in [CSV, "A", ""] {
+            let mut result = String::new();
+            decompress(csv.as_bytes())
+                .unwrap()
+                .read_to_string(&mut result)
+                .unwrap();
+
+            assert_eq!(result, csv);
+        }
+    }
+}
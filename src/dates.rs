@@ -0,0 +1,64 @@
+//! Reformatting columns of dates to a consistent format.
+//!
+//! A column is treated as dates when every non-empty cell parses in one of
+//! the [`INPUT_FORMATS`], so the same format is used for the whole column.
+
+use crate::error::{CsvMdError, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::NaiveDate;
+use std::fmt::Write;
+
+/// The formats dates are read in, in order of preference.
+///
+/// Dates written with slashes are ambiguous, so `01/02/2024` is read day
+/// first, as the 1st of February, unless a date in the same column only
+/// makes sense month first, like `12/31/2024`.
+pub(crate) const INPUT_FORMATS: &[&str] = &[
+    "%Y-%m-%d",
+    "%Y/%m/%d",
+    "%d/%m/%Y",
+    "%m/%d/%Y",
+    "%d.%m.%Y",
+    "%d %b %Y",
+    "%b %d, %Y",
+    "%b %d %Y",
+];
+
+/// A bitmask of the [`INPUT_FORMATS`] which `cell` parses in.
+pub(crate) fn matching_formats(cell: &str) -> u16 {
+    INPUT_FORMATS
+        .iter()
+        .enumerate()
+        .filter(|(_, format)| NaiveDate::parse_from_str(cell.trim(), format).is_ok())
+        .fold(0, |mask, (i, _)| mask | 1 << i)
+}
+
+/// Reformat `cell`, read in `INPUT_FORMATS[input]`, with the strftime
+/// `pattern`, returning `None` if it doesn't parse.
+///
+/// # Errors
+///
+/// Returns `CsvMdError::InvalidConfig` if `pattern` isn't a valid strftime
+/// pattern.
+pub(crate) fn reformat(cell: &str, input: usize, pattern: &str) -> Result<Option<String>> {
+    let items: Vec<Item> = StrftimeItems::new(pattern).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return Err(CsvMdError::InvalidConfig(format!(
+            "invalid date format {:?}",
+            pattern
+        )));
+    }
+
+    let Ok(date) = NaiveDate::parse_from_str(cell.trim(), INPUT_FORMATS[input]) else {
+        return Ok(None);
+    };
+
+    let mut formatted = String::new();
+    write!(formatted, "{}", date.format_with_items(items.into_iter())).map_err(|_| {
+        CsvMdError::InvalidConfig(format!(
+            "date format {:?} can't be used for dates without times",
+            pattern
+        ))
+    })?;
+    Ok(Some(formatted))
+}
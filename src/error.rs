@@ -21,6 +21,81 @@ pub enum CsvMdError {
     /// Error during string formatting operations.
     #[error("Formatting error: {0}")]
     Fmt(#[from] fmt::Error),
+
+    /// The configuration contains an invalid combination of options.
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
+    /// A column selected by name doesn't exist in the header row.
+    #[error("Column not found: {0}")]
+    ColumnNotFound(String),
+
+    /// A row has more columns than the first row while streaming
+    /// optimistically, so the table already written would be malformed.
+    #[error(
+        "Row {row} has {found} columns but the first row has {expected}; \
+         convert without optimistic streaming to handle rows of different lengths"
+    )]
+    ColumnCountChanged {
+        /// The one-based number of the row in the table.
+        row: usize,
+        /// The number of columns in the first row.
+        expected: usize,
+        /// The number of columns in the row.
+        found: usize,
+    },
+
+    /// A field is larger than `max_field_bytes`, which could be a quote that's
+    /// never closed swallowing the rest of the input.
+    #[error(
+        "Record {record} has a field of at least {size} bytes, more than the limit of \
+         {limit} bytes; check for a quote that's never closed"
+    )]
+    FieldTooLarge {
+        /// The one-based number of the record in the input.
+        record: usize,
+        /// The size of the field in bytes, or as much of it as was read before
+        /// it passed the limit.
+        size: usize,
+        /// The configured limit in bytes.
+        limit: usize,
+    },
+
+    /// The input doesn't have the shape an output format needs, like the
+    /// two or three columns of a relationship for a Mermaid diagram.
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    /// The output has more data rows than `Config::tty_row_limit`, set by
+    /// `--tty-row-limit`, allows when writing to a terminal.
+    #[error(
+        "The output has more than {limit} rows, too many to write to a terminal; \
+         redirect it to a file, see the first and last rows with `--preview 10,10`, \
+         or pass `--force` to write it anyway"
+    )]
+    TooManyRowsForTerminal {
+        /// The most rows which can be written to a terminal.
+        limit: usize,
+    },
+
+    /// The Markdown written isn't a valid GitHub Flavored Markdown table,
+    /// found by checking the output when `validate_output` is set.
+    #[error("Invalid output on line {line}: {reason}")]
+    InvalidOutput {
+        /// The one-based number of the line of output.
+        line: usize,
+        /// What's wrong with the line.
+        reason: String,
+    },
+
+    /// Converting one of several input files failed.
+    #[error("{file}: {source}")]
+    InFile {
+        /// The name of the file which couldn't be converted.
+        file: String,
+        /// What went wrong converting it.
+        source: Box<CsvMdError>,
+    },
 }
 
 impl From<csv::Error> for CsvMdError {
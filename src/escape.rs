@@ -0,0 +1,210 @@
+//! Escaping cells for each output format.
+//!
+//! Every format has its own special characters, so the cells of a table are
+//! escaped by the [`Escaper`] for the format being written, which
+//! [`OutputFormat::escaper`](crate::formats::OutputFormat::escaper) chooses.
+
+use crate::{escape_markdown_cell, Config};
+use std::borrow::Cow;
+
+/// Escapes the text of a cell for an output format.
+pub trait Escaper {
+    /// Escape `field` so it's written as literal text, borrowing it when
+    /// nothing needs escaping.
+    fn escape<'a>(&self, field: &'a str, config: &Config) -> Cow<'a, str>;
+}
+
+/// Escapes cells for a Markdown table: `|` becomes `\|` (or `&#124;`,
+/// depending on `Config::pipe_escape`), newlines become `<br>` tags and
+/// carriage returns are removed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarkdownEscaper;
+
+impl Escaper for MarkdownEscaper {
+    fn escape<'a>(&self, field: &'a str, config: &Config) -> Cow<'a, str> {
+        escape_markdown_cell(field, config.pipe_escape)
+    }
+}
+
+/// Escapes cells for an Emacs Org-mode table: `|` becomes `\vert{}`, and as
+/// cells can't span lines, newlines become spaces and carriage returns are
+/// removed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OrgEscaper;
+
+impl Escaper for OrgEscaper {
+    fn escape<'a>(&self, field: &'a str, _config: &Config) -> Cow<'a, str> {
+        if !field.contains(['|', '\n', '\r']) {
+            return Cow::Borrowed(field);
+        }
+
+        let mut escaped = String::with_capacity(field.len() + 8);
+        for c in field.chars() {
+            match c {
+                '|' => escaped.push_str("\\vert{}"),
+                '\n' => escaped.push(' '),
+                '\r' => {}
+                c => escaped.push(c),
+            }
+        }
+        Cow::Owned(escaped)
+    }
+}
+
+/// Escapes the labels of a Mermaid diagram: as each relationship is on a
+/// line of its own, newlines become spaces and carriage returns are removed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MermaidEscaper;
+
+impl Escaper for MermaidEscaper {
+    fn escape<'a>(&self, field: &'a str, _config: &Config) -> Cow<'a, str> {
+        if !field.contains(['\n', '\r']) {
+            return Cow::Borrowed(field);
+        }
+
+        Cow::Owned(field.replace('\r', "").replace('\n', " "))
+    }
+}
+
+/// Escapes cells for a reStructuredText table: the `\\`, `*`, `` ` `` and `|`
+/// which start inline markup are escaped with a backslash, and carriage
+/// returns are removed. Newlines are kept, as grid table cells can span
+/// several lines.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RstEscaper;
+
+impl Escaper for RstEscaper {
+    fn escape<'a>(&self, field: &'a str, _config: &Config) -> Cow<'a, str> {
+        if !field.contains(['\\', '*', '`', '|', '\r']) {
+            return Cow::Borrowed(field);
+        }
+
+        let mut escaped = String::with_capacity(field.len() + 8);
+        for c in field.chars() {
+            match c {
+                '\\' | '*' | '`' | '|' => {
+                    escaped.push('\\');
+                    escaped.push(c);
+                }
+                '\r' => {}
+                c => escaped.push(c),
+            }
+        }
+        Cow::Owned(escaped)
+    }
+}
+
+/// Escapes cells for HTML: `&`, `<`, `>` and `"` become entities, newlines
+/// become `<br>` tags and carriage returns are removed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HtmlEscaper;
+
+impl Escaper for HtmlEscaper {
+    fn escape<'a>(&self, field: &'a str, _config: &Config) -> Cow<'a, str> {
+        if !field.contains(['&', '<', '>', '"', '\n', '\r']) {
+            return Cow::Borrowed(field);
+        }
+
+        let mut escaped = String::with_capacity(field.len() + 8);
+        for c in field.chars() {
+            match c {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                '\n' => escaped.push_str("<br>"),
+                '\r' => {}
+                c => escaped.push(c),
+            }
+        }
+        Cow::Owned(escaped)
+    }
+}
+
+/// Escapes cells for the inside of a JSON string: quotes, backslashes and
+/// control characters are escaped, but the surrounding quotes aren't added.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonEscaper;
+
+impl Escaper for JsonEscaper {
+    fn escape<'a>(&self, field: &'a str, _config: &Config) -> Cow<'a, str> {
+        if !field.contains(|c: char| c == '"' || c == '\\' || (c as u32) < 0x20) {
+            return Cow::Borrowed(field);
+        }
+
+        let mut escaped = String::with_capacity(field.len() + 8);
+        for c in field.chars() {
+            match c {
+                '"' => escaped.push_str("\\\""),
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\t' => escaped.push_str("\\t"),
+                c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+                c => escaped.push(c),
+            }
+        }
+        Cow::Owned(escaped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIELD: &str = "a | <b> \"c\"";
+
+    #[test]
+    fn test_markdown_escaper() {
+        let config = Config::default();
+        assert_eq!(MarkdownEscaper.escape(FIELD, &config), "a \\| <b> \"c\"");
+        assert!(matches!(
+            MarkdownEscaper.escape("plain", &config),
+            Cow::Borrowed("plain")
+        ));
+    }
+
+    #[test]
+    fn test_org_escaper() {
+        let config = Config::default();
+        assert_eq!(OrgEscaper.escape(FIELD, &config), "a \\vert{} <b> \"c\"");
+        assert_eq!(OrgEscaper.escape("a\r\nb", &config), "a b");
+    }
+
+    #[test]
+    fn test_mermaid_escaper() {
+        let config = Config::default();
+        assert_eq!(MermaidEscaper.escape(FIELD, &config), FIELD);
+        assert_eq!(MermaidEscaper.escape("a\r\nb", &config), "a b");
+    }
+
+    #[test]
+    fn test_rst_escaper() {
+        let config = Config::default();
+        assert_eq!(RstEscaper.escape(FIELD, &config), "a \\| <b> \"c\"");
+        assert_eq!(
+            RstEscaper.escape("*C:\\temp*\r\n`x`", &config),
+            "\\*C:\\\\temp\\*\n\\`x\\`"
+        );
+    }
+
+    #[test]
+    fn test_html_escaper() {
+        let config = Config::default();
+        assert_eq!(
+            HtmlEscaper.escape(FIELD, &config),
+            "a | &lt;b&gt; &quot;c&quot;"
+        );
+        assert_eq!(HtmlEscaper.escape("a & b\r\nc", &config), "a &amp; b<br>c");
+    }
+
+    #[test]
+    fn test_json_escaper() {
+        let config = Config::default();
+        assert_eq!(JsonEscaper.escape(FIELD, &config), "a | <b> \\\"c\\\"");
+        assert_eq!(
+            JsonEscaper.escape("C:\\temp\n\u{1}", &config),
+            "C:\\\\temp\\n\\u0001"
+        );
+    }
+}
@@ -0,0 +1,668 @@
+//! Output formats other than Markdown: Org-mode tables, reStructuredText grid
+//! tables, HTML tables, JSON and Mermaid class diagrams.
+//!
+//! Org-mode tables are written by the Markdown renderer, so every option
+//! applies. The others write the rows Markdown tables are made from, so the
+//! parsing options from a [`Config`] apply along with the options choosing
+//! and cleaning up rows and columns, like `columns`, `filter`, `trim_mode`,
+//! `null_values`, `preview` and `pin_rows_to_bottom`, but not the options for
+//! how a Markdown table looks, like `row_numbers` or `number_formats`. HTML
+//! also uses `mark_empty_cells` and `caption`.
+
+use crate::error::{CsvMdError, Result};
+use crate::escape::{
+    Escaper, HtmlEscaper, JsonEscaper, MarkdownEscaper, MermaidEscaper, OrgEscaper, RstEscaper,
+};
+use crate::{
+    csv_to_markdown, csv_to_markdown_with_stats, key_rows, read_processed_table, Config,
+    ConversionStats,
+};
+use std::fmt::Write;
+use std::io::Read;
+use unicode_width::UnicodeWidthStr;
+
+/// The formats a CSV can be converted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum OutputFormat {
+    /// A Markdown table (default).
+    #[default]
+    Markdown,
+    /// An Emacs Org-mode table, with a separator line like `|-----+-----|`.
+    OrgMode,
+    /// A reStructuredText grid table, with a border around every cell.
+    Rst,
+    /// An HTML `<table>`.
+    Html,
+    /// A JSON array with an object for each data row.
+    Json,
+    /// JSON Lines, with an object for each data row on its own line.
+    JsonLines,
+    /// A Mermaid class diagram, from rows of relationships like
+    /// `from,relation,to`.
+    Mermaid,
+}
+
+impl OutputFormat {
+    /// The escaper for cells written in this format.
+    pub fn escaper(self) -> &'static dyn Escaper {
+        match self {
+            OutputFormat::Markdown => &MarkdownEscaper,
+            OutputFormat::OrgMode => &OrgEscaper,
+            OutputFormat::Rst => &RstEscaper,
+            OutputFormat::Html => &HtmlEscaper,
+            OutputFormat::Json | OutputFormat::JsonLines => &JsonEscaper,
+            OutputFormat::Mermaid => &MermaidEscaper,
+        }
+    }
+}
+
+/// Convert CSV data to a string in the given format.
+///
+/// # Example
+///
+/// ```rust
+/// use csvmd::formats::{convert_to, OutputFormat};
+/// use csvmd::Config;
+/// use std::io::Cursor;
+///
+/// let json = convert_to(Cursor::new("Name,Age\nJohn,25"), Config::default(), OutputFormat::Json)?;
+/// assert_eq!(json, "[\n  {\"Name\":\"John\",\"Age\":\"25\"}\n]\n");
+/// # Ok::<(), csvmd::error::CsvMdError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns `CsvMdError` if the input cannot be read or is malformed.
+pub fn convert_to<R: Read>(input: R, config: Config, format: OutputFormat) -> Result<String> {
+    match format {
+        OutputFormat::Markdown => csv_to_markdown(input, config),
+        OutputFormat::OrgMode => csv_to_org(input, config),
+        OutputFormat::Rst => csv_to_rst(input, &config),
+        OutputFormat::Html => csv_to_html(input, &config),
+        OutputFormat::Json => csv_to_json(input, &config),
+        OutputFormat::JsonLines => csv_to_json_lines(input, &config),
+        OutputFormat::Mermaid => csv_to_mermaid(input, &config),
+    }
+}
+
+/// Convert CSV data to a string in the given format, like [`convert_to`],
+/// along with measurements of the output, where `rows` is the number of data
+/// rows and `max_line_width` is measured over every line of the output.
+///
+/// Formats other than Markdown and Org-mode read the whole input before
+/// converting it, to count its rows.
+///
+/// # Example
+///
+/// ```rust
+/// use csvmd::formats::{convert_to_with_stats, OutputFormat};
+/// use csvmd::Config;
+/// use std::io::Cursor;
+///
+/// let input = Cursor::new("Name,Age\nJohn,25\nJane,30");
+/// let (_, stats) = convert_to_with_stats(input, Config::default(), OutputFormat::Html)?;
+/// assert_eq!(stats.rows, 2);
+/// assert_eq!(stats.columns, 2);
+/// # Ok::<(), csvmd::error::CsvMdError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns `CsvMdError` if the input cannot be read or is malformed.
+pub fn convert_to_with_stats<R: Read>(
+    mut input: R,
+    config: Config,
+    format: OutputFormat,
+) -> Result<(String, ConversionStats)> {
+    match format {
+        OutputFormat::Markdown => csv_to_markdown_with_stats(input, config),
+        OutputFormat::OrgMode => csv_to_markdown_with_stats(
+            input,
+            Config {
+                org_mode: true,
+                ..config
+            },
+        ),
+        _ => {
+            let mut data = Vec::new();
+            input.read_to_end(&mut data)?;
+            let (header, rows) = read_processed_table(data.as_slice(), &config)?;
+            let columns = header.iter().chain(&rows).map(Vec::len).max().unwrap_or(0);
+            let output = convert_to(data.as_slice(), config, format)?;
+            let stats = ConversionStats {
+                rows: rows.len(),
+                columns,
+                max_line_width: output
+                    .lines()
+                    .map(UnicodeWidthStr::width)
+                    .max()
+                    .unwrap_or(0),
+            };
+            Ok((output, stats))
+        }
+    }
+}
+
+/// Convert CSV data to an Emacs Org-mode table, like [`csv_to_markdown`] with
+/// `org_mode` set.
+///
+/// # Errors
+///
+/// Returns `CsvMdError` if the input cannot be read or is malformed.
+pub fn csv_to_org<R: Read>(input: R, config: Config) -> Result<String> {
+    csv_to_markdown(
+        input,
+        Config {
+            org_mode: true,
+            ..config
+        },
+    )
+}
+
+/// Convert CSV data to a reStructuredText grid table, with the header
+/// separated by a `=` rule when `has_headers` is set. Every row is read
+/// first to find the width of each column.
+///
+/// Cells containing newlines span several lines of their row, as grid tables
+/// allow.
+///
+/// # Errors
+///
+/// Returns `CsvMdError` if the input cannot be read or is malformed.
+pub fn csv_to_rst<R: Read>(input: R, config: &Config) -> Result<String> {
+    let (header, rows) = read_processed_table(input, config)?;
+    let columns = header.iter().chain(&rows).map(Vec::len).max().unwrap_or(0);
+    if columns == 0 {
+        return Ok(String::new());
+    }
+
+    // The lines of each cell, in each row
+    let cells: Vec<Vec<Vec<String>>> = header
+        .iter()
+        .chain(&rows)
+        .map(|row| {
+            (0..columns)
+                .map(|i| {
+                    let cell = row.get(i).map_or("", String::as_str);
+                    let cell = OutputFormat::Rst.escaper().escape(cell, config);
+                    cell.lines().map(str::to_string).collect()
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut widths = vec![0; columns];
+    for row in &cells {
+        for (lines, width) in row.iter().zip(widths.iter_mut()) {
+            *width = lines
+                .iter()
+                .map(|line| line.width())
+                .fold(*width, usize::max);
+        }
+    }
+
+    let border = |fill: &str| {
+        let rules: Vec<String> = widths.iter().map(|width| fill.repeat(width + 2)).collect();
+        format!("+{}+\n", rules.join("+"))
+    };
+
+    let mut rst = border("-");
+    for (i, row) in cells.iter().enumerate() {
+        let height = row.iter().map(Vec::len).max().unwrap_or(0).max(1);
+        for line in 0..height {
+            rst.push('|');
+            for (lines, width) in row.iter().zip(&widths) {
+                let text = lines.get(line).map_or("", String::as_str);
+                write!(rst, " {}{} |", text, " ".repeat(width - text.width()))?;
+            }
+            rst.push('\n');
+        }
+        rst.push_str(&border(if i == 0 && header.is_some() { "=" } else { "-" }));
+    }
+
+    Ok(rst)
+}
+
+/// Convert CSV data to an HTML table, with the header in a `<thead>` when
+/// `has_headers` is set.
+///
+/// With `mark_empty_cells`, empty and null data cells are written as
+/// `<td class="empty"></td>`. With `caption`, the table starts with a
+/// `<caption>` element.
+///
+/// # Errors
+///
+/// Returns `CsvMdError` if the input cannot be read or is malformed.
+pub fn csv_to_html<R: Read>(input: R, config: &Config) -> Result<String> {
+    let (header, rows) = read_processed_table(input, config)?;
+    let columns = header.iter().chain(&rows).map(Vec::len).max().unwrap_or(0);
+    if columns == 0 {
+        return Ok(String::new());
+    }
+
+    let mut html = String::from("<table>\n");
+    if let Some(caption) = &config.caption {
+        let caption = OutputFormat::Html.escaper().escape(caption, config);
+        writeln!(html, "<caption>{}</caption>", caption)?;
+    }
+    if let Some(header) = &header {
+        html.push_str("<thead>\n");
+        write_html_row(&mut html, header, columns, "th", config)?;
+        html.push_str("</thead>\n");
+    }
+    html.push_str("<tbody>\n");
+    for row in &rows {
+        write_html_row(&mut html, row, columns, "td", config)?;
+    }
+    html.push_str("</tbody>\n</table>\n");
+
+    Ok(html)
+}
+
+/// Write a table row of `columns` cells, padding it with empty cells.
+fn write_html_row(
+    html: &mut String,
+    row: &[String],
+    columns: usize,
+    tag: &str,
+    config: &Config,
+) -> Result<()> {
+    html.push_str("<tr>");
+    for i in 0..columns {
+        let cell = row.get(i).map_or("", String::as_str);
+        if tag == "td" && config.mark_empty_cells && (cell.is_empty() || config.is_null(cell)) {
+            html.push_str("<td class=\"empty\"></td>");
+            continue;
+        }
+        let cell = OutputFormat::Html.escaper().escape(cell, config);
+        write!(html, "<{}>{}</{}>", tag, cell, tag)?;
+    }
+    html.push_str("</tr>\n");
+    Ok(())
+}
+
+/// Convert CSV data to a JSON array with an object for each data row, keyed
+/// like [`csv_to_records_map`](crate::csv_to_records_map) but keeping the
+/// columns in order. Every value is a string.
+///
+/// # Errors
+///
+/// Returns `CsvMdError` if the input cannot be read or is malformed.
+pub fn csv_to_json<R: Read>(input: R, config: &Config) -> Result<String> {
+    let rows = read_json_rows(input, config)?;
+    if rows.is_empty() {
+        return Ok("[]\n".to_string());
+    }
+
+    let objects: Vec<String> = rows.iter().map(|row| json_object(row, config)).collect();
+    Ok(format!("[\n  {}\n]\n", objects.join(",\n  ")))
+}
+
+/// Convert CSV data to JSON Lines, with an object for each data row on its
+/// own line, keyed like [`csv_to_json`].
+///
+/// # Errors
+///
+/// Returns `CsvMdError` if the input cannot be read or is malformed.
+pub fn csv_to_json_lines<R: Read>(input: R, config: &Config) -> Result<String> {
+    let rows = read_json_rows(input, config)?;
+    Ok(rows
+        .iter()
+        .map(|row| json_object(row, config) + "\n")
+        .collect())
+}
+
+/// Convert CSV data of relationships to a Mermaid class diagram, for a
+/// ` ```mermaid ` block.
+///
+/// Every row needs two columns, `from,to`, giving a relationship like
+/// `Customer --> Order`, or three, `from,relation,to`, which labels it like
+/// `Customer --> Order : places`. The relation can be left empty. The header,
+/// when `has_headers` is set, only sets the number of columns. Class names
+/// can only contain letters, digits and underscores.
+///
+/// # Example
+///
+/// ```rust
+/// use csvmd::formats::csv_to_mermaid;
+/// use csvmd::Config;
+/// use std::io::Cursor;
+///
+/// let input = Cursor::new("from,relation,to\nCustomer,places,Order");
+/// let mermaid = csv_to_mermaid(input, &Config::default())?;
+/// assert_eq!(mermaid, "classDiagram\n    Customer --> Order : places\n");
+/// # Ok::<(), csvmd::error::CsvMdError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns `CsvMdError` if the input cannot be read or is malformed, or
+/// `CsvMdError::InvalidInput` if a row doesn't have the same two or three
+/// columns, or a class name is empty or has other characters.
+pub fn csv_to_mermaid<R: Read>(input: R, config: &Config) -> Result<String> {
+    let (header, rows) = read_processed_table(input, config)?;
+    let Some(columns) = header.as_ref().or(rows.first()).map(Vec::len) else {
+        return Ok("classDiagram\n".to_string());
+    };
+    if !(2..=3).contains(&columns) {
+        return Err(CsvMdError::InvalidInput(format!(
+            "a Mermaid diagram needs two columns, `from,to`, or three, `from,relation,to`, \
+             but the input has {}",
+            columns
+        )));
+    }
+
+    let mut mermaid = String::from("classDiagram\n");
+    for (i, row) in rows.iter().enumerate() {
+        let row_number = i + 1;
+        if row.len() != columns {
+            return Err(CsvMdError::InvalidInput(format!(
+                "row {} has {} columns, but a Mermaid diagram needs {} in every row",
+                row_number,
+                row.len(),
+                columns
+            )));
+        }
+
+        let from = mermaid_class_name(&row[0], row_number)?;
+        let to = mermaid_class_name(&row[columns - 1], row_number)?;
+        write!(mermaid, "    {} --> {}", from, to)?;
+
+        let relation = if columns == 3 { row[1].trim() } else { "" };
+        if !relation.is_empty() {
+            write!(
+                mermaid,
+                " : {}",
+                OutputFormat::Mermaid.escaper().escape(relation, config)
+            )?;
+        }
+        mermaid.push('\n');
+    }
+
+    Ok(mermaid)
+}
+
+/// Read each data row as pairs of keys and cells, in column order, for a
+/// JSON object.
+fn read_json_rows<R: Read>(input: R, config: &Config) -> Result<Vec<Vec<(String, String)>>> {
+    let (header, rows) = read_processed_table(input, config)?;
+    Ok(key_rows(header.as_deref(), rows))
+}
+
+/// Check `cell`, from the data row numbered `row`, can be used as the name
+/// of a class in a Mermaid diagram.
+fn mermaid_class_name(cell: &str, row: usize) -> Result<&str> {
+    let name = cell.trim();
+    if name.is_empty() {
+        return Err(CsvMdError::InvalidInput(format!(
+            "row {} has an empty class name",
+            row
+        )));
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(CsvMdError::InvalidInput(format!(
+            "row {} has the class name {:?}, but Mermaid class names can only contain \
+             letters, digits and underscores",
+            row, name
+        )));
+    }
+    Ok(name)
+}
+
+/// Write a row as a JSON object on a single line.
+fn json_object(row: &[(String, String)], config: &Config) -> String {
+    let escaper = OutputFormat::Json.escaper();
+    let fields: Vec<String> = row
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "\"{}\":\"{}\"",
+                escaper.escape(key, config),
+                escaper.escape(value, config)
+            )
+        })
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const CSV: &str = "Name,Note\nJohn,\"<b>\"\"hi\"\" & bye</b>\"\nJane";
+
+    #[test]
+    fn test_output_format_escaper() {
+        let config = Config::default();
+        for (format, expected) in [
+            (OutputFormat::Markdown, "a\\|b<br>\"c\" & d"),
+            (OutputFormat::OrgMode, "a\\vert{}b \"c\" & d"),
+            (OutputFormat::Rst, "a\\|b\n\"c\" & d"),
+            (OutputFormat::Html, "a|b<br>&quot;c&quot; &amp; d"),
+            (OutputFormat::Json, "a|b\\n\\\"c\\\" & d"),
+            (OutputFormat::JsonLines, "a|b\\n\\\"c\\\" & d"),
+            (OutputFormat::Mermaid, "a|b \"c\" & d"),
+        ] {
+            let escaped = format.escaper().escape("a|b\n\"c\" & d", &config);
+            assert_eq!(escaped, expected, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn test_csv_to_org() {
+        let org = csv_to_org(Cursor::new("Name,Op\nJohn,a|b\nJane"), Config::default()).unwrap();
+
+        assert_eq!(
+            org,
+            "| Name | Op |\n|-----+-----|\n| John | a\\vert{}b |\n| Jane |  |\n"
+        );
+
+        let config = Config {
+            cell_padding: false,
+            header_alignment: crate::HeaderAlignment::Center,
+            ..Config::default()
+        };
+        let org = convert_to(Cursor::new("A,B,C\n1,2,3"), config, OutputFormat::OrgMode).unwrap();
+        assert_eq!(org, "|A|B|C|\n|---+---+---|\n|1|2|3|\n");
+    }
+
+    #[test]
+    fn test_csv_to_rst() {
+        let rst = csv_to_rst(Cursor::new("Name,Age\nJohn,25"), &Config::default()).unwrap();
+
+        assert_eq!(
+            rst,
+            "+------+-----+\n\
+             | Name | Age |\n\
+             +======+=====+\n\
+             | John | 25  |\n\
+             +------+-----+\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_to_rst_multi_line_cells() {
+        let config = Config {
+            has_headers: false,
+            ..Config::default()
+        };
+        let rst = csv_to_rst(Cursor::new("\"a\nb*\",c\nd"), &config).unwrap();
+
+        assert_eq!(
+            rst,
+            "+-----+---+\n\
+             | a   | c |\n\
+             | b\\* |   |\n\
+             +-----+---+\n\
+             | d   |   |\n\
+             +-----+---+\n"
+        );
+        assert_eq!(csv_to_rst(Cursor::new(""), &config).unwrap(), "");
+    }
+
+    #[test]
+    fn test_csv_to_html() {
+        let html = csv_to_html(Cursor::new(CSV), &Config::default()).unwrap();
+
+        assert_eq!(
+            html,
+            "<table>\n<thead>\n<tr><th>Name</th><th>Note</th></tr>\n</thead>\n<tbody>\n\
+             <tr><td>John</td><td>&lt;b&gt;&quot;hi&quot; &amp; bye&lt;/b&gt;</td></tr>\n\
+             <tr><td>Jane</td><td></td></tr>\n</tbody>\n</table>\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_to_html_mark_empty_cells() {
+        let config = Config {
+            mark_empty_cells: true,
+            null_values: vec!["NULL".to_string()],
+            ..Config::default()
+        };
+        let html = csv_to_html(Cursor::new("Name,,Note\nJohn,NULL, \nJane,0"), &config).unwrap();
+
+        // Header cells are never marked, and a space or a zero isn't empty
+        assert_eq!(
+            html,
+            "<table>\n<thead>\n<tr><th>Name</th><th></th><th>Note</th></tr>\n</thead>\n<tbody>\n\
+             <tr><td>John</td><td class=\"empty\"></td><td> </td></tr>\n\
+             <tr><td>Jane</td><td>0</td><td class=\"empty\"></td></tr>\n</tbody>\n</table>\n"
+        );
+
+        let markdown = csv_to_markdown(Cursor::new("A,B\nJohn,"), config).unwrap();
+        assert_eq!(
+            markdown,
+            csv_to_markdown(Cursor::new("A,B\nJohn,"), Config::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_csv_to_html_caption() {
+        let config = Config {
+            caption: Some("Sales <2024> & \"more\"".to_string()),
+            ..Config::default()
+        };
+        let html = csv_to_html(Cursor::new("A\n1"), &config).unwrap();
+
+        assert_eq!(
+            html,
+            "<table>\n<caption>Sales &lt;2024&gt; &amp; &quot;more&quot;</caption>\n\
+             <thead>\n<tr><th>A</th></tr>\n</thead>\n<tbody>\n<tr><td>1</td></tr>\n\
+             </tbody>\n</table>\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_to_html_without_headers() {
+        let config = Config {
+            has_headers: false,
+            ..Config::default()
+        };
+        let html = csv_to_html(Cursor::new("a\nb\nc"), &config).unwrap();
+
+        assert_eq!(
+            html,
+            "<table>\n<tbody>\n<tr><td>a</td></tr>\n<tr><td>b</td></tr>\n<tr><td>c</td></tr>\n\
+             </tbody>\n</table>\n"
+        );
+        assert_eq!(csv_to_html(Cursor::new(""), &config).unwrap(), "");
+    }
+
+    #[test]
+    fn test_csv_to_json() {
+        let json = csv_to_json(Cursor::new(CSV), &Config::default()).unwrap();
+
+        assert_eq!(
+            json,
+            "[\n  {\"Name\":\"John\",\"Note\":\"<b>\\\"hi\\\" & bye</b>\"},\n  {\"Name\":\"Jane\"}\n]\n"
+        );
+        assert_eq!(
+            csv_to_json(Cursor::new("Name"), &Config::default()).unwrap(),
+            "[]\n"
+        );
+    }
+
+    #[test]
+    fn test_formats_apply_row_options() {
+        let config = Config {
+            columns: Some(vec!["Note".to_string()]),
+            filter: Some("Name!=Jane".parse().unwrap()),
+            trim_mode: crate::TrimMode::Both,
+            null_values: vec!["NULL".to_string()],
+            ..Config::default()
+        };
+        let input = "Name,Note\nJohn, a|b \nJane,x\nJim,NULL";
+
+        let json = csv_to_json(Cursor::new(input), &config).unwrap();
+        assert_eq!(json, "[\n  {\"Note\":\"a|b\"},\n  {\"Note\":\"\"}\n]\n");
+
+        let html = csv_to_html(Cursor::new(input), &config).unwrap();
+        assert!(html.contains("<tr><td>a|b</td></tr>\n<tr><td></td></tr>\n</tbody>"));
+
+        let rst = csv_to_rst(Cursor::new(input), &config).unwrap();
+        assert_eq!(
+            rst,
+            "+------+\n| Note |\n+======+\n| a\\|b |\n+------+\n|      |\n+------+\n"
+        );
+
+        let (_, stats) =
+            convert_to_with_stats(Cursor::new(input), config, OutputFormat::JsonLines).unwrap();
+        assert_eq!((stats.rows, stats.columns), (2, 1));
+    }
+
+    #[test]
+    fn test_csv_to_json_lines() {
+        let json =
+            csv_to_json_lines(Cursor::new("B,A\n1,\"x\ny\"\n3,4"), &Config::default()).unwrap();
+
+        assert_eq!(
+            json,
+            "{\"B\":\"1\",\"A\":\"x\\ny\"}\n{\"B\":\"3\",\"A\":\"4\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_to_mermaid() {
+        let input = "from,relation,to\nCustomer,places,Order\n Order ,\"has\nmany\",Line_Item\nOrder,,Invoice";
+        let mermaid = csv_to_mermaid(Cursor::new(input), &Config::default()).unwrap();
+
+        assert_eq!(
+            mermaid,
+            "classDiagram\n    Customer --> Order : places\n    Order --> Line_Item : has many\n    \
+             Order --> Invoice\n"
+        );
+
+        let config = Config {
+            has_headers: false,
+            ..Config::default()
+        };
+        let mermaid = convert_to(Cursor::new("A,B\nB,C"), config, OutputFormat::Mermaid).unwrap();
+        assert_eq!(mermaid, "classDiagram\n    A --> B\n    B --> C\n");
+    }
+
+    #[test]
+    fn test_csv_to_mermaid_invalid_input() {
+        let config = Config {
+            flexible: true,
+            ..Config::default()
+        };
+        for (input, message) in [
+            ("a,b,c,d\nA,x,B,y", "but the input has 4"),
+            ("from,to\nA,B\nA,B,C", "row 2 has 3 columns"),
+            ("from,to\nA,", "row 1 has an empty class name"),
+            (
+                "from,to\nOrder Item,B",
+                "row 1 has the class name \"Order Item\"",
+            ),
+        ] {
+            let err = csv_to_mermaid(Cursor::new(input), &config).unwrap_err();
+            assert!(
+                matches!(&err, CsvMdError::InvalidInput(reason) if reason.contains(message)),
+                "{} doesn't mention {:?}",
+                err,
+                message
+            );
+        }
+    }
+}
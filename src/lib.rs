@@ -24,15 +24,29 @@
 //! println!("{}", result);
 //! ```
 
+pub mod cli;
+#[cfg(feature = "compression")]
+pub mod compression;
+#[cfg(feature = "dates")]
+mod dates;
 pub mod error;
+pub mod escape;
+pub mod formats;
 
-use csv::ReaderBuilder;
-use error::Result;
-use std::fmt::Write as FmtWrite;
-use std::io::{Read, Seek, SeekFrom, Write};
+use csv::{ReaderBuilder, StringRecord};
+use error::{CsvMdError, Result};
+use formats::OutputFormat;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::SystemTime;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 /// Header alignment options for Markdown tables.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum HeaderAlignment {
     /// Left-aligned headers (default): `| --- |`
     Left,
@@ -42,8 +56,348 @@ pub enum HeaderAlignment {
     Right,
 }
 
+/// What to output for a table with a header row but no data rows, for example
+/// when a filter removes every row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum EmptyTableBehavior {
+    /// The header, separator and a single row of empty cells.
+    HeaderWithEmptyRow,
+    /// Just the header and separator (default).
+    #[default]
+    HeaderOnly,
+    /// No output at all.
+    Nothing,
+}
+
+/// Where to write the header separator line relative to the header row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SeparatorPosition {
+    /// After the header row, as in standard Markdown (default).
+    #[default]
+    AfterFirst,
+    /// Before the header row, for renderers which treat the row after the
+    /// separator as the header.
+    BeforeFirst,
+}
+
+/// Which side of a short row its missing cells are added on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PadSide {
+    /// After the row's cells, like `| X | Y |  |  |` (default).
+    #[default]
+    End,
+    /// Before the row's cells, like `|  |  | X | Y |`, for rows which line up
+    /// on their last cell, like an indented hierarchy.
+    Start,
+}
+
+/// What to do with control characters in cells, like NUL, bell (`\x07`) or
+/// escape (`\x1b`). Newlines and carriage returns are always handled
+/// separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ControlCharPolicy {
+    /// Leave them in the output unchanged (default).
+    #[default]
+    Keep,
+    /// Remove them from the output.
+    Strip,
+    /// Replace them with a visible escape like `\u0007`.
+    Escape,
+}
+
+/// The aggregate written in the summary row for each numeric column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SummaryKind {
+    /// The sum of the values, labelled `Total`.
+    Sum,
+    /// The mean of the values, labelled `Mean`.
+    Mean,
+    /// The number of values, labelled `Count`.
+    Count,
+}
+
+impl SummaryKind {
+    /// The label written in the first column of the summary row.
+    fn label(self) -> &'static str {
+        match self {
+            SummaryKind::Sum => "Total",
+            SummaryKind::Mean => "Mean",
+            SummaryKind::Count => "Count",
+        }
+    }
+}
+
+/// Which whitespace to trim from the start and end of each cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TrimMode {
+    /// Leave cells as they are (default).
+    #[default]
+    None,
+    /// Trim whitespace from both ends.
+    Both,
+    /// Trim whitespace from the end only, keeping any indentation.
+    Trailing,
+    /// Trim whitespace from the start only.
+    Leading,
+}
+
+/// How pipe characters inside cells are escaped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PipeEscape {
+    /// Escape pipes with a backslash, as `\|` (default).
+    #[default]
+    Backslash,
+    /// Write pipes as the HTML entity `&#124;`, which works in renderers that
+    /// don't honour `\|` inside tables.
+    Entity,
+}
+
+/// The kind of callout to wrap the output in, like GitHub's `> [!NOTE]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CalloutKind {
+    /// `> [!NOTE]`
+    Note,
+    /// `> [!WARNING]`
+    Warning,
+    /// `> [!TIP]`
+    Tip,
+}
+
+impl CalloutKind {
+    /// The marker written on the first line of the callout.
+    fn marker(self) -> &'static str {
+        match self {
+            CalloutKind::Note => "[!NOTE]",
+            CalloutKind::Warning => "[!WARNING]",
+            CalloutKind::Tip => "[!TIP]",
+        }
+    }
+}
+
+/// A reference to a column, either by position or by header name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ColumnRef {
+    /// A zero-based column index.
+    Index(usize),
+    /// A header name. Requires `has_headers`.
+    Name(String),
+}
+
+impl FromStr for ColumnRef {
+    type Err = std::convert::Infallible;
+
+    /// Parse a column made up only of digits as an index, and anything else
+    /// as a header name.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match s.parse::<usize>() {
+            Ok(index) => ColumnRef::Index(index),
+            Err(_) => ColumnRef::Name(s.to_string()),
+        })
+    }
+}
+
+/// Comparison operators supported by [`RowFilter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FilterOp {
+    /// The cell is exactly equal to the value: `column==value`
+    Equals,
+    /// The cell is not equal to the value: `column!=value`
+    NotEquals,
+    /// The cell contains the value as a substring: `column contains value`
+    Contains,
+}
+
+/// A simple filter keeping only the data rows where a column matches a value.
+///
+/// Filters can be parsed from strings like `status==active`, `2!=0` or
+/// `name contains Smith`. A column made up only of digits is treated as a
+/// zero-based index; anything else is a header name.
+///
+/// Rows too short to have the filtered column never match.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RowFilter {
+    /// The column to compare.
+    pub column: ColumnRef,
+    /// How to compare the cell with `value`.
+    pub op: FilterOp,
+    /// The value to compare the cell with.
+    pub value: String,
+}
+
+impl RowFilter {
+    /// Whether the given cell (or `None` if the row is too short) matches the filter.
+    fn matches(&self, cell: Option<&str>) -> bool {
+        let Some(cell) = cell else {
+            return false;
+        };
+
+        match self.op {
+            FilterOp::Equals => cell == self.value,
+            FilterOp::NotEquals => cell != self.value,
+            FilterOp::Contains => cell.contains(self.value.as_str()),
+        }
+    }
+}
+
+impl FromStr for RowFilter {
+    type Err = CsvMdError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (column, op, value) = if let Some((column, value)) = s.split_once("==") {
+            (column, FilterOp::Equals, value)
+        } else if let Some((column, value)) = s.split_once("!=") {
+            (column, FilterOp::NotEquals, value)
+        } else if let Some((column, value)) = s.split_once(" contains ") {
+            (column, FilterOp::Contains, value)
+        } else {
+            return Err(CsvMdError::InvalidConfig(format!(
+                "invalid filter `{}`, expected `column==value`, `column!=value` or `column contains value`",
+                s
+            )));
+        };
+
+        let column = column.trim();
+        if column.is_empty() {
+            return Err(CsvMdError::InvalidConfig(format!(
+                "invalid filter `{}`, missing column",
+                s
+            )));
+        }
+
+        let Ok(column) = column.parse::<ColumnRef>();
+
+        Ok(Self {
+            column,
+            op,
+            value: value.to_string(),
+        })
+    }
+}
+
+/// How to format the numbers in a column.
+///
+/// Formats can be parsed from patterns like `0.00` for two decimal places, or
+/// `#,##0.00` to also group the digits into thousands with `,`. Numbers are
+/// rounded half away from zero, so `2.5` becomes `3` with no decimal places,
+/// and a number rounded to zero is never written as `-0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct NumberFormat {
+    /// Number of digits after the decimal point.
+    pub decimals: usize,
+    /// Separator between each group of three digits before the decimal point.
+    pub thousands_separator: Option<char>,
+}
+
+impl NumberFormat {
+    /// Format a cell holding a number, or return `None` if it doesn't hold a
+    /// finite number.
+    fn format(&self, cell: &str) -> Option<String> {
+        let cell = cell.trim();
+        let number = match parse_number(cell)? {
+            NumberKind::NonFinite => return None,
+            NumberKind::Decimal => Cow::Borrowed(cell),
+            // Written out in full, which `f64` does without an exponent
+            NumberKind::Scientific => Cow::Owned(cell.parse::<f64>().ok()?.to_string()),
+        };
+
+        let formatted = round_decimal(&number, self.decimals);
+        Some(match self.thousands_separator {
+            Some(separator) => group_thousands(&formatted, separator).unwrap_or(formatted),
+            None => formatted,
+        })
+    }
+}
+
+/// Round `number`, written in decimal notation, to `decimals` places, half
+/// away from zero. Working on the digits, rather than an `f64`, means a
+/// number like `1.005` rounds up as written.
+fn round_decimal(number: &str, decimals: usize) -> String {
+    let (negative, unsigned) = match number.strip_prefix(['-', '+']) {
+        Some(rest) => (number.starts_with('-'), rest),
+        None => (false, number),
+    };
+    let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+
+    let mut digits: Vec<u8> = integer.bytes().chain(fraction.bytes()).collect();
+    let kept = integer.len() + decimals;
+    let round_up = digits.get(kept).is_some_and(|&digit| digit >= b'5');
+    digits.resize(kept, b'0');
+
+    if round_up {
+        let carried = digits.iter_mut().rev().all(|digit| {
+            if *digit == b'9' {
+                *digit = b'0';
+                true
+            } else {
+                *digit += 1;
+                false
+            }
+        });
+        if carried {
+            digits.insert(0, b'1');
+        }
+    }
+
+    let point = digits.len() - decimals;
+    let integer = std::str::from_utf8(&digits[..point]).unwrap_or_default();
+    let integer = match integer.trim_start_matches('0') {
+        "" => "0",
+        integer => integer,
+    };
+    let fraction = std::str::from_utf8(&digits[point..]).unwrap_or_default();
+
+    let mut rounded = String::with_capacity(digits.len() + 2);
+    if negative && digits.iter().any(|&digit| digit != b'0') {
+        rounded.push('-');
+    }
+    rounded.push_str(integer);
+    if decimals > 0 {
+        rounded.push('.');
+        rounded.push_str(fraction);
+    }
+    rounded
+}
+
+impl FromStr for NumberFormat {
+    type Err = CsvMdError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            CsvMdError::InvalidConfig(format!(
+                "invalid number format `{}`, expected a pattern like `0.00` or `#,##0.00`",
+                s
+            ))
+        };
+
+        let (integer, fraction) = match s.split_once('.') {
+            Some((_, "")) => return Err(invalid()),
+            Some(parts) => parts,
+            None => (s, ""),
+        };
+        if !integer.ends_with(['0', '#']) || !fraction.chars().all(|c| c == '0') {
+            return Err(invalid());
+        }
+
+        let mut thousands_separator = None;
+        for c in integer.chars().filter(|&c| c != '0' && c != '#') {
+            if thousands_separator.is_some_and(|separator| separator != c) {
+                return Err(invalid());
+            }
+            thousands_separator = Some(c);
+        }
+
+        Ok(Self {
+            decimals: fraction.len(),
+            thousands_separator,
+        })
+    }
+}
+
 /// Configuration for CSV to Markdown conversion.
-#[derive(Debug, Clone)]
+///
+/// Configurations can be compared and hashed, so they can be used as part of
+/// the key when caching conversions.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Config {
     /// Whether the CSV has headers (affects separator line placement).
     pub has_headers: bool,
@@ -51,8 +405,319 @@ pub struct Config {
     pub flexible: bool,
     /// CSV field delimiter character.
     pub delimiter: u8,
+    /// Choose the delimiter from the start of the input instead of using
+    /// `delimiter`. See [`detect_delimiter`].
+    pub detect_delimiter: bool,
+    /// Decide whether the first row is a header from its cells instead of
+    /// using `has_headers`. See [`detect_header`].
+    pub detect_header: bool,
+    /// Read the delimiter from an Excel `sep=` line, like `sep=;`, at the
+    /// start of the input, leaving the line out of the table. This takes
+    /// precedence over `delimiter` and `detect_delimiter`.
+    pub honor_sep_directive: bool,
+    /// Fail with [`CsvMdError::FieldTooLarge`] when a field is larger than
+    /// this many bytes, such as when a quote that's never closed swallows the
+    /// rest of the input. Fields are checked as they're read, so the
+    /// conversion fails before much more than this of a field is held in
+    /// memory.
+    pub max_field_bytes: Option<usize>,
+    /// Remove byte order marks (U+FEFF) from anywhere in a cell, not just the
+    /// start of the input, such as those left at the start of each section
+    /// when BOM-prefixed files are joined together. They're removed once a
+    /// record has been read, so a BOM before a quote still stops the field
+    /// being read as quoted.
+    pub strip_all_bom: bool,
+    /// Pass input which already looks like a Markdown table, with a header
+    /// line followed by a separator line like `| --- |`, through unchanged
+    /// instead of converting it again.
+    pub detect_markdown: bool,
+    /// Treat each line of the input as a row with a single cell, without
+    /// splitting on the delimiter or handling quotes.
+    pub single_column_mode: bool,
+    /// Split each line on runs of the delimiter, treating them as a single
+    /// separator, for data lined up with spaces like `a   b   c`. Empty
+    /// fields are left out, so leading and trailing delimiters are ignored.
+    /// This splits lines without the CSV parser, so quotes aren't handled and
+    /// fields can't contain the delimiter or newlines.
+    pub collapse_delimiters: bool,
+    /// Without `has_headers`, write a header row naming the columns
+    /// `Column 1`, `Column 2` and so on, so the table still has a header.
+    /// Not used with `column_tables`.
+    pub synthetic_headers: bool,
     /// Header alignment for Markdown table.
     pub header_alignment: HeaderAlignment,
+    /// Wrap the text of each header cell in `**`, for contexts where the
+    /// header row isn't styled.
+    pub bold_headers: bool,
+    /// Wrap the text of the first cell of each data row in `**`, so the first
+    /// column reads as row labels. Unless `column_alignments` gives it an
+    /// alignment, the first column is left-aligned whatever the
+    /// `header_alignment`.
+    pub bold_first_column: bool,
+    /// Alignment for each column, in order. Columns without an entry use
+    /// `header_alignment`.
+    pub column_alignments: Vec<HeaderAlignment>,
+    /// Read the row after the header as alignment markers for each column,
+    /// like `:-:` or `-:`, instead of as data. The markers replace
+    /// `column_alignments`, and an empty marker uses `header_alignment`.
+    /// Requires `has_headers`.
+    pub alignment_row: bool,
+    /// The zero-based row, counting the header as row 0, holding a type for
+    /// each column, like `int` or `string`. Numeric and date types are
+    /// right-aligned and other types use `header_alignment`. Like the
+    /// alignment row, it replaces `column_alignments` and isn't output.
+    pub types_row: Option<usize>,
+    /// Right-align columns where every non-empty data cell is a number,
+    /// unless `column_alignments` or the alignment row already gives them an
+    /// alignment. Scientific notation, `NaN` and infinity count as numbers.
+    pub auto_align_numeric: bool,
+    /// Group the digits of numbers into thousands with this separator, like
+    /// `1,234,567.89`, in columns where every non-empty data cell is a
+    /// number. Numbers in scientific notation, `NaN`, infinity and integers
+    /// with a leading zero, like the code `0123`, are left as they are.
+    pub thousands_separator: Option<char>,
+    /// How to format the numbers in each column, in order. Only columns where
+    /// every non-empty data cell is a number are formatted, and cells which
+    /// don't hold a finite number, like `NaN`, are left as they are.
+    pub column_number_format: Vec<Option<NumberFormat>>,
+    /// Reformat the dates in columns where every non-empty data cell is a
+    /// date, with this strftime pattern like `%Y-%m-%d`. Slashed dates like
+    /// `01/02/2024` are read day first unless a date in the column only
+    /// makes sense month first.
+    #[cfg(feature = "dates")]
+    pub date_format: Option<String>,
+    /// Zero-based indices of output columns to follow each number with a bar
+    /// of block characters, like `40 ███▌`, whose length shows where the
+    /// value falls between the smallest and largest in its column. Cells
+    /// which don't hold a finite number are left as they are.
+    pub sparkline_columns: Vec<usize>,
+    /// Write an HTML comment like `<!-- csvmd:widths=12,4,20 -->` before the
+    /// table, giving the display width of the widest cell in each column for
+    /// tools which re-render the output.
+    pub emit_width_hints: bool,
+    /// Number of dashes in each cell of the header separator line, not
+    /// counting alignment colons. Values below 1 are treated as 1.
+    pub separator_dashes: usize,
+    /// Write a space either side of the contents of each cell.
+    pub cell_padding: bool,
+    /// End the output with a newline.
+    pub trailing_newline: bool,
+    /// Write a blank line after the output, to separate it from whatever
+    /// follows. With `trailing_newline` off, this ends the output with a
+    /// single newline instead.
+    pub trailing_blank_line: bool,
+    /// Write an attribution line after the table, like
+    /// `_Generated from data.csv on 2024-01-01T12:00:00Z_`, with the time in
+    /// UTC. The file name comes from `source_name`, and is left out without
+    /// one, like when reading stdin.
+    pub source_footer: bool,
+    /// The name of the file being converted, for `source_footer`.
+    /// [`convert_file`] fills it in from the path when it's not set.
+    pub source_name: Option<String>,
+    /// Write the smallest valid table, as if `cell_padding` and
+    /// `trailing_newline` were off and `separator_dashes` was 1, like
+    /// `|Name|Age|\n|-|-|\n|John|25|`.
+    pub minify: bool,
+    /// Write an Emacs Org-mode table instead of a Markdown one: the separator
+    /// line joins columns with `+`, like `|-----+-----|`, without alignment
+    /// markers, and cells are escaped with [`escape::OrgEscaper`].
+    pub org_mode: bool,
+    /// Where to write the header separator line. Ignored without a header row.
+    pub separator_position: SeparatorPosition,
+    /// Which side of a row shorter than the table its empty cells are added
+    /// on. With `row_numbers`, the number stays in the first column.
+    pub pad_side: PadSide,
+    /// Names of the columns to include, in output order. Requires `has_headers`.
+    ///
+    /// When a name appears more than once in the header, every matching column
+    /// is included in its original order, unless `first_match_only` is set.
+    pub columns: Option<Vec<String>>,
+    /// When selecting `columns` by name, only include the first column matching
+    /// each name rather than all duplicates.
+    pub first_match_only: bool,
+    /// New names for header cells, keyed by their name in the input, like
+    /// `usr_id` to `User ID`. Other header cells and the data rows are left
+    /// as they are. Columns are still selected by their names in the input.
+    /// Requires `has_headers`.
+    pub rename_headers: BTreeMap<String, String>,
+    /// Which whitespace to trim from the start and end of each cell, before
+    /// any other cell options are applied.
+    pub trim_mode: TrimMode,
+    /// Values, like `\\N`, `NULL` or `NA`, which mean a cell is empty. Data
+    /// cells matching one, ignoring whitespace around them, are written as
+    /// empty cells, so they're also left out of options like
+    /// `auto_align_numeric` and `summary_row`. Header cells are left as they
+    /// are.
+    pub null_values: Vec<String>,
+    /// Match `null_values` ignoring ASCII case, so `NULL` also matches `null`.
+    pub null_case_insensitive: bool,
+    /// In HTML output, write empty data cells, and cells matching one of the
+    /// `null_values`, as `<td class="empty"></td>`, so missing data can be
+    /// styled differently. Markdown tables have no way to mark a cell, so
+    /// this has no effect on them.
+    pub mark_empty_cells: bool,
+    /// When set, tab characters in cells are expanded to this many spaces.
+    pub tab_width: Option<usize>,
+    /// When set, only data rows matching the filter are included.
+    pub filter: Option<RowFilter>,
+    /// Move data rows whose first cell is one of these values, like `Total`,
+    /// to the bottom of the table, keeping them in their original order. The
+    /// order of the other rows is unchanged.
+    pub pin_rows_to_bottom: Vec<String>,
+    /// Only write the first and last data rows, given as `(head, tail)`
+    /// counts, like `df.head()` and `df.tail()` together. When rows are left
+    /// out between them, a row with `…` in every column shows where. Every row
+    /// is read, but only the last `tail` rows are kept in memory at once,
+    /// unless there's a `summary_row`, which covers the rows left out too.
+    pub preview: Option<(usize, usize)>,
+    /// Fail with [`CsvMdError::TooManyRowsForTerminal`] as soon as there are
+    /// more than this many data rows, after `filter` and `preview`, without
+    /// reading the rest of the input. This is meant for output to a terminal,
+    /// where a table that long is more likely a mistake than wanted.
+    pub tty_row_limit: Option<usize>,
+    /// Minimum number of columns in the table. Narrower tables are padded with
+    /// empty cells.
+    pub min_columns: Option<usize>,
+    /// Add a `#` column at the start of the table numbering the data rows.
+    /// Not used with `column_tables`. With `group_by`, each group's rows are
+    /// numbered from `row_number_start`.
+    pub row_numbers: bool,
+    /// The number given to the first data row when `row_numbers` is set.
+    pub row_number_start: i64,
+    /// Pad row numbers with leading zeros to the width of the largest, like
+    /// `001`, `002` and so on, so the column doesn't change width. Every row
+    /// is read before the table is written.
+    pub zero_pad_row_numbers: bool,
+    /// Split the output into several tables of at most this many data rows,
+    /// each starting with the header row. Not used with `column_tables`. With
+    /// `group_by`, each group's table is split.
+    pub rows_per_table: Option<usize>,
+    /// The line written between tables when `rows_per_table` is set, like
+    /// `---` or `<div style="page-break-after: always"></div>`.
+    pub page_divider: String,
+    /// Leave out columns at the end of the table which are empty in every
+    /// row, like the extra column created by a trailing comma on each line.
+    pub trim_trailing_empty_columns: bool,
+    /// Zero-based indices of output columns holding boolean values. Used with
+    /// `boolean_as_checkbox`.
+    pub boolean_columns: Vec<usize>,
+    /// Render boolean values as task-list checkboxes: `[x]` for true and `[ ]`
+    /// for false.
+    ///
+    /// Applies to `boolean_columns`, or if that's empty, to every column where
+    /// all non-empty data cells are booleans. `true`/`false`, `yes`/`no` and
+    /// `1`/`0` are recognised in any case. Other values are left untouched.
+    pub boolean_as_checkbox: bool,
+    /// Add a leading column of task-list checkboxes, `[x]` where the cell in
+    /// this zero-based input column is true and `[ ]` otherwise. Booleans are
+    /// recognised like `boolean_as_checkbox`, and the column is headed with
+    /// the source column's name.
+    pub checklist_from: Option<usize>,
+    /// Leave the `checklist_from` column itself out of the output.
+    pub checklist_hide_source: bool,
+    /// What to output when `has_headers` is set but there are no data rows.
+    pub empty_table_behavior: EmptyTableBehavior,
+    /// Write this message instead of a table for input with no rows, or only
+    /// whitespace, like `_No data_`. Without it, empty input gives no output.
+    pub empty_input_message: Option<String>,
+    /// Instead of one table, output a small `Row | Value` table for each
+    /// column, under a heading with the column's name.
+    ///
+    /// This needs the whole table in memory, so the streaming functions buffer
+    /// their input when it's set.
+    pub column_tables: bool,
+    /// Instead of the data, write a `Column | Inferred Type` table describing
+    /// each column, with types like `Integer`, `Number`, `Boolean` and `Text`.
+    /// A type is only known once every cell in its column has been seen.
+    pub schema_table: bool,
+    /// Write a separate table for each distinct value in this column, under a
+    /// heading with the value, in the order the values first appear. The
+    /// column refers to the table as written, after any `columns` selection,
+    /// and must be in it. Every row is gathered into its group before the first table is written.
+    pub group_by: Option<ColumnRef>,
+    /// With `group_by`, start with a list of links to each group's heading.
+    pub toc: bool,
+    /// Add a final row to the table with this aggregate of each column where
+    /// every non-empty data cell is a finite number, labelled in the first
+    /// column. Other columns are left empty, as are empty cells out of the
+    /// aggregate. Not used with `column_tables` or `group_by`. When streaming,
+    /// the input is buffered, since the row can't be worked out until the end.
+    pub summary_row: Option<SummaryKind>,
+    /// When set, a YAML front matter block with these keys is written before
+    /// the table, along with `columns` and `rows` counts unless they're given.
+    pub front_matter: Option<BTreeMap<String, String>>,
+    /// A caption for the table. Markdown tables are preceded by it as a bold
+    /// paragraph, like `**Sales by region**`, written as given so it can
+    /// contain other Markdown. HTML tables start with a `<caption>` element.
+    pub caption: Option<String>,
+    /// Maximum number of newlines per cell to convert to `<br>` tags. Any
+    /// further newlines are collapsed into spaces.
+    pub max_newlines_per_cell: Option<usize>,
+    /// Number of rows at the start of the input making up the header, when
+    /// `has_headers` is set. Multiple header rows are merged into one.
+    pub header_rows: usize,
+    /// Zero-based index of the record holding the header, when `has_headers`
+    /// is set. Records before it, like a title, are left out. Blank lines
+    /// aren't counted, as the CSV parser skips them, so after a title and a
+    /// blank line the header's index is 1, not 2.
+    pub header_row_index: Option<usize>,
+    /// Separator between the cells of each column when merging multiple
+    /// header rows. Empty cells are skipped.
+    pub header_row_separator: String,
+    /// Move data cells longer than this many characters into footnotes,
+    /// leaving a numbered `[^n]` reference in the cell. The footnotes are
+    /// written after the table.
+    pub footnote_over: Option<usize>,
+    /// Keep only the columns whose header matches this regular expression,
+    /// in their original order. Requires a header row.
+    #[cfg(feature = "regex")]
+    pub select_columns_regex: Option<String>,
+    /// What to do with control characters in cells other than newlines and
+    /// carriage returns.
+    pub control_char_policy: ControlCharPolicy,
+    /// Prefix cells starting with `=`, `+`, `-` or `@` with a `'`, so they
+    /// aren't run as formulas if the output ends up in a spreadsheet.
+    pub sanitize_formulas: bool,
+    /// Escape a `>`, `#`, `-`, `+` or `1.` at the start of a cell, so lenient
+    /// renderers don't read it as a blockquote, heading or list.
+    pub escape_block_markers: bool,
+    /// How pipe characters inside cells are escaped.
+    pub pipe_escape: PipeEscape,
+    /// When streaming from a non-seekable input, copy it to a temporary file
+    /// to read twice instead of holding all of it in memory.
+    pub spill_to_disk: bool,
+    /// Stream rows in a single pass, taking the number of columns from the
+    /// first row instead of reading the input twice. A later row with more
+    /// columns gives a [`CsvMdError::ColumnCountChanged`] error.
+    ///
+    /// Options which need to see every row before writing anything, like
+    /// `front_matter` or `auto_align_numeric`, can't be used with it.
+    pub optimistic_streaming: bool,
+    /// Wrap the output in a callout block, starting with a line like
+    /// `> [!NOTE]` and with every following line prefixed by `> `.
+    pub callout: Option<CalloutKind>,
+    /// Check the output against the rules for GitHub Flavored Markdown
+    /// tables before returning it, giving a [`CsvMdError::InvalidOutput`]
+    /// error if a table has no separator row or rows with different numbers
+    /// of cells. When streaming, the output is buffered so it can be checked.
+    pub validate_output: bool,
+    /// Pad the cells of each table to the width of the widest cell in their
+    /// column, so the columns line up in the raw Markdown. Like
+    /// `validate_output`, this works on the finished output, so streaming
+    /// buffers it.
+    pub pretty: bool,
+    /// With `pretty`, right-justify numeric cells within their padding, so
+    /// they line up by their last digit in the raw Markdown. Text stays
+    /// left-justified, and the alignment markers are unchanged.
+    pub numeric_pad_right: bool,
+    /// Fit each table within this many columns, so no line of it is wider.
+    /// When the table is too wide, the space left after the pipes and padding
+    /// is shared between the columns in proportion to their widths, and cells
+    /// too long for their share are cut short with `…`. Columns aren't made
+    /// narrower than their separator's dashes, so a table with too many
+    /// columns can still be wider. Like `pretty`, this works on the finished
+    /// output, and the two can be used together.
+    pub max_table_width: Option<usize>,
 }
 
 impl Default for Config {
@@ -61,587 +726,7200 @@ impl Default for Config {
             has_headers: true,
             flexible: true,
             delimiter: b',',
+            detect_delimiter: false,
+            detect_header: false,
+            honor_sep_directive: true,
+            max_field_bytes: None,
+            strip_all_bom: false,
+            detect_markdown: false,
+            single_column_mode: false,
+            collapse_delimiters: false,
+            synthetic_headers: false,
             header_alignment: HeaderAlignment::Left,
+            bold_headers: false,
+            bold_first_column: false,
+            column_alignments: Vec::new(),
+            alignment_row: false,
+            types_row: None,
+            auto_align_numeric: false,
+            thousands_separator: None,
+            column_number_format: Vec::new(),
+            sparkline_columns: Vec::new(),
+            #[cfg(feature = "dates")]
+            date_format: None,
+            emit_width_hints: false,
+            separator_dashes: 3,
+            cell_padding: true,
+            trailing_newline: true,
+            trailing_blank_line: false,
+            source_footer: false,
+            source_name: None,
+            minify: false,
+            org_mode: false,
+            separator_position: SeparatorPosition::default(),
+            pad_side: PadSide::default(),
+            columns: None,
+            first_match_only: false,
+            rename_headers: BTreeMap::new(),
+            trim_mode: TrimMode::None,
+            null_values: Vec::new(),
+            null_case_insensitive: false,
+            mark_empty_cells: false,
+            tab_width: None,
+            filter: None,
+            pin_rows_to_bottom: Vec::new(),
+            preview: None,
+            tty_row_limit: None,
+            min_columns: None,
+            row_numbers: false,
+            row_number_start: 1,
+            zero_pad_row_numbers: false,
+            rows_per_table: None,
+            page_divider: "---".to_string(),
+            trim_trailing_empty_columns: false,
+            boolean_columns: Vec::new(),
+            boolean_as_checkbox: false,
+            checklist_from: None,
+            checklist_hide_source: false,
+            empty_table_behavior: EmptyTableBehavior::default(),
+            empty_input_message: None,
+            column_tables: false,
+            schema_table: false,
+            group_by: None,
+            toc: false,
+            summary_row: None,
+            front_matter: None,
+            caption: None,
+            max_newlines_per_cell: None,
+            header_rows: 1,
+            header_row_index: None,
+            header_row_separator: " / ".to_string(),
+            footnote_over: None,
+            #[cfg(feature = "regex")]
+            select_columns_regex: None,
+            control_char_policy: ControlCharPolicy::Keep,
+            sanitize_formulas: false,
+            escape_block_markers: false,
+            pipe_escape: PipeEscape::Backslash,
+            spill_to_disk: false,
+            optimistic_streaming: false,
+            callout: None,
+            validate_output: false,
+            pretty: false,
+            numeric_pad_right: false,
+            max_table_width: None,
         }
     }
 }
 
-/// Convert CSV data to a Markdown table string.
-///
-/// This function reads CSV data from the provided reader and converts it to
-/// a Markdown table format. It processes the data in a streaming fashion to
-/// handle large files efficiently.
-///
-/// # Arguments
-///
-/// * `input` - A reader containing CSV data
-/// * `config` - Configuration options for the conversion
-///
-/// # Returns
-///
-/// A string containing the formatted Markdown table.
-///
-/// # Errors
-///
-/// Returns `CsvMdError` if:
-/// - The input cannot be read
-/// - The CSV data is malformed
-/// - Memory allocation fails during processing
-///
-/// # Example
-///
-/// ```rust
-/// use csvmd::{csv_to_markdown, Config};
-/// use std::io::Cursor;
-///
-/// let csv_data = "Name,Age\nJohn,25\nJane,30";
-/// let input = Cursor::new(csv_data);
-/// let config = Config::default();
-/// let result = csv_to_markdown(input, config)?;
-/// assert!(result.contains("| Name | Age |"));
-/// # Ok::<(), csvmd::error::CsvMdError>(())
-/// ```
-pub fn csv_to_markdown<R: Read>(input: R, config: Config) -> Result<String> {
-    let mut reader = ReaderBuilder::new()
-        .has_headers(false)
-        .flexible(config.flexible)
-        .delimiter(config.delimiter)
-        .from_reader(input);
+impl Config {
+    /// Check the configuration for values which can't produce a valid table.
+    ///
+    /// Conversions don't call this themselves, and do their best with any
+    /// configuration, so it's useful for rejecting bad input early.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use csvmd::Config;
+    ///
+    /// assert!(Config::default().validate().is_ok());
+    ///
+    /// let config = Config {
+    ///     separator_dashes: 0,
+    ///     ..Config::default()
+    /// };
+    /// assert!(config.validate().is_err());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `CsvMdError::InvalidConfig` describing the first problem found.
+    pub fn validate(&self) -> Result<()> {
+        if self.separator_dashes == 0 {
+            return Err(CsvMdError::InvalidConfig(
+                "separator_dashes must be at least 1, so every alignment keeps a dash".to_string(),
+            ));
+        }
 
-    let mut records: Vec<Vec<String>> = Vec::new();
-    let mut max_cols = 0;
+        Ok(())
+    }
 
-    // First pass: collect all records and determine max column count
-    for result in reader.records() {
-        let record = result?;
-        let row: Vec<String> = record.iter().map(escape_markdown_cell).collect();
+    /// How many bytes at the start of the input the options in use need to
+    /// look at before converting it. Looking for a `sep=` line only needs the
+    /// first few, so streaming can start without waiting for more input.
+    pub fn sniffed_bytes(&self) -> u64 {
+        if self.detect_markdown || self.detect_delimiter || self.detect_header {
+            8 * 1024
+        } else if self.honor_sep_directive {
+            // The longest `sep=` line, like `sep=;\r\n`
+            7
+        } else {
+            0
+        }
+    }
 
-        max_cols = max_cols.max(row.len());
-        records.push(row);
+    /// Whether `cell`, ignoring whitespace around it, is one of the
+    /// `null_values`.
+    fn is_null(&self, cell: &str) -> bool {
+        let cell = cell.trim();
+        self.null_values.iter().any(|null| {
+            if self.null_case_insensitive {
+                null.eq_ignore_ascii_case(cell)
+            } else {
+                null == cell
+            }
+        })
     }
 
-    if records.is_empty() {
-        return Ok(String::new());
+    /// Whether to write a space either side of each cell.
+    fn pads_cells(&self) -> bool {
+        self.cell_padding && !self.minify
     }
 
-    // Estimate output size to reduce allocations
-    let estimated_size = estimate_output_size(&records, max_cols);
-    let mut output = String::with_capacity(estimated_size);
+    /// Number of dashes in each cell of the header separator line.
+    fn separator_dash_count(&self) -> usize {
+        // Every alignment needs at least one dash, or `:---:` would become `::`
+        if self.minify {
+            1
+        } else {
+            self.separator_dashes.max(1)
+        }
+    }
 
-    // Write the table
-    for (i, record) in records.iter().enumerate() {
-        write_table_row(&mut output, record, max_cols)?;
+    /// Whether to end the output with a newline.
+    fn ends_with_newline(&self) -> bool {
+        self.trailing_newline && !self.minify
+    }
 
-        // Add header separator after first row if configured
-        if i == 0 && config.has_headers {
-            write_header_separator(&mut output, max_cols, config.header_alignment)?;
+    /// Whether the options in use need every row in memory before output can
+    /// be written, ruling out the two-pass streaming approach.
+    fn requires_buffering(&self) -> bool {
+        self.column_tables
+            || self.schema_table
+            || self.group_by.is_some()
+            || self.summary_row.is_some()
+            || self.validate_output
+            || self.pretty
+            || self.max_table_width.is_some()
+    }
+
+    /// Whether the options in use need every row to be read before the first
+    /// line of output can be written, ruling out optimistic streaming.
+    fn requires_first_pass(&self) -> bool {
+        #[cfg(feature = "dates")]
+        if self.date_format.is_some() {
+            return true;
         }
+
+        self.front_matter.is_some()
+            || (self.boolean_as_checkbox && self.boolean_columns.is_empty())
+            || self.emit_width_hints
+            || self.trim_trailing_empty_columns
+            || self.alignment_row
+            || self.types_row.is_some()
+            || self.empty_input_message.is_some()
+            || self.empty_table_behavior != EmptyTableBehavior::HeaderOnly
+            || !self.sparkline_columns.is_empty()
+            || (self.row_numbers && self.zero_pad_row_numbers)
+            || self.detects_numeric_columns()
     }
 
-    Ok(output)
+    /// Whether the options in use need to know which columns hold numbers.
+    fn detects_numeric_columns(&self) -> bool {
+        self.auto_align_numeric
+            || self.thousands_separator.is_some()
+            || self.column_number_format.iter().any(Option::is_some)
+    }
 }
 
-/// Convert CSV data to Markdown and write directly to output.
-///
-/// This streaming version uses a two-pass approach:
-/// 1. First pass: determine the maximum column count
-/// 2. Second pass: stream output with correct table formatting
-///
-/// This provides memory efficiency for large files while ensuring correct
-/// Markdown table structure.
-///
-/// # Arguments
-///
-/// * `input` - A reader containing CSV data
-/// * `output` - A writer where the Markdown table will be written
-/// * `config` - Configuration options for the conversion
-///
-/// # Errors
+/// Turns parsed CSV records into escaped table rows, applying the row and
+/// column options from a [`Config`].
 ///
-/// Returns `CsvMdError` if reading, parsing, or writing fails.
-pub fn csv_to_markdown_streaming<R: Read, W: Write>(
-    mut input: R,
-    mut output: W,
-    config: Config,
-) -> Result<()> {
-    // First, we need to read the input to determine max columns
-    // Since we need to read twice, we'll read all data into memory first
-    let mut buffer = Vec::new();
-    input.read_to_end(&mut buffer)?;
+/// Every conversion path feeds its records through a pipeline so that the
+/// counting pass and the output pass agree on the shape of the table.
+struct RowPipeline<'a> {
+    config: Cow<'a, Config>,
+    /// Indices of the columns to keep, resolved from the header row.
+    selection: Option<Vec<usize>>,
+    /// Index of the column the row filter applies to.
+    filter_column: Option<usize>,
+    /// Number of records before the header left out so far, for
+    /// `header_row_index`.
+    records_skipped: usize,
+    /// Header rows waiting to be merged, when `header_rows` is more than one.
+    pending_header: Vec<Vec<String>>,
+    /// Number of records processed so far, counting a merged header as one.
+    rows_seen: usize,
+    /// Column alignments read from the alignment row, if there was one.
+    alignments: Option<Vec<HeaderAlignment>>,
+    /// Rows held back to write at the bottom, for `pin_rows_to_bottom`.
+    pinned: VecDeque<Vec<String>>,
+    /// Number of data rows seen so far, for `preview`.
+    data_rows: usize,
+    /// The last data rows seen, for the tail of a `preview`.
+    tail: VecDeque<Vec<String>>,
+    /// Whether rows were left out between the head and tail of a `preview`.
+    elided: bool,
+    /// Every data row, for a `summary_row` of a `preview`, which leaves some
+    /// of them out.
+    summarised: Option<Vec<Vec<String>>>,
+    /// The most cells in any row, for the width of the elision row.
+    widest: usize,
+    /// Whether to escape cells for a Markdown or Org-mode table, rather than
+    /// leaving them for another format to escape.
+    escape: bool,
+}
 
-    // First pass: determine max column count
-    let max_cols = {
-        let cursor = std::io::Cursor::new(&buffer);
-        let mut reader = ReaderBuilder::new()
-            .has_headers(false)
-            .flexible(config.flexible)
-            .delimiter(config.delimiter)
-            .from_reader(cursor);
+impl<'a> RowPipeline<'a> {
+    fn new(config: &'a Config) -> Self {
+        Self::with_config(Cow::Borrowed(config))
+    }
 
-        let mut max_cols = 0;
-        for result in reader.records() {
-            let record = result?;
-            max_cols = max_cols.max(record.len());
+    fn with_config(config: Cow<'a, Config>) -> Self {
+        let summarised = (config.summary_row.is_some() && config.preview.is_some()).then(Vec::new);
+        Self {
+            config,
+            selection: None,
+            filter_column: None,
+            records_skipped: 0,
+            pending_header: Vec::new(),
+            rows_seen: 0,
+            alignments: None,
+            pinned: VecDeque::new(),
+            data_rows: 0,
+            tail: VecDeque::new(),
+            elided: false,
+            summarised,
+            widest: 0,
+            escape: true,
         }
-        max_cols
-    };
+    }
 
-    // Second pass: stream output with correct column count
-    let cursor = std::io::Cursor::new(&buffer);
-    let mut reader = ReaderBuilder::new()
-        .has_headers(false)
-        .flexible(config.flexible)
-        .delimiter(config.delimiter)
-        .from_reader(cursor);
+    /// A pipeline which leaves cells unescaped, for formats other than
+    /// Markdown and Org-mode.
+    fn unescaped(config: &'a Config) -> Self {
+        Self {
+            escape: false,
+            ..Self::new(config)
+        }
+    }
 
-    let mut first_row = true;
+    /// Process every record from `records`, yielding the rows to write.
+    fn rows<S: RecordSource>(self, records: S) -> ProcessedRows<'a, S> {
+        ProcessedRows {
+            pipeline: self,
+            records,
+            finished: false,
+            yielded: 0,
+        }
+    }
 
-    for result in reader.records() {
-        let record = result?;
-        let row: Vec<String> = record.iter().map(escape_markdown_cell).collect();
+    /// Process a single record into the cells that should be written.
+    ///
+    /// Returns `None` if the record should be left out of the table.
+    fn process(&mut self, record: Vec<String>) -> Result<Option<Vec<String>>> {
+        if let (Some(index), true) = (self.config.header_row_index, self.config.has_headers) {
+            if self.records_skipped < index {
+                self.records_skipped += 1;
+                return Ok(None);
+            }
+        }
 
-        // Write the row with correct column count
-        write_table_row_to_writer(&mut output, &row, max_cols)?;
+        if self.rows_seen == 0 && self.config.has_headers && self.config.header_rows > 1 {
+            self.pending_header.push(record);
+            if self.pending_header.len() < self.config.header_rows {
+                return Ok(None);
+            }
+            return self.finish();
+        }
 
-        // Add header separator after first row if configured
-        if first_row && config.has_headers {
-            write_header_separator_to_writer(&mut output, max_cols, config.header_alignment)?;
-            first_row = false;
+        self.process_record(&record)
+    }
+
+    /// Process anything still buffered once every record has been read, a
+    /// row at a time until it returns `None`.
+    fn finish(&mut self) -> Result<Option<Vec<String>>> {
+        if self.pending_header.is_empty() {
+            if std::mem::take(&mut self.elided) {
+                return Ok(Some(vec!["…".to_string(); self.widest]));
+            }
+            return Ok(self.tail.pop_front().or_else(|| self.pinned.pop_front()));
         }
+
+        let header = merge_header_rows(&self.pending_header, &self.config.header_row_separator);
+        self.pending_header.clear();
+        self.process_record(&header)
     }
 
-    output.flush()?;
-    Ok(())
-}
+    fn process_record(&mut self, record: &[String]) -> Result<Option<Vec<String>>> {
+        let is_header = self.rows_seen == 0 && self.config.has_headers;
+        if self.rows_seen == 0 {
+            if self.config.alignment_row && !self.config.has_headers {
+                return Err(CsvMdError::InvalidConfig(
+                    "an alignment row requires a header row".to_string(),
+                ));
+            }
+            self.selection = resolve_columns(record, &self.config)?;
+            self.filter_column = resolve_filter_column(record, &self.config)?;
+        }
+        self.rows_seen += 1;
 
-/// Convert CSV data to Markdown and write directly to output using a seekable input.
-///
-/// For seekable inputs (e.g., files), this variant avoids buffering the entire
-/// input into memory. It performs two passes by rewinding the reader between
-/// passes to compute the maximum column count and then write the output.
-pub fn csv_to_markdown_streaming_seekable<R: Read + Seek, W: Write>(
-    mut input: R,
-    mut output: W,
-    config: Config,
-) -> Result<()> {
-    // First pass: determine max column count
-    input.seek(SeekFrom::Start(0))?;
-    let mut reader = ReaderBuilder::new()
-        .has_headers(false)
-        .flexible(config.flexible)
-        .delimiter(config.delimiter)
-        .from_reader(&mut input);
+        if self.config.alignment_row && self.rows_seen == 2 {
+            self.alignments = Some(self.parse_alignment_row(record)?);
+            return Ok(None);
+        }
+
+        if self.config.types_row == Some(self.rows_seen - 1) {
+            self.alignments = Some(self.parse_types_row(record)?);
+            return Ok(None);
+        }
+
+        if let (Some(filter), Some(column), false) =
+            (&self.config.filter, self.filter_column, is_header)
+        {
+            if !filter.matches(record.get(column).map(String::as_str)) {
+                return Ok(None);
+            }
+        }
+
+        let record: Cow<[String]> = if is_header && !self.config.rename_headers.is_empty() {
+            Cow::Owned(
+                record
+                    .iter()
+                    .map(|cell| {
+                        let renamed = self.config.rename_headers.get(cell.trim());
+                        renamed.unwrap_or(cell).clone()
+                    })
+                    .collect(),
+            )
+        } else {
+            Cow::Borrowed(record)
+        };
+
+        let hidden = self.hidden_column();
+        let mut row: Vec<String> = match &self.selection {
+            Some(indices) => indices
+                .iter()
+                .filter(|&&i| Some(i) != hidden)
+                .map(|&i| {
+                    let cell = record.get(i).map_or("", String::as_str);
+                    process_cell(cell, &self.config, self.escape, is_header)
+                })
+                .collect(),
+            None => record
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| Some(i) != hidden)
+                .map(|(_, cell)| process_cell(cell, &self.config, self.escape, is_header))
+                .collect(),
+        };
+
+        if let Some(column) = self.config.checklist_from {
+            let cell = record.get(column).map_or("", String::as_str);
+            let checkbox = match (is_header, parse_bool(cell)) {
+                (true, _) => process_cell(cell, &self.config, self.escape, is_header),
+                (false, Some(true)) => "[x]".to_string(),
+                (false, _) => "[ ]".to_string(),
+            };
+            row.insert(0, checkbox);
+        }
+
+        // A record without any cells can't be written as a valid table row
+        if row.is_empty() {
+            return Ok(None);
+        }
+        self.widest = self.widest.max(row.len());
+
+        if let (Some(summarised), false) = (&mut self.summarised, is_header) {
+            summarised.push(row.clone());
+        }
+
+        let first = record.first().map_or("", |cell| cell.trim());
+        if !is_header
+            && self
+                .config
+                .pin_rows_to_bottom
+                .iter()
+                .any(|pin| pin == first)
+        {
+            self.pinned.push_back(row);
+            return Ok(None);
+        }
+
+        if let (Some((head, tail)), false) = (self.config.preview, is_header) {
+            self.data_rows += 1;
+            if self.data_rows > head {
+                self.tail.push_back(row);
+                if self.tail.len() > tail {
+                    self.tail.pop_front();
+                    self.elided = true;
+                }
+                return Ok(None);
+            }
+        }
 
-    let mut max_cols = 0;
-    for result in reader.records() {
-        let record = result?;
-        max_cols = max_cols.max(record.len());
+        Ok(Some(row))
     }
 
-    // Second pass: rewind and stream output with correct column count
-    drop(reader);
-    input.seek(SeekFrom::Start(0))?;
-    let mut reader = ReaderBuilder::new()
-        .has_headers(false)
-        .flexible(config.flexible)
-        .delimiter(config.delimiter)
-        .from_reader(&mut input);
+    /// Parse the alignment markers in `record` for each selected column.
+    fn parse_alignment_row(&self, record: &[String]) -> Result<Vec<HeaderAlignment>> {
+        self.selected_cells(record)
+            .into_iter()
+            .map(|marker| parse_alignment_marker(marker, self.config.header_alignment))
+            .collect()
+    }
 
-    let mut first_row = true;
-    for result in reader.records() {
-        let record = result?;
-        let row: Vec<String> = record.iter().map(escape_markdown_cell).collect();
-        write_table_row_to_writer(&mut output, &row, max_cols)?;
+    /// Parse the column types in `record` into an alignment for each
+    /// selected column.
+    fn parse_types_row(&self, record: &[String]) -> Result<Vec<HeaderAlignment>> {
+        self.selected_cells(record)
+            .into_iter()
+            .map(|name| type_alignment(name, self.config.header_alignment))
+            .collect()
+    }
 
-        if first_row && config.has_headers {
-            write_header_separator_to_writer(&mut output, max_cols, config.header_alignment)?;
-            first_row = false;
+    /// The cells of `record` in the output columns, without processing. The
+    /// checklist column, which isn't in the input, is given an empty cell.
+    fn selected_cells<'r>(&self, record: &'r [String]) -> Vec<&'r str> {
+        let hidden = self.hidden_column();
+        let mut cells: Vec<&str> = match &self.selection {
+            Some(indices) => indices
+                .iter()
+                .filter(|&&i| Some(i) != hidden)
+                .map(|&i| record.get(i).map_or("", String::as_str))
+                .collect(),
+            None => record
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| Some(i) != hidden)
+                .map(|(_, cell)| cell.as_str())
+                .collect(),
+        };
+        if self.config.checklist_from.is_some() {
+            cells.insert(0, "");
         }
+        cells
     }
 
-    output.flush()?;
-    Ok(())
+    /// The input column left out of the output because it's only used for
+    /// the checklist column.
+    fn hidden_column(&self) -> Option<usize> {
+        self.config
+            .checklist_from
+            .filter(|_| self.config.checklist_hide_source)
+    }
 }
 
-/// Escape Markdown special characters in a CSV cell.
-///
-/// This function handles:
-/// - Pipe characters (`|`) → escaped as `\|`
-/// - Newlines (`\n`) → converted to `<br>` tags
-/// - Carriage returns (`\r`) → removed
-fn escape_markdown_cell(field: &str) -> String {
-    field
-        .replace('|', "\\|")
-        .replace('\n', "<br>")
-        .replace('\r', "")
+/// Choose the alignment for a column from the name of its type, right-aligning
+/// numbers and dates and using `default` for other types.
+fn type_alignment(name: &str, default: HeaderAlignment) -> Result<HeaderAlignment> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "int" | "integer" | "float" | "double" | "decimal" | "number" | "numeric" | "date"
+        | "datetime" | "time" => Ok(HeaderAlignment::Right),
+        "string" | "str" | "text" | "bool" | "boolean" | "" => Ok(default),
+        _ => Err(CsvMdError::InvalidConfig(format!(
+            "`{}` in the types row isn't a known type, like `int`, `float`, `date` or `string`",
+            name
+        ))),
+    }
 }
 
-/// Write a table row to a string buffer.
-fn write_table_row(output: &mut String, row: &[String], max_cols: usize) -> Result<()> {
-    output.push('|');
+/// Parse a Markdown separator cell like `:-:` into an alignment, using
+/// `default` for an empty cell.
+fn parse_alignment_marker(marker: &str, default: HeaderAlignment) -> Result<HeaderAlignment> {
+    let marker = marker.trim();
+    if marker.is_empty() {
+        return Ok(default);
+    }
 
-    for i in 0..max_cols {
-        let cell = row.get(i).map(String::as_str).unwrap_or("");
-        write!(output, " {} |", cell)?;
+    if !marker.chars().all(|c| c == ':' || c == '-') {
+        return Err(CsvMdError::InvalidConfig(format!(
+            "invalid alignment marker `{}`, expected something like `:-`, `:-:` or `-:`",
+            marker
+        )));
     }
 
-    output.push('\n');
-    Ok(())
+    Ok(match (marker.starts_with(':'), marker.ends_with(':')) {
+        (true, true) if marker.len() > 1 => HeaderAlignment::Center,
+        (false, true) => HeaderAlignment::Right,
+        _ => HeaderAlignment::Left,
+    })
 }
 
-/// Write a table row directly to a writer.
-fn write_table_row_to_writer<W: Write>(
-    output: &mut W,
-    row: &[String],
-    max_cols: usize,
-) -> Result<()> {
-    write!(output, "|")?;
+/// Iterator over the rows produced by running records through a [`RowPipeline`].
+struct ProcessedRows<'a, S> {
+    pipeline: RowPipeline<'a>,
+    records: S,
+    finished: bool,
+    /// Number of rows yielded so far, for `tty_row_limit`.
+    yielded: usize,
+}
 
-    for i in 0..max_cols {
-        let cell = row.get(i).map(String::as_str).unwrap_or("");
-        write!(output, " {} |", cell)?;
+impl<S> ProcessedRows<'_, S> {
+    /// Column alignments read from the alignment row, once it has been processed.
+    fn alignments(&self) -> Option<&[HeaderAlignment]> {
+        self.pipeline.alignments.as_deref()
     }
 
-    writeln!(output)?;
-    Ok(())
+    /// Every data row, including those a `preview` leaves out, when they're
+    /// kept for a `summary_row`.
+    fn summarised(&self) -> Option<&[Vec<String>]> {
+        self.pipeline.summarised.as_deref()
+    }
 }
 
-/// Write the header separator line to a string buffer.
-fn write_header_separator(
-    output: &mut String,
-    max_cols: usize,
-    alignment: HeaderAlignment,
-) -> Result<()> {
-    output.push('|');
+impl<S: RecordSource> Iterator for ProcessedRows<'_, S> {
+    type Item = Result<Vec<String>>;
 
-    let separator = match alignment {
-        HeaderAlignment::Left => " --- |",
-        HeaderAlignment::Center => " :---: |",
-        HeaderAlignment::Right => " ---: |",
-    };
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let result = if self.finished {
+                match self.pipeline.finish() {
+                    Ok(None) => return None,
+                    result => result,
+                }
+            } else {
+                match self.records.next_record() {
+                    Some(Ok(record)) => self.pipeline.process(record),
+                    Some(Err(err)) => return Some(Err(err)),
+                    None => {
+                        self.finished = true;
+                        continue;
+                    }
+                }
+            };
 
-    for _ in 0..max_cols {
-        output.push_str(separator);
+            match result {
+                Ok(Some(row)) => {
+                    self.yielded += 1;
+                    let config = &self.pipeline.config;
+                    let data_rows = self.yielded - usize::from(config.has_headers);
+                    if let Some(limit) = config.tty_row_limit.filter(|&limit| data_rows > limit) {
+                        return Some(Err(CsvMdError::TooManyRowsForTerminal { limit }));
+                    }
+                    return Some(Ok(row));
+                }
+                Ok(None) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
     }
+}
 
-    output.push('\n');
-    Ok(())
+/// Merge several header rows into one, joining the non-empty cells in each
+/// column with `separator`.
+fn merge_header_rows(rows: &[Vec<String>], separator: &str) -> Vec<String> {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+
+    (0..columns)
+        .map(|column| {
+            rows.iter()
+                .filter_map(|row| row.get(column))
+                .map(|cell| cell.trim())
+                .filter(|cell| !cell.is_empty())
+                .collect::<Vec<_>>()
+                .join(separator)
+        })
+        .collect()
 }
 
-/// Write the header separator line directly to a writer.
-fn write_header_separator_to_writer<W: Write>(
-    output: &mut W,
-    max_cols: usize,
-    alignment: HeaderAlignment,
-) -> Result<()> {
-    write!(output, "|")?;
+/// Apply the configured cell transformations, then escape the result for
+/// Markdown when `escape` is set. Header cells are never treated as nulls, so
+/// a column named `NA` keeps its name.
+fn process_cell(field: &str, config: &Config, escape: bool, is_header: bool) -> String {
+    let mut cell = Cow::Borrowed(match config.trim_mode {
+        TrimMode::None => field,
+        TrimMode::Both => field.trim(),
+        TrimMode::Trailing => field.trim_end(),
+        TrimMode::Leading => field.trim_start(),
+    });
 
-    let separator = match alignment {
-        HeaderAlignment::Left => " --- |",
-        HeaderAlignment::Center => " :---: |",
-        HeaderAlignment::Right => " ---: |",
-    };
+    if !is_header && config.is_null(&cell) {
+        return String::new();
+    }
 
-    for _ in 0..max_cols {
-        write!(output, "{}", separator)?;
+    if let Some(width) = config.tab_width {
+        if cell.contains('\t') {
+            cell = Cow::Owned(cell.replace('\t', &" ".repeat(width)));
+        }
     }
 
-    writeln!(output)?;
-    Ok(())
-}
+    if let Some(max_newlines) = config.max_newlines_per_cell {
+        cell = cap_newlines(cell, max_newlines);
+    }
 
-/// Estimate the output size to pre-allocate string capacity.
-fn estimate_output_size(records: &[Vec<String>], max_cols: usize) -> usize {
-    let avg_cell_size = records
-        .iter()
-        .flat_map(|row| row.iter())
-        .map(|cell| cell.len())
-        .sum::<usize>()
-        / records.len().max(1);
+    if config.control_char_policy != ControlCharPolicy::Keep {
+        cell = replace_control_chars(cell, config.control_char_policy);
+    }
+
+    if config.sanitize_formulas && cell.starts_with(['=', '+', '-', '@']) {
+        cell = Cow::Owned(format!("'{}", cell));
+    }
+
+    if !escape {
+        return cell.into_owned();
+    }
+
+    if config.escape_block_markers {
+        if let Some(escaped) = escape_block_marker(&cell) {
+            cell = Cow::Owned(escaped);
+        }
+    }
+
+    let format = if config.org_mode {
+        OutputFormat::OrgMode
+    } else {
+        OutputFormat::Markdown
+    };
+    match format.escaper().escape(&cell, config) {
+        Cow::Borrowed(_) => cell.into_owned(),
+        Cow::Owned(escaped) => escaped,
+    }
+}
+
+/// Escape a blockquote, heading or list marker at the start of a cell, like
+/// `> quote`, `# head`, `- item` or `1. list`, returning `None` if there
+/// isn't one.
+fn escape_block_marker(cell: &str) -> Option<String> {
+    let start = cell.len() - cell.trim_start().len();
+    let rest = &cell[start..];
+    let ends_marker = |after: &str| after.is_empty() || after.starts_with(char::is_whitespace);
+
+    // The byte offset of the marker character to escape
+    let marker = if rest.starts_with('>') {
+        start
+    } else if rest.starts_with('#') {
+        let hashes = rest.len() - rest.trim_start_matches('#').len();
+        if hashes > 6 || !ends_marker(&rest[hashes..]) {
+            return None;
+        }
+        start
+    } else if rest.starts_with(['-', '+']) {
+        if !ends_marker(&rest[1..]) {
+            return None;
+        }
+        start
+    } else {
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits == 0 || digits > 9 || !rest[digits..].starts_with('.') {
+            return None;
+        }
+        if !ends_marker(&rest[digits + 1..]) {
+            return None;
+        }
+        start + digits
+    };
+
+    Some(format!("{}\\{}", &cell[..marker], &cell[marker..]))
+}
+
+/// Strip or escape control characters other than newlines and carriage returns.
+fn replace_control_chars(cell: Cow<'_, str>, policy: ControlCharPolicy) -> Cow<'_, str> {
+    let is_replaced = |c: char| c.is_control() && c != '\n' && c != '\r';
+    if !cell.contains(is_replaced) {
+        return cell;
+    }
+
+    let mut replaced = String::with_capacity(cell.len());
+    for c in cell.chars() {
+        if !is_replaced(c) {
+            replaced.push(c);
+        } else if policy == ControlCharPolicy::Escape {
+            replaced.push_str(&format!("\\u{:04X}", c as u32));
+        }
+    }
+    Cow::Owned(replaced)
+}
+
+/// Keep the first `max` newlines in a cell, collapsing any others into spaces.
+fn cap_newlines(cell: Cow<'_, str>, max: usize) -> Cow<'_, str> {
+    if cell.matches('\n').count() <= max {
+        return cell;
+    }
+
+    let mut capped = String::with_capacity(cell.len());
+    for (i, line) in cell.split('\n').enumerate() {
+        if i > 0 {
+            capped.push(if i <= max { '\n' } else { ' ' });
+        }
+        capped.push_str(line.strip_suffix('\r').unwrap_or(line));
+    }
+    Cow::Owned(capped)
+}
+
+/// Resolve `Config::columns` against the header row into column indices.
+fn resolve_columns(header: &[String], config: &Config) -> Result<Option<Vec<usize>>> {
+    #[cfg(feature = "regex")]
+    if let Some(pattern) = &config.select_columns_regex {
+        return resolve_columns_regex(header, config, pattern).map(Some);
+    }
+
+    let Some(names) = &config.columns else {
+        return Ok(None);
+    };
+
+    if !config.has_headers {
+        return Err(CsvMdError::InvalidConfig(
+            "selecting columns by name requires a header row".to_string(),
+        ));
+    }
+
+    let mut indices = Vec::new();
+    for name in names {
+        let mut matches = header
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| *cell == name)
+            .map(|(i, _)| i)
+            .peekable();
+
+        if matches.peek().is_none() {
+            return Err(CsvMdError::ColumnNotFound(name.clone()));
+        }
+
+        if config.first_match_only {
+            indices.extend(matches.take(1));
+        } else {
+            indices.extend(matches);
+        }
+    }
+
+    Ok(Some(indices))
+}
+
+/// Resolve `Config::select_columns_regex` into the indices of the matching
+/// header cells.
+#[cfg(feature = "regex")]
+fn resolve_columns_regex(header: &[String], config: &Config, pattern: &str) -> Result<Vec<usize>> {
+    if config.columns.is_some() {
+        return Err(CsvMdError::InvalidConfig(
+            "columns can't be selected by both name and regex".to_string(),
+        ));
+    }
+
+    if !config.has_headers {
+        return Err(CsvMdError::InvalidConfig(
+            "selecting columns by regex requires a header row".to_string(),
+        ));
+    }
+
+    let regex = regex::Regex::new(pattern).map_err(|err| {
+        CsvMdError::InvalidConfig(format!("invalid column regex {:?}: {}", pattern, err))
+    })?;
+
+    let indices: Vec<usize> = header
+        .iter()
+        .enumerate()
+        .filter(|(_, cell)| regex.is_match(cell))
+        .map(|(i, _)| i)
+        .collect();
+
+    if indices.is_empty() {
+        return Err(CsvMdError::ColumnNotFound(pattern.to_string()));
+    }
+
+    Ok(indices)
+}
+
+/// Resolve the column that `Config::filter` applies to into an index.
+fn resolve_filter_column(header: &[String], config: &Config) -> Result<Option<usize>> {
+    let Some(filter) = &config.filter else {
+        return Ok(None);
+    };
+
+    match &filter.column {
+        ColumnRef::Index(index) => Ok(Some(*index)),
+        ColumnRef::Name(name) if config.has_headers => header
+            .iter()
+            .position(|cell| cell == name)
+            .map(Some)
+            .ok_or_else(|| CsvMdError::ColumnNotFound(name.clone())),
+        ColumnRef::Name(_) => Err(CsvMdError::InvalidConfig(
+            "filtering on a column by name requires a header row".to_string(),
+        )),
+    }
+}
+
+/// Read the records in `input` using the parsing options from `config`.
+fn read_records<R: Read>(input: R, config: &Config) -> Records<R> {
+    let input = FieldLimit {
+        input,
+        limit: config.max_field_bytes,
+        delimiter: (!config.single_column_mode).then_some(config.delimiter),
+        quoting: !config.single_column_mode && !config.collapse_delimiters,
+        state: FieldState::Start,
+        field_bytes: 0,
+        passed: false,
+    };
+    let reader = if config.single_column_mode {
+        RecordReader::Lines(BufReader::new(input).lines())
+    } else if config.collapse_delimiters {
+        RecordReader::Split(BufReader::new(input).lines(), char::from(config.delimiter))
+    } else {
+        let reader = ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(config.flexible)
+            .delimiter(config.delimiter)
+            .from_reader(input);
+        RecordReader::Csv(reader.into_records())
+    };
+
+    Records {
+        reader,
+        max_field_bytes: config.max_field_bytes,
+        strip_all_bom: config.strip_all_bom,
+        records_read: 0,
+    }
+}
+
+/// The records read from an input, checking the size of their fields against
+/// `Config::max_field_bytes` and removing byte order marks for
+/// `Config::strip_all_bom`.
+struct Records<R> {
+    reader: RecordReader<FieldLimit<R>>,
+    max_field_bytes: Option<usize>,
+    strip_all_bom: bool,
+    records_read: usize,
+}
+
+/// Reads records either parsed as CSV, taken a line at a time for
+/// `single_column_mode`, or split on runs of the delimiter for
+/// `collapse_delimiters`.
+enum RecordReader<R> {
+    Csv(csv::StringRecordsIntoIter<R>),
+    Lines(std::io::Lines<BufReader<R>>),
+    Split(std::io::Lines<BufReader<R>>, char),
+}
+
+impl<R: Read> Iterator for Records<R> {
+    type Item = Result<StringRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = match &mut self.reader {
+            RecordReader::Csv(records) => records.next(),
+            RecordReader::Lines(lines) => lines.next().map(line_record),
+            RecordReader::Split(lines, delimiter) => {
+                let delimiter = *delimiter;
+                lines.next().map(|line| split_record(line, delimiter))
+            }
+        }?;
+        self.records_read += 1;
+
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                let passed = match err.kind() {
+                    csv::ErrorKind::Io(err) => err
+                        .get_ref()
+                        .and_then(|err| err.downcast_ref::<FieldLimitPassed>()),
+                    _ => None,
+                };
+                return Some(Err(match (passed, self.max_field_bytes) {
+                    (Some(passed), Some(limit)) => CsvMdError::FieldTooLarge {
+                        record: self.records_read,
+                        size: passed.0,
+                        limit,
+                    },
+                    _ => err.into(),
+                }));
+            }
+        };
+        if let Some(limit) = self.max_field_bytes {
+            if let Some(size) = record.iter().map(str::len).find(|&size| size > limit) {
+                return Some(Err(CsvMdError::FieldTooLarge {
+                    record: self.records_read,
+                    size,
+                    limit,
+                }));
+            }
+        }
+        if self.strip_all_bom && record.iter().any(|field| field.contains('\u{feff}')) {
+            return Some(Ok(record
+                .iter()
+                .map(|field| field.replace('\u{feff}', ""))
+                .collect()));
+        }
+        Some(Ok(record))
+    }
+}
+
+/// Reads an input for [`Records`], failing as soon as a field is larger than
+/// `Config::max_field_bytes`, rather than once the whole record has been
+/// buffered. Fields are found by following the delimiters, line breaks and
+/// quotes in the bytes as they're read.
+struct FieldLimit<R> {
+    input: R,
+    limit: Option<usize>,
+    /// The delimiter between fields, or `None` when every line is one field.
+    delimiter: Option<u8>,
+    /// Whether fields can be quoted, as they can when parsed as CSV.
+    quoting: bool,
+    state: FieldState,
+    /// Number of bytes in the field being read, not counting its quotes.
+    field_bytes: usize,
+    /// Whether the field being read has passed the limit, which fails the
+    /// next read, once the bytes before it have been parsed.
+    passed: bool,
+}
+
+/// Where a [`FieldLimit`] is within a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldState {
+    Start,
+    Unquoted,
+    Quoted,
+    /// After a `"` in a quoted field, which either closes it or, doubled,
+    /// stands for a `"`.
+    QuoteInQuoted,
+}
+
+impl<R> FieldLimit<R> {
+    /// Follow `byte` through the field being read.
+    fn scan(&mut self, byte: u8) {
+        use FieldState::*;
+
+        let ends_field = matches!(byte, b'\n' | b'\r') || Some(byte) == self.delimiter;
+        let (state, counted) = match (self.state, byte) {
+            (Start | Unquoted | QuoteInQuoted, _) if ends_field => (Start, false),
+            (Start, b'"') if self.quoting => (Quoted, false),
+            (Quoted, b'"') => (QuoteInQuoted, false),
+            (Quoted, _) | (QuoteInQuoted, b'"') => (Quoted, true),
+            (Start | Unquoted | QuoteInQuoted, _) => (Unquoted, true),
+        };
+
+        self.state = state;
+        if state == Start {
+            self.field_bytes = 0;
+        } else if counted {
+            self.field_bytes += 1;
+        }
+    }
+}
+
+impl<R: Read> Read for FieldLimit<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.passed {
+            return Err(std::io::Error::other(FieldLimitPassed(self.field_bytes)));
+        }
+        let read = self.input.read(buf)?;
+        let Some(limit) = self.limit else {
+            return Ok(read);
+        };
+
+        // Records before the field which is too large are read as usual, as
+        // they may already be in the buffer of the reader
+        for (i, &byte) in buf[..read].iter().enumerate() {
+            self.scan(byte);
+            if self.field_bytes > limit {
+                self.passed = true;
+                return Ok(i + 1);
+            }
+        }
+        Ok(read)
+    }
+}
+
+/// The error a [`FieldLimit`] fails with, giving how much of the field had
+/// been read.
+#[derive(Debug)]
+struct FieldLimitPassed(usize);
+
+impl std::fmt::Display for FieldLimitPassed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "a field of {} bytes is too large", self.0)
+    }
+}
+
+impl std::error::Error for FieldLimitPassed {}
+
+/// A source of records for the renderer, with the cells of each row before
+/// any of the row or column options are applied.
+///
+/// Anything which can produce rows of cells, not just a CSV reader, can be
+/// converted by implementing this.
+trait RecordSource {
+    /// Read the next record, or `None` at the end of the input.
+    fn next_record(&mut self) -> Option<Result<Vec<String>>>;
+}
+
+impl<R: Read> RecordSource for Records<R> {
+    fn next_record(&mut self) -> Option<Result<Vec<String>>> {
+        let record = self.next()?;
+        Some(record.map(|record| record.iter().map(str::to_string).collect()))
+    }
+}
+
+/// Records which have already been read into memory.
+impl RecordSource for std::vec::IntoIter<Vec<String>> {
+    fn next_record(&mut self) -> Option<Result<Vec<String>>> {
+        self.next().map(Ok)
+    }
+}
+
+/// Each line of an input as a record with a single cell.
+impl<B: BufRead> RecordSource for std::io::Lines<B> {
+    fn next_record(&mut self) -> Option<Result<Vec<String>>> {
+        let line = self.next()?;
+        Some(line.map(|line| vec![line]).map_err(Into::into))
+    }
+}
+
+/// Read the first `len` bytes of `input`, for sniffing its format.
+fn read_prefix<R: Read>(input: &mut R, len: u64) -> std::io::Result<Vec<u8>> {
+    let mut prefix = Vec::new();
+    input.take(len).read_to_end(&mut prefix)?;
+    Ok(prefix)
+}
+
+/// Whether `sample`, the start of an input, looks like a Markdown table
+/// rather than CSV: a line containing `|`, followed by a separator line like
+/// `| --- | :-: |`.
+fn looks_like_markdown_table(sample: &[u8]) -> bool {
+    let mut lines = sample
+        .split(|&byte| byte == b'\n')
+        .map(|line| line.trim_ascii())
+        .filter(|line| !line.is_empty());
+
+    let (Some(header), Some(separator)) = (lines.next(), lines.next()) else {
+        return false;
+    };
+
+    header.contains(&b'|')
+        && separator.contains(&b'|')
+        && separator.contains(&b'-')
+        && separator
+            .iter()
+            .all(|byte| matches!(byte, b'|' | b'-' | b':' | b' ' | b'\t'))
+}
+
+/// Find an Excel `sep=` line, like `sep=;`, at the start of `sample`,
+/// returning the delimiter it gives and the length of the line.
+fn sep_directive(sample: &[u8]) -> Option<(u8, usize)> {
+    let end = sample.iter().position(|&byte| byte == b'\n');
+    let line = &sample[..end.unwrap_or(sample.len())];
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+
+    match line.strip_prefix(b"sep=")? {
+        [delimiter] => Some((*delimiter, end.map_or(sample.len(), |end| end + 1))),
+        _ => None,
+    }
+}
+
+/// Turn a line of input into a record with a single cell.
+fn line_record(line: std::io::Result<String>) -> csv::Result<StringRecord> {
+    let mut record = StringRecord::new();
+    record.push_field(&line?);
+    Ok(record)
+}
+
+/// Split a line into a record on runs of `delimiter`, leaving out empty fields.
+fn split_record(line: std::io::Result<String>, delimiter: char) -> csv::Result<StringRecord> {
+    let line = line?;
+    Ok(line
+        .split(delimiter)
+        .filter(|field| !field.is_empty())
+        .collect())
+}
+
+/// Convert CSV data to a Markdown table string.
+///
+/// This function reads CSV data from the provided reader and converts it to
+/// a Markdown table format. It processes the data in a streaming fashion to
+/// handle large files efficiently.
+///
+/// # Arguments
+///
+/// * `input` - A reader containing CSV data
+/// * `config` - Configuration options for the conversion
+///
+/// # Returns
+///
+/// A string containing the formatted Markdown table.
+///
+/// # Errors
+///
+/// Returns `CsvMdError` if:
+/// - The input cannot be read
+/// - The CSV data is malformed
+/// - Memory allocation fails during processing
+///
+/// # Example
+///
+/// ```rust
+/// use csvmd::{csv_to_markdown, Config};
+/// use std::io::Cursor;
+///
+/// let csv_data = "Name,Age\nJohn,25\nJane,30";
+/// let input = Cursor::new(csv_data);
+/// let config = Config::default();
+/// let result = csv_to_markdown(input, config)?;
+/// assert!(result.contains("| Name | Age |"));
+/// # Ok::<(), csvmd::error::CsvMdError>(())
+/// ```
+pub fn csv_to_markdown<R: Read>(input: R, config: Config) -> Result<String> {
+    let mut records = Vec::new();
+    let mut output = Vec::new();
+    convert_into(input, &config, &mut records, &mut output)?;
+    into_string(output)
+}
+
+/// Read the CSV file at `path` and convert it to a Markdown table string.
+///
+/// # Example
+///
+/// ```rust
+/// use csvmd::{convert_file, Config};
+/// use std::io::Write;
+///
+/// let mut file = tempfile::NamedTempFile::new()?;
+/// write!(file, "Name,Age\nJohn,25")?;
+///
+/// let markdown = convert_file(file.path(), Config::default())?;
+/// assert_eq!(markdown, "| Name | Age |\n| --- | --- |\n| John | 25 |\n");
+///
+/// // Errors opening the file say which file it was
+/// let err = convert_file("missing.csv", Config::default()).unwrap_err();
+/// assert!(err.to_string().contains("missing.csv"));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns `CsvMdError::Io`, including the path in its message, if the file
+/// can't be opened or read, or another `CsvMdError` if its contents are
+/// malformed.
+pub fn convert_file<P: AsRef<Path>>(path: P, config: Config) -> Result<String> {
+    let path = path.as_ref();
+    let with_path = |err: std::io::Error| {
+        std::io::Error::new(err.kind(), format!("{}: {}", path.display(), err))
+    };
+
+    let file = std::fs::File::open(path).map_err(with_path)?;
+    let config = Config {
+        source_name: config.source_name.or_else(|| {
+            let name = path.file_name()?;
+            Some(name.to_string_lossy().into_owned())
+        }),
+        ..config
+    };
+    csv_to_markdown(BufReader::new(file), config).map_err(|err| match err {
+        CsvMdError::Io(err) => CsvMdError::Io(with_path(err)),
+        err => err,
+    })
+}
+
+/// Convert CSV data to a Markdown table string, then pass it through `post`
+/// for any final changes to the whole output, like adding a header or footer.
+///
+/// # Example
+///
+/// ```rust
+/// use csvmd::{csv_to_markdown_with_postprocess, Config};
+/// use std::io::Cursor;
+///
+/// let input = Cursor::new("Name,Age\nJohn,25");
+/// let result = csv_to_markdown_with_postprocess(input, Config::default(), |markdown| {
+///     markdown + "\n_Source: HR export_\n"
+/// })?;
+/// assert_eq!(
+///     result,
+///     "| Name | Age |\n| --- | --- |\n| John | 25 |\n\n_Source: HR export_\n"
+/// );
+/// # Ok::<(), csvmd::error::CsvMdError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns `CsvMdError` if the input cannot be read or is malformed, in
+/// which case `post` isn't called.
+pub fn csv_to_markdown_with_postprocess<R: Read, F: FnOnce(String) -> String>(
+    input: R,
+    config: Config,
+    post: F,
+) -> Result<String> {
+    csv_to_markdown(input, config).map(post)
+}
+
+/// Convert CSV data to a Markdown table, passing each data record through
+/// `f`, which returns the records to write in its place.
+///
+/// Returning no records drops a row, and returning several splits or
+/// duplicates it. `f` is given the cells as they appear in the input, and the
+/// records it returns are formatted like any other, so the table is sized to
+/// fit them. The header, and the rows up to and including the alignment row
+/// or types row when there is one, aren't passed to `f`.
+///
+/// # Example
+///
+/// ```rust
+/// use csvmd::{csv_to_markdown_flat_map, Config};
+/// use std::io::Cursor;
+///
+/// let input = Cursor::new("Name,Tags\nJohn,a;b");
+/// let markdown = csv_to_markdown_flat_map(input, Config::default(), |row| {
+///     row[1]
+///         .split(';')
+///         .map(|tag| vec![row[0].clone(), tag.to_string()])
+///         .collect()
+/// })?;
+/// assert_eq!(
+///     markdown,
+///     "| Name | Tags |\n| --- | --- |\n| John | a |\n| John | b |\n"
+/// );
+/// # Ok::<(), csvmd::error::CsvMdError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns `CsvMdError` if the input cannot be read or is malformed.
+pub fn csv_to_markdown_flat_map<R, F>(input: R, config: Config, f: F) -> Result<String>
+where
+    R: Read,
+    F: Fn(&[String]) -> Vec<Vec<String>>,
+{
+    let header = if config.has_headers {
+        config.header_row_index.unwrap_or(0) + config.header_rows.max(1)
+    } else {
+        0
+    };
+    let annotations = match config.types_row {
+        Some(row) => (row + 1).saturating_sub(usize::from(config.has_headers)),
+        None => usize::from(config.alignment_row),
+    };
+    let mut untransformed = header + annotations;
+
+    let mut transformed = Vec::new();
+    let mut records = read_records(input, &config);
+    while let Some(record) = records.next_record() {
+        let record = record?;
+        if untransformed > 0 {
+            untransformed -= 1;
+            transformed.push(record);
+            continue;
+        }
+        transformed.extend(f(&record));
+    }
+
+    let mut output = Vec::new();
+    convert_records_into(
+        transformed.into_iter(),
+        &config,
+        &mut Vec::new(),
+        &mut output,
+    )?;
+    into_string(output)
+}
+
+/// Measurements of a rendered Markdown table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConversionStats {
+    /// Number of data rows in the table, excluding the header.
+    pub rows: usize,
+    /// Number of columns in the table.
+    pub columns: usize,
+    /// Display width of the widest line of output, including pipes and
+    /// padding. Wide characters, like most CJK characters, count as two
+    /// columns.
+    pub max_line_width: usize,
+}
+
+/// Convert CSV data to a Markdown table string, along with measurements of
+/// the rendered table for tools which need to lay it out.
+///
+/// # Example
+///
+/// ```rust
+/// use csvmd::{csv_to_markdown_with_stats, Config};
+/// use std::io::Cursor;
+///
+/// let input = Cursor::new("Name,Age\nJohn,25");
+/// let (markdown, stats) = csv_to_markdown_with_stats(input, Config::default())?;
+/// assert_eq!(markdown.lines().next(), Some("| Name | Age |"));
+/// assert_eq!(stats.rows, 1);
+/// assert_eq!(stats.max_line_width, 14);
+/// # Ok::<(), csvmd::error::CsvMdError>(())
+/// ```
+pub fn csv_to_markdown_with_stats<R: Read>(
+    input: R,
+    config: Config,
+) -> Result<(String, ConversionStats)> {
+    let mut records = Vec::new();
+    let mut output = Vec::new();
+    let layout = convert_into(input, &config, &mut records, &mut output)?;
+    let markdown = into_string(output)?;
+    let stats = ConversionStats::measure(&layout, &config, &markdown);
+    Ok((markdown, stats))
+}
+
+impl ConversionStats {
+    /// Measure `markdown`, rendered with `layout`.
+    fn measure(layout: &TableLayout, config: &Config, markdown: &str) -> Self {
+        Self {
+            rows: layout.data_rows(config),
+            columns: layout.columns,
+            max_line_width: markdown
+                .lines()
+                .map(UnicodeWidthStr::width)
+                .max()
+                .unwrap_or(0),
+        }
+    }
+}
+
+/// Parse CSV data into a map for each data row, keyed by header name.
+///
+/// Cells are returned as they appear in the input, without any of the
+/// Markdown escaping or cell options applied. Only the parsing options from
+/// `config` are used: `has_headers`, `header_rows`, `delimiter`, `flexible`
+/// and `single_column_mode`.
+///
+/// When a header name appears more than once, later columns get a suffix,
+/// like `Name_2`. A row shorter than the header leaves out the keys for its
+/// missing cells, and cells beyond the end of the header are ignored. Without
+/// `has_headers`, cells are keyed by their zero-based column index.
+///
+/// # Example
+///
+/// ```rust
+/// use csvmd::{csv_to_records_map, Config};
+/// use std::io::Cursor;
+///
+/// let rows = csv_to_records_map(Cursor::new("Name,Age\nJohn,25"), &Config::default())?;
+/// assert_eq!(rows[0]["Name"], "John");
+/// assert_eq!(rows[0]["Age"], "25");
+/// # Ok::<(), csvmd::error::CsvMdError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns `CsvMdError` if the input cannot be read or is malformed.
+pub fn csv_to_records_map<R: Read>(
+    input: R,
+    config: &Config,
+) -> Result<Vec<BTreeMap<String, String>>> {
+    Ok(read_keyed_rows(input, config)?
+        .into_iter()
+        .map(|row| row.into_iter().collect())
+        .collect())
+}
+
+/// Read the header of CSV data, without reading the rest of the input.
+///
+/// This is much quicker than a conversion for a large input when only the
+/// column names or count are needed. The header is found like it is when
+/// converting: rows before `header_row_index` are skipped, a `sep=` line is
+/// honored, a byte order mark is ignored and `header_rows` are merged into
+/// one. Without `has_headers`, the cells of the first row are returned.
+/// Cells are returned as they appear in the input, and an empty input gives
+/// no cells.
+///
+/// # Example
+///
+/// ```rust
+/// use csvmd::{peek_header, Config};
+/// use std::io::Cursor;
+///
+/// let header = peek_header(Cursor::new("sep=;\nName;Age\nJohn;25"), &Config::default())?;
+/// assert_eq!(header, ["Name", "Age"]);
+/// # Ok::<(), csvmd::error::CsvMdError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns `CsvMdError` if the start of the input cannot be read or is
+/// malformed.
+pub fn peek_header<R: Read>(mut input: R, config: &Config) -> Result<Vec<String>> {
+    let mut config = Cow::Borrowed(config);
+    let mut prefix = read_prefix(&mut input, config.sniffed_bytes())?;
+    if config.detect_header {
+        config.to_mut().has_headers = sniff_header(&prefix, &config);
+    }
+    if config.detect_delimiter {
+        config.to_mut().delimiter = detect_delimiter(&prefix);
+    }
+    if let Some((delimiter, len)) = sep_directive(&prefix).filter(|_| config.honor_sep_directive) {
+        config.to_mut().delimiter = delimiter;
+        prefix.drain(..len);
+    }
+
+    let mut records = read_records(std::io::Cursor::new(prefix).chain(input), &config);
+    if !config.has_headers {
+        return records.next_record().unwrap_or(Ok(Vec::new()));
+    }
+
+    for record in records.by_ref().take(config.header_row_index.unwrap_or(0)) {
+        record?;
+    }
+    let header_rows = (0..config.header_rows.max(1))
+        .map_while(|_| records.next_record())
+        .collect::<Result<Vec<_>>>()?;
+    Ok(merge_header_rows(
+        &header_rows,
+        &config.header_row_separator,
+    ))
+}
+
+/// Read each data row as pairs of keys and cells, in column order, as
+/// described for [`csv_to_records_map`].
+pub(crate) fn read_keyed_rows<R: Read>(
+    input: R,
+    config: &Config,
+) -> Result<Vec<Vec<(String, String)>>> {
+    let (header, records) = read_raw_table(input, config)?;
+    let header: Option<Vec<String>> =
+        header.map(|header| header.iter().map(String::from).collect());
+    let records = records
+        .iter()
+        .map(|record| record.iter().map(String::from).collect());
+    Ok(key_rows(header.as_deref(), records))
+}
+
+/// Pair each cell of `rows` with its key, as described for
+/// [`csv_to_records_map`].
+pub(crate) fn key_rows<I>(header: Option<&[String]>, rows: I) -> Vec<Vec<(String, String)>>
+where
+    I: IntoIterator<Item = Vec<String>>,
+{
+    let keys = header.map(unique_keys).unwrap_or_default();
+
+    let rows = rows.into_iter().map(|record| {
+        let row = record.into_iter().enumerate().filter_map(|(i, cell)| {
+            let key = if header.is_some() {
+                keys.get(i)?.clone()
+            } else {
+                i.to_string()
+            };
+            Some((key, cell))
+        });
+        row.collect()
+    });
+    rows.collect()
+}
+
+/// Read the header, if `has_headers` is set, and the data records of an
+/// input, using only the parsing options from `config` and leaving the cells
+/// as they are.
+pub(crate) fn read_raw_table<R: Read>(
+    input: R,
+    config: &Config,
+) -> Result<(Option<StringRecord>, Vec<StringRecord>)> {
+    let mut records = read_records(input, config);
+
+    let header = if config.has_headers {
+        for record in records.by_ref().take(config.header_row_index.unwrap_or(0)) {
+            record?;
+        }
+        let header_rows = (0..config.header_rows.max(1))
+            .map_while(|_| records.next_record())
+            .collect::<Result<Vec<_>>>()?;
+        Some(StringRecord::from(merge_header_rows(
+            &header_rows,
+            &config.header_row_separator,
+        )))
+    } else {
+        None
+    };
+
+    Ok((header, records.collect::<Result<_>>()?))
+}
+
+/// A header, if there is one, and the data rows of a table.
+pub(crate) type Table = (Option<Vec<String>>, Vec<Vec<String>>);
+
+/// Read the header, if `has_headers` is set, and the data rows of an input
+/// through a [`RowPipeline`], applying the row and column options from
+/// `config` but leaving the cells unescaped, for formats which escape cells
+/// themselves.
+pub(crate) fn read_processed_table<R: Read>(input: R, config: &Config) -> Result<Table> {
+    let mut rows = RowPipeline::unescaped(config).rows(read_records(input, config));
+    let header = if config.has_headers {
+        rows.next().transpose()?
+    } else {
+        None
+    };
+    Ok((header, rows.collect::<Result<_>>()?))
+}
+
+/// Turn header names into unique keys, adding a suffix like `_2` to later
+/// duplicates.
+fn unique_keys(header: &[String]) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::with_capacity(header.len());
+    for name in header {
+        let mut key = name.to_string();
+        let mut count = 1;
+        while keys.contains(&key) {
+            count += 1;
+            key = format!("{}_{}", name, count);
+        }
+        keys.push(key);
+    }
+    keys
+}
+
+/// Compare two CSVs and convert the rows which were added, removed or changed
+/// to a Markdown table, with a status column of `+`, `-` or `~` first.
+///
+/// Without a `key`, rows are matched when every cell is the same, so a
+/// changed row appears as one removed row and one added row. With a `key`,
+/// rows are matched by the cell in that zero-based column, and a matched row
+/// whose other cells differ is shown as changed, with each changed cell
+/// written like `old → new`. Rows with the same key are matched in order.
+///
+/// Added and changed rows come first, in the order of `new`, followed by the
+/// removed rows in the order of `old`. Unchanged rows are left out. Both
+/// inputs are parsed with `config`, and the header is taken from `new`.
+///
+/// # Example
+///
+/// ```rust
+/// use csvmd::{diff_to_markdown, Config};
+/// use std::io::Cursor;
+///
+/// let old = Cursor::new("Name,Age\nJohn,25\nJane,30");
+/// let new = Cursor::new("Name,Age\nJohn,26\nJane,30");
+/// let markdown = diff_to_markdown(old, new, Config::default(), Some(0))?;
+/// assert_eq!(
+///     markdown,
+///     "| Status | Name | Age |\n| --- | --- | --- |\n| ~ | John | 25 → 26 |\n"
+/// );
+/// # Ok::<(), csvmd::error::CsvMdError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns `CsvMdError` if either input cannot be read or is malformed, or
+/// `CsvMdError::ColumnNotFound` if `key` is beyond the end of the header.
+pub fn diff_to_markdown<R: Read, S: Read>(
+    old: R,
+    new: S,
+    config: Config,
+    key: Option<usize>,
+) -> Result<String> {
+    diff_to_markdown_with_stats(old, new, config, key).map(|(markdown, _)| markdown)
+}
+
+/// Like [`diff_to_markdown`], along with measurements of the rendered table,
+/// where `rows` is the number of added, removed and changed rows.
+///
+/// # Errors
+///
+/// Returns `CsvMdError` if either input cannot be read or is malformed, or
+/// `CsvMdError::ColumnNotFound` if `key` is beyond the end of the header.
+pub fn diff_to_markdown_with_stats<R: Read, S: Read>(
+    old: R,
+    new: S,
+    config: Config,
+    key: Option<usize>,
+) -> Result<(String, ConversionStats)> {
+    let (_, old_rows) = read_raw_table(old, &config)?;
+    let (header, new_rows) = read_raw_table(new, &config)?;
+    let cells =
+        |record: &StringRecord| -> Vec<String> { record.iter().map(str::to_string).collect() };
+
+    if let (Some(key), Some(header)) = (key, &header) {
+        if key >= header.len() {
+            return Err(CsvMdError::ColumnNotFound(key.to_string()));
+        }
+    }
+
+    // The unmatched rows of `old`, by the cells they're matched on
+    let match_on = |row: &StringRecord| -> Vec<String> {
+        match key {
+            Some(key) => vec![row.get(key).unwrap_or_default().to_string()],
+            None => cells(row),
+        }
+    };
+    let mut unmatched: BTreeMap<Vec<String>, VecDeque<usize>> = BTreeMap::new();
+    for (i, row) in old_rows.iter().enumerate() {
+        unmatched.entry(match_on(row)).or_default().push_back(i);
+    }
+
+    let mut rows = Vec::new();
+    if let Some(header) = &header {
+        rows.push(
+            std::iter::once("Status".to_string())
+                .chain(cells(header))
+                .collect(),
+        );
+    }
+
+    let mut removed = vec![true; old_rows.len()];
+    for row in &new_rows {
+        let Some(i) = unmatched
+            .get_mut(&match_on(row))
+            .and_then(VecDeque::pop_front)
+        else {
+            rows.push(std::iter::once("+".to_string()).chain(cells(row)).collect());
+            continue;
+        };
+        removed[i] = false;
+
+        let old_row = &old_rows[i];
+        if old_row == row {
+            continue;
+        }
+        let changed = (0..old_row.len().max(row.len())).map(|column| {
+            let before = old_row.get(column).unwrap_or_default();
+            let after = row.get(column).unwrap_or_default();
+            if before == after {
+                after.to_string()
+            } else {
+                format!("{} → {}", before, after)
+            }
+        });
+        rows.push(std::iter::once("~".to_string()).chain(changed).collect());
+    }
+
+    for (row, _) in old_rows.iter().zip(removed).filter(|(_, removed)| *removed) {
+        rows.push(std::iter::once("-".to_string()).chain(cells(row)).collect());
+    }
+
+    // The rows are already split into a single header row and data rows
+    let config = Config {
+        header_rows: 1,
+        header_row_index: None,
+        ..config
+    };
+    let mut output = Vec::new();
+    let layout = convert_records_into(rows.into_iter(), &config, &mut Vec::new(), &mut output)?;
+    let markdown = into_string(output)?;
+    let stats = ConversionStats::measure(&layout, &config, &markdown);
+    Ok((markdown, stats))
+}
+
+/// Convert CSV data to Markdown, using the given buffers for the parsed records
+/// and the rendered output.
+///
+/// Both buffers are cleared first, so callers can reuse their allocations
+/// across conversions. Returns the layout of the table that was written.
+fn convert_into<R: Read>(
+    mut input: R,
+    config: &Config,
+    records: &mut Vec<Vec<String>>,
+    output: &mut Vec<u8>,
+) -> Result<TableLayout> {
+    records.clear();
+    output.clear();
+
+    let mut prefix = read_prefix(&mut input, config.sniffed_bytes())?;
+    if config.detect_markdown && looks_like_markdown_table(&prefix) {
+        output.extend_from_slice(&prefix);
+        input.read_to_end(output)?;
+        return Ok(TableLayout::default());
+    }
+    let directive = sep_directive(&prefix).filter(|_| config.honor_sep_directive);
+    let detected;
+    let config = if config.detect_delimiter || config.detect_header || directive.is_some() {
+        detected = Config {
+            delimiter: match directive {
+                Some((delimiter, _)) => delimiter,
+                None if config.detect_delimiter => detect_delimiter(&prefix),
+                None => config.delimiter,
+            },
+            has_headers: if config.detect_header {
+                sniff_header(&prefix, config)
+            } else {
+                config.has_headers
+            },
+            ..config.clone()
+        };
+        &detected
+    } else {
+        config
+    };
+    if let Some((_, len)) = directive {
+        prefix.drain(..len);
+    }
+    let input = std::io::Cursor::new(prefix).chain(input);
+
+    convert_records_into(read_records(input, config), config, records, output)
+}
+
+/// Convert parsed records to a Markdown table in `output`, collecting the
+/// processed rows in `records`.
+fn convert_records_into<S: RecordSource>(
+    input: S,
+    config: &Config,
+    records: &mut Vec<Vec<String>>,
+    output: &mut Vec<u8>,
+) -> Result<TableLayout> {
+    let layout = write_records_into(input, config, records, output)?;
+    if let Some(max_width) = config.max_table_width {
+        let markdown = String::from_utf8_lossy(output);
+        *output = rewrite_tables(&markdown, |rows| fit_table(rows, max_width, config)).into_bytes();
+    } else if config.pretty {
+        let markdown = String::from_utf8_lossy(output);
+        *output = rewrite_tables(&markdown, |rows| pad_table(rows, config)).into_bytes();
+    }
+    // Written after the writers from `output_writer`, like `finish_output`
+    if config.source_footer && !output.is_empty() {
+        if !output.ends_with(b"\n") {
+            output.push(b'\n');
+        }
+        write!(output, "\n{}", source_footer(config, SystemTime::now()))?;
+        if config.ends_with_newline() {
+            output.push(b'\n');
+        }
+    }
+    if config.trailing_blank_line && !output.is_empty() {
+        output.push(b'\n');
+    }
+    if config.validate_output {
+        validate_markdown(&String::from_utf8_lossy(output))?;
+    }
+    Ok(layout)
+}
+
+fn write_records_into<S: RecordSource>(
+    input: S,
+    config: &Config,
+    records: &mut Vec<Vec<String>>,
+    output: &mut Vec<u8>,
+) -> Result<TableLayout> {
+    let mut layout = TableLayout::default();
+
+    // First pass: collect all records and determine the table layout
+    let mut rows = RowPipeline::new(config).rows(input);
+    for row in &mut rows {
+        let row = row?;
+        layout.observe(&row, config);
+        records.push(row);
+    }
+    layout.observe_alignments(rows.alignments());
+
+    if let Some(kind) = config.summary_row {
+        // Summarising every row, rather than the ones a preview shows
+        let data = match rows.summarised() {
+            Some(summarised) => summarised,
+            None => &records[usize::from(config.has_headers).min(records.len())..],
+        };
+        if !data.is_empty()
+            && !config.column_tables
+            && !config.schema_table
+            && config.group_by.is_none()
+        {
+            let row = summary_row(data, kind);
+            layout.observe(&row, config);
+            records.push(row);
+        }
+    }
+
+    // Estimate output size to reduce allocations. This is only a hint, and
+    // `min_columns` can make it far larger than the table will ever be, so a
+    // failed reservation just leaves the buffer to grow as it's written.
+    let _ = output.try_reserve(estimated_markdown_size(records, config));
+    let mut output = output_writer(output, config);
+
+    if write_empty_input_message(&mut output, &layout, config)? || records.is_empty() {
+        return Ok(layout);
+    }
+
+    let layout = layout.finish(config);
+
+    if config.schema_table {
+        write_schema_table(&mut output, records, &layout, config)?;
+        return Ok(layout);
+    }
+
+    if config.column_tables {
+        write_column_tables(&mut output, records, &layout, config)?;
+        return Ok(layout);
+    }
+
+    if let Some(group_by) = &config.group_by {
+        write_grouped_tables(&mut output, records, group_by, &layout, config)?;
+        return Ok(layout);
+    }
+
+    // Write the table
+    let mut writer = TableWriter::new(&layout, config);
+    for record in records.iter() {
+        writer.write_row(&mut output, record)?;
+    }
+    writer.finish(&mut output)?;
+
+    Ok(layout)
+}
+
+/// A reusable converter which keeps its buffers between conversions.
+///
+/// When converting many small CSVs, for example in a server, this avoids
+/// allocating new buffers for every conversion.
+///
+/// # Example
+///
+/// ```rust
+/// use csvmd::{Config, Converter};
+/// use std::io::Cursor;
+///
+/// let mut converter = Converter::new(Config::default());
+/// for csv_data in ["Name\nJohn", "Name\nJane"] {
+///     let markdown = converter.convert(Cursor::new(csv_data))?;
+///     assert!(markdown.starts_with("| Name |"));
+/// }
+/// # Ok::<(), csvmd::error::CsvMdError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Converter {
+    config: Config,
+    records: Vec<Vec<String>>,
+    output: Vec<u8>,
+}
+
+impl Converter {
+    /// Create a converter which uses `config` for every conversion.
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            records: Vec::new(),
+            output: Vec::new(),
+        }
+    }
+
+    /// The configuration used for conversions.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Convert CSV data to a Markdown table, returning a view of the output
+    /// which is valid until the next conversion.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CsvMdError` if the input cannot be read or is malformed.
+    pub fn convert<R: Read>(&mut self, input: R) -> Result<&str> {
+        convert_into(input, &self.config, &mut self.records, &mut self.output)?;
+        std::str::from_utf8(&self.output)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err).into())
+    }
+}
+
+/// Convert rendered output back into a `String`.
+fn into_string(output: Vec<u8>) -> Result<String> {
+    String::from_utf8(output)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err).into())
+}
+
+/// Convert CSV data to Markdown and write directly to output.
+///
+/// This streaming version uses a two-pass approach:
+/// 1. First pass: determine the maximum column count
+/// 2. Second pass: stream output with correct table formatting
+///
+/// This provides memory efficiency for large files while ensuring correct
+/// Markdown table structure. Options which need every row before writing
+/// any, like `column_tables`, `schema_table`, `group_by` and `summary_row`,
+/// or which work on the finished output, like `pretty`, make it buffer the
+/// whole table instead.
+///
+/// # Arguments
+///
+/// * `input` - A reader containing CSV data
+/// * `output` - A writer where the Markdown table will be written
+/// * `config` - Configuration options for the conversion
+///
+/// # Errors
+///
+/// Returns `CsvMdError` if reading, parsing, or writing fails.
+pub fn csv_to_markdown_streaming<R: Read, W: Write>(
+    mut input: R,
+    mut output: W,
+    mut config: Config,
+) -> Result<()> {
+    if config.requires_buffering() {
+        output.write_all(csv_to_markdown(input, config)?.as_bytes())?;
+        output.flush()?;
+        return Ok(());
+    }
+
+    let mut prefix = read_prefix(&mut input, config.sniffed_bytes())?;
+    if config.detect_markdown && looks_like_markdown_table(&prefix) {
+        output.write_all(&prefix)?;
+        std::io::copy(&mut input, &mut output)?;
+        output.flush()?;
+        return Ok(());
+    }
+    if config.detect_header {
+        config.has_headers = sniff_header(&prefix, &config);
+    }
+    if config.detect_delimiter {
+        config.delimiter = detect_delimiter(&prefix);
+    }
+    if let Some((delimiter, len)) = sep_directive(&prefix).filter(|_| config.honor_sep_directive) {
+        config.delimiter = delimiter;
+        prefix.drain(..len);
+    }
+    let mut input = std::io::Cursor::new(prefix).chain(input);
+
+    if config.spill_to_disk && !config.optimistic_streaming {
+        let mut file = tempfile::tempfile()?;
+        std::io::copy(&mut input, &mut file)?;
+        return csv_to_markdown_streaming_seekable(file, output, config);
+    }
+
+    let mut output = output_writer(output, &config);
+
+    if config.optimistic_streaming {
+        write_table_optimistic(read_records(input, &config), &mut output, &config)?;
+        finish_output(&mut output, &config)?;
+        return Ok(());
+    }
+
+    // First, we need to read the input to determine max columns
+    // Since we need to read twice, we'll read all data into memory first
+    let mut buffer = Vec::new();
+    input.read_to_end(&mut buffer)?;
+
+    // First pass: determine the table layout
+    let layout = scan_layout(std::io::Cursor::new(&buffer), &config)?;
+
+    // Second pass: stream output with the correct layout
+    write_table(std::io::Cursor::new(&buffer), &mut output, &layout, &config)?;
+
+    finish_output(&mut output, &config)?;
+    Ok(())
+}
+
+/// Convert CSV data to Markdown and write directly to output using a seekable input.
+///
+/// For seekable inputs (e.g., files), this variant avoids buffering the entire
+/// input into memory. It performs two passes by rewinding the reader between
+/// passes to compute the maximum column count and then write the output.
+pub fn csv_to_markdown_streaming_seekable<R: Read + Seek, W: Write>(
+    mut input: R,
+    mut output: W,
+    mut config: Config,
+) -> Result<()> {
+    input.seek(SeekFrom::Start(0))?;
+    if config.requires_buffering() {
+        output.write_all(csv_to_markdown(input, config)?.as_bytes())?;
+        output.flush()?;
+        return Ok(());
+    }
+
+    // Where the records start, after any `sep=` line
+    let mut start = 0;
+    if config.sniffed_bytes() > 0 {
+        let prefix = read_prefix(&mut input, config.sniffed_bytes())?;
+        input.seek(SeekFrom::Start(0))?;
+        if config.detect_markdown && looks_like_markdown_table(&prefix) {
+            std::io::copy(&mut input, &mut output)?;
+            output.flush()?;
+            return Ok(());
+        }
+        if config.detect_header {
+            config.has_headers = sniff_header(&prefix, &config);
+        }
+        if config.detect_delimiter {
+            config.delimiter = detect_delimiter(&prefix);
+        }
+        if let Some((delimiter, len)) =
+            sep_directive(&prefix).filter(|_| config.honor_sep_directive)
+        {
+            config.delimiter = delimiter;
+            start = len as u64;
+            input.seek(SeekFrom::Start(start))?;
+        }
+    }
+
+    let mut output = output_writer(output, &config);
+
+    if config.optimistic_streaming {
+        write_table_optimistic(read_records(input, &config), &mut output, &config)?;
+        finish_output(&mut output, &config)?;
+        return Ok(());
+    }
+
+    // First pass: determine the table layout
+    let layout = scan_layout(&mut input, &config)?;
+
+    // Second pass: rewind and stream output with the correct layout
+    input.seek(SeekFrom::Start(start))?;
+    write_table(&mut input, &mut output, &layout, &config)?;
+
+    finish_output(&mut output, &config)?;
+    Ok(())
+}
+
+/// Choose the delimiter for an input from `sample`, its start.
+///
+/// Up to 20 rows of the sample are parsed with each of `,`, tab, `;` and
+/// `|`. A delimiter is only chosen if it splits every row into the same
+/// number of columns, more than one, so a delimiter which only appears in
+/// the header, like a quoted `;`, isn't picked. Where several delimiters
+/// qualify, the one giving the most columns wins, with ties going to the
+/// earlier one. Without a consistent delimiter, this falls back to `,`.
+///
+/// # Example
+///
+/// ```rust
+/// use csvmd::detect_delimiter;
+///
+/// assert_eq!(detect_delimiter(b"Name;Age\nJohn;25\n"), b';');
+/// assert_eq!(detect_delimiter(b"\"Name;Full\",Age\nJohn,25\n"), b',');
+/// assert_eq!(detect_delimiter(b"Name\nJohn\n"), b',');
+/// ```
+pub fn detect_delimiter(sample: &[u8]) -> u8 {
+    const ROWS: usize = 20;
+
+    // Leave out a final line which may have been cut off partway through
+    let sample = match sample.iter().rposition(|&byte| byte == b'\n') {
+        Some(end) => &sample[..=end],
+        None => sample,
+    };
+
+    let columns = |delimiter: u8| -> Option<usize> {
+        let mut counts = ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .delimiter(delimiter)
+            .from_reader(sample)
+            .into_records()
+            .take(ROWS)
+            .map(|record| record.map(|record| record.len()));
+
+        let first = counts.next()?.ok()?;
+        for count in counts {
+            if count.ok()? != first {
+                return None;
+            }
+        }
+        Some(first).filter(|&columns| columns > 1)
+    };
+
+    let mut best = (b',', 0);
+    for delimiter in [b',', b'\t', b';', b'|'] {
+        if let Some(count) = columns(delimiter) {
+            if count > best.1 {
+                best = (delimiter, count);
+            }
+        }
+    }
+    best.0
+}
+
+/// Decide whether the first row of CSV data is a header, for
+/// [`Config::detect_header`].
+///
+/// The first row is read like [`peek_header`] reads it without
+/// `has_headers`, so a `sep=` line and `detect_delimiter` are honored. It's
+/// taken to be a header when every cell is non-empty, isn't a number and is
+/// different from the others, like `level|time|message`. An empty input has
+/// no header.
+///
+/// # Example
+///
+/// ```rust
+/// use csvmd::{detect_header, Config};
+/// use std::io::Cursor;
+///
+/// let config = Config::default();
+/// assert!(detect_header(Cursor::new("Name,Age\nJohn,25"), &config)?);
+/// assert!(!detect_header(Cursor::new("John,25\nJane,30"), &config)?);
+/// assert!(!detect_header(Cursor::new("ok,ok\nok,fail"), &config)?);
+/// # Ok::<(), csvmd::error::CsvMdError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns `CsvMdError` if the start of the input cannot be read or is
+/// malformed.
+pub fn detect_header<R: Read>(input: R, config: &Config) -> Result<bool> {
+    let config = Config {
+        has_headers: false,
+        detect_header: false,
+        ..config.clone()
+    };
+    let row = peek_header(input, &config)?;
+
+    let mut seen = BTreeSet::new();
+    Ok(!row.is_empty()
+        && row.iter().all(|cell| {
+            let cell = cell.trim();
+            !cell.is_empty() && parse_number(cell).is_none() && seen.insert(cell)
+        }))
+}
+
+/// Whether the input starting with `prefix` has a header, for
+/// `detect_header`, falling back to `has_headers` if the prefix is malformed.
+///
+/// This is how a conversion decides, from the first
+/// [`Config::sniffed_bytes`] of its input, so a first row longer than that is
+/// judged by the part of it in the prefix.
+///
+/// # Example
+///
+/// ```rust
+/// use csvmd::{sniff_header, Config};
+///
+/// let config = Config {
+///     detect_header: true,
+///     ..Config::default()
+/// };
+/// assert!(sniff_header(b"Name,Age\nJohn,25", &config));
+/// assert!(!sniff_header(b"John,25\nJane,30", &config));
+/// ```
+pub fn sniff_header(prefix: &[u8], config: &Config) -> bool {
+    detect_header(prefix, config).unwrap_or(config.has_headers)
+}
+
+/// Suggest a different delimiter when `sample`, the start of an input, would
+/// be converted into a table with a single column but every line contains
+/// another common delimiter, like a semicolon-separated file read with the
+/// default comma.
+///
+/// Only the first 20 lines of the sample are checked. Returns `None` when the
+/// delimiter in `config` already splits the rows, when the sample starts
+/// with a `sep=` line that will be honored, or with `single_column_mode`,
+/// where a single column is expected.
+///
+/// # Example
+///
+/// ```rust
+/// use csvmd::{suggest_delimiter, Config};
+///
+/// let sample = b"Name;Age\nJohn;25\n";
+/// assert_eq!(suggest_delimiter(sample, &Config::default()), Some(b';'));
+/// assert_eq!(suggest_delimiter(b"Name,Age\nJohn,25\n", &Config::default()), None);
+/// ```
+pub fn suggest_delimiter(sample: &[u8], config: &Config) -> Option<u8> {
+    const LINES: usize = 20;
+
+    if config.single_column_mode || (config.honor_sep_directive && sep_directive(sample).is_some())
+    {
+        return None;
+    }
+
+    let mut records = read_records(sample, config).take(LINES);
+    if records.any(|record| record.is_ok_and(|record| record.len() > 1)) {
+        return None;
+    }
+
+    let lines: Vec<&[u8]> = sample
+        .split(|&byte| byte == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|line| !line.is_empty())
+        .take(LINES)
+        .collect();
+    if lines.is_empty() {
+        return None;
+    }
+
+    [b';', b'\t', b'|', b',']
+        .into_iter()
+        .filter(|&candidate| candidate != config.delimiter)
+        .filter(|&candidate| lines.iter().all(|line| line.contains(&candidate)))
+        .max_by_key(|&candidate| {
+            lines
+                .iter()
+                .flat_map(|line| line.iter())
+                .filter(|&&byte| byte == candidate)
+                .count()
+        })
+}
+
+/// Convert each line of `input` into a row with a single cell, writing the
+/// table to `output` as the lines are read.
+///
+/// Unlike `single_column_mode`, this takes a [`BufRead`] and reads the input
+/// once, holding a single line in memory at a time, which suits long logs.
+/// Options which need to see every row before writing anything, like
+/// `front_matter`, `pretty` or `group_by`, can't be used with it.
+///
+/// # Example
+///
+/// ```rust
+/// use csvmd::{csv_to_markdown_lines, Config};
+///
+/// let mut output = Vec::new();
+/// csv_to_markdown_lines(&b"Message\nStarted, at last\n"[..], &mut output, Config::default())?;
+/// assert_eq!(
+///     String::from_utf8(output).unwrap(),
+///     "| Message |\n| --- |\n| Started, at last |\n"
+/// );
+/// # Ok::<(), csvmd::error::CsvMdError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns `CsvMdError` if reading or writing fails, or
+/// `CsvMdError::InvalidConfig` if an option needs every row to be read first.
+pub fn csv_to_markdown_lines<R: BufRead, W: Write>(
+    input: R,
+    output: W,
+    config: Config,
+) -> Result<()> {
+    let mut output = output_writer(output, &config);
+    write_table_optimistic(input.lines(), &mut output, &config)?;
+    finish_output(&mut output, &config)?;
+    Ok(())
+}
+
+/// A reader producing the Markdown table for CSV data as it's read, so a
+/// conversion can be used anywhere a [`Read`] is expected, such as the body
+/// of an HTTP response.
+///
+/// Rows are only pulled from the input when the Markdown written so far has
+/// been read, so like optimistic streaming the table's columns are taken from
+/// its first row. As with [`csv_to_markdown_streaming`], the delimiter is
+/// detected and `sep=` directives are honoured, but `detect_markdown` isn't
+/// applied.
+///
+/// # Example
+///
+/// ```rust
+/// use csvmd::{Config, MarkdownReader};
+/// use std::io::{Cursor, Read};
+///
+/// let mut reader = MarkdownReader::new(Cursor::new("Name,Age\nJohn,25"), Config::default())?;
+/// let mut markdown = String::new();
+/// reader.read_to_string(&mut markdown)?;
+/// assert_eq!(markdown, "| Name | Age |\n| --- | --- |\n| John | 25 |\n");
+/// # Ok::<(), csvmd::error::CsvMdError>(())
+/// ```
+///
+/// Errors while converting are returned from [`Read::read`] as
+/// [`std::io::Error`]s, wrapping the `CsvMdError` unless it was already an
+/// I/O error.
+pub struct MarkdownReader<R: Read> {
+    rows: ProcessedRows<'static, Records<std::io::Chain<std::io::Cursor<Vec<u8>>, R>>>,
+    writer: Option<TableWriter<'static>>,
+    output: CalloutWriter<TrailingNewlineWriter<Vec<u8>>>,
+    position: usize,
+    rows_read: usize,
+    finished: bool,
+}
+
+impl<R: Read> MarkdownReader<R> {
+    /// Create a reader converting `input` with `config`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CsvMdError` if the start of the input can't be read, or
+    /// `CsvMdError::InvalidConfig` if an option needs every row to be read
+    /// before any output is written.
+    pub fn new(mut input: R, mut config: Config) -> Result<Self> {
+        if config.requires_buffering() || config.requires_first_pass() {
+            return Err(CsvMdError::InvalidConfig(
+                "a MarkdownReader can't be used with options that need to read every row first"
+                    .to_string(),
+            ));
+        }
+
+        let mut prefix = read_prefix(&mut input, config.sniffed_bytes())?;
+        if config.detect_header {
+            config.has_headers = sniff_header(&prefix, &config);
+        }
+        if config.detect_delimiter {
+            config.delimiter = detect_delimiter(&prefix);
+        }
+        if let Some((delimiter, len)) =
+            sep_directive(&prefix).filter(|_| config.honor_sep_directive)
+        {
+            config.delimiter = delimiter;
+            prefix.drain(..len);
+        }
+
+        let records = read_records(std::io::Cursor::new(prefix).chain(input), &config);
+        let output = output_writer(Vec::new(), &config);
+        Ok(Self {
+            rows: RowPipeline::with_config(Cow::Owned(config)).rows(records),
+            writer: None,
+            output,
+            position: 0,
+            rows_read: 0,
+            finished: false,
+        })
+    }
+
+    /// Write rows into the buffer until there's something to read or the
+    /// input has run out.
+    fn fill_buffer(&mut self) -> Result<()> {
+        self.output.get_mut().get_mut().clear();
+        self.position = 0;
+
+        while !self.finished && self.output.get_mut().get_mut().is_empty() {
+            match self.rows.next().transpose()? {
+                Some(row) => {
+                    self.rows_read += 1;
+                    let writer = match &mut self.writer {
+                        Some(writer) => {
+                            writer.check_row_width(&row, self.rows_read)?;
+                            writer
+                        }
+                        None => self.writer.insert(TableWriter::from_first_row(
+                            &row,
+                            Cow::Owned(self.rows.pipeline.config.as_ref().clone()),
+                        )),
+                    };
+                    writer.write_row(&mut self.output, &row)?;
+                }
+                None => {
+                    if let Some(writer) = &mut self.writer {
+                        writer.finish(&mut self.output)?;
+                    }
+                    finish_output(&mut self.output, &self.rows.pipeline.config)?;
+                    self.finished = true;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for MarkdownReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.position == self.output.get_mut().get_mut().len() {
+            self.fill_buffer().map_err(|err| match err {
+                CsvMdError::Io(err) => err,
+                err => std::io::Error::new(std::io::ErrorKind::InvalidData, err),
+            })?;
+        }
+
+        let buffered = &self.output.get_mut().get_mut()[self.position..];
+        let len = buffered.len().min(buf.len());
+        buf[..len].copy_from_slice(&buffered[..len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+/// Estimate the size in bytes of the Markdown table for the given records.
+///
+/// This is the heuristic csvmd uses to pre-allocate its own output buffer, so
+/// library users writing their own writers can size buffers consistently. The
+/// estimate is an upper bound for plain tables, and saturates rather than
+/// overflowing for very large inputs.
+///
+/// # Example
+///
+/// ```rust
+/// use csvmd::{csv_to_markdown, estimated_markdown_size, Config};
+/// use std::io::Cursor;
+///
+/// let records = vec![
+///     vec!["Name".to_string(), "Age".to_string()],
+///     vec!["John".to_string(), "25".to_string()],
+/// ];
+/// let estimate = estimated_markdown_size(&records, &Config::default());
+///
+/// let markdown = csv_to_markdown(Cursor::new("Name,Age\nJohn,25"), Config::default())?;
+/// assert!(estimate >= markdown.len());
+///
+/// // Absurdly wide tables saturate instead of overflowing
+/// let config = Config {
+///     min_columns: Some(usize::MAX),
+///     ..Config::default()
+/// };
+/// assert_eq!(estimated_markdown_size(&records, &config), usize::MAX);
+/// # Ok::<(), csvmd::error::CsvMdError>(())
+/// ```
+pub fn estimated_markdown_size(records: &[Vec<String>], config: &Config) -> usize {
+    let columns = records
+        .iter()
+        .map(Vec::len)
+        .max()
+        .unwrap_or(0)
+        .max(config.min_columns.unwrap_or(0));
+
+    let cell_bytes = records
+        .iter()
+        .flat_map(|row| row.iter())
+        .fold(0usize, |total, cell| total.saturating_add(cell.len()));
+
+    // Each row is `|`, then ` cell |` per column, then a newline
+    let row_overhead = columns.saturating_mul(3).saturating_add(2);
+    // The separator is at most ` :---: |` per column, with the configured dashes
+    let separator_cell = config.separator_dashes.max(1).saturating_add(5);
+    let separator = columns.saturating_mul(separator_cell).saturating_add(2);
+
+    cell_bytes
+        .saturating_add(row_overhead.saturating_mul(records.len()))
+        .saturating_add(separator)
+        .saturating_add(100)
+}
+
+/// Read every record to determine the layout of the table.
+fn scan_layout<R: Read>(input: R, config: &Config) -> Result<TableLayout> {
+    let mut layout = TableLayout::default();
+    let mut rows = RowPipeline::new(config).rows(read_records(input, config));
+    for row in &mut rows {
+        layout.observe(&row?, config);
+    }
+    layout.observe_alignments(rows.alignments());
+
+    Ok(layout.finish(config))
+}
+
+/// Read every record again and write it to `output` using a precomputed layout.
+fn write_table<R: Read, W: Write>(
+    input: R,
+    output: &mut W,
+    layout: &TableLayout,
+    config: &Config,
+) -> Result<()> {
+    if write_empty_input_message(output, layout, config)? {
+        return Ok(());
+    }
+
+    let mut writer = TableWriter::new(layout, config);
+
+    for row in RowPipeline::new(config).rows(read_records(input, config)) {
+        writer.write_row(output, &row?)?;
+    }
+
+    writer.finish(output)
+}
+
+/// Write every record to `output` in a single pass, using a layout taken from
+/// the first row.
+fn write_table_optimistic<S, W>(records: S, output: &mut W, config: &Config) -> Result<()>
+where
+    S: RecordSource,
+    W: Write,
+{
+    if config.requires_buffering() || config.requires_first_pass() {
+        return Err(CsvMdError::InvalidConfig(
+            "single-pass streaming can't be used with options that need to read every row first"
+                .to_string(),
+        ));
+    }
+
+    let mut rows = RowPipeline::new(config).rows(records);
+    let Some(first) = rows.next().transpose()? else {
+        return Ok(());
+    };
+
+    let mut writer = TableWriter::from_first_row(&first, Cow::Borrowed(config));
+    writer.write_row(output, &first)?;
+
+    for (index, row) in rows.enumerate() {
+        let row = row?;
+        writer.check_row_width(&row, index + 2)?;
+        writer.write_row(output, &row)?;
+    }
+
+    writer.finish(output)
+}
+
+/// Write `Config::empty_input_message` if it's set and the input was empty,
+/// returning whether it was written.
+fn write_empty_input_message<W: Write>(
+    output: &mut W,
+    layout: &TableLayout,
+    config: &Config,
+) -> Result<bool> {
+    match &config.empty_input_message {
+        Some(message) if !layout.has_content => {
+            writeln!(output, "{}", message)?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// The shape of a table, determined by looking at every row before any output
+/// is written.
+#[derive(Debug, Clone, Default)]
+struct TableLayout {
+    /// Number of columns in every row of the table.
+    columns: usize,
+    /// Columns whose boolean values are rendered as checkboxes.
+    checkbox_columns: Vec<usize>,
+    /// For each column, whether every non-empty data cell seen so far is a
+    /// boolean, and whether any were.
+    boolean_columns_seen: Vec<(bool, bool)>,
+    /// For each column, whether every non-empty data cell seen so far is a
+    /// number, and whether any were.
+    numeric_columns_seen: Vec<(bool, bool)>,
+    /// Columns where every non-empty data cell is a number.
+    numeric_columns: Vec<usize>,
+    /// For each column, the [`dates::INPUT_FORMATS`] every non-empty data
+    /// cell seen so far parses in, as a bitmask, and whether there were any.
+    #[cfg(feature = "dates")]
+    date_formats_seen: Vec<(u16, bool)>,
+    /// Columns of dates, with the index of the input format they're read in.
+    #[cfg(feature = "dates")]
+    date_columns: Vec<(usize, usize)>,
+    /// The smallest and largest number in each of the
+    /// `Config::sparkline_columns`, in order, if there were any.
+    sparkline_ranges: Vec<Option<(f64, f64)>>,
+    /// Number of rows observed so far.
+    rows: usize,
+    /// Number of columns up to and including the last non-empty cell in any row.
+    non_empty_columns: usize,
+    /// Display width of the widest cell in each column, when width hints are enabled.
+    widths: Vec<usize>,
+    /// Whether any cell contains something other than whitespace.
+    has_content: bool,
+    /// Alignment for each column, falling back to `Config::header_alignment`.
+    alignments: Vec<HeaderAlignment>,
+}
+
+impl TableLayout {
+    /// Number of data rows, excluding the header.
+    fn data_rows(&self, config: &Config) -> usize {
+        if config.has_headers {
+            self.rows.saturating_sub(1)
+        } else {
+            self.rows
+        }
+    }
+
+    /// Whether the table has a header row but no data rows.
+    fn is_header_only(&self, config: &Config) -> bool {
+        config.has_headers && self.rows == 1
+    }
+
+    /// Account for a processed row.
+    fn observe(&mut self, row: &[String], config: &Config) {
+        let is_header = self.rows == 0 && config.has_headers;
+        self.rows += 1;
+        self.columns = self.columns.max(row.len());
+        if let Some(last) = row.iter().rposition(|cell| !cell.is_empty()) {
+            self.non_empty_columns = self.non_empty_columns.max(last + 1);
+        }
+        self.has_content = self.has_content || row.iter().any(|cell| !cell.trim().is_empty());
+
+        if config.emit_width_hints {
+            if self.widths.len() < row.len() {
+                self.widths.resize(row.len(), 0);
+            }
+            for (cell, width) in row.iter().zip(self.widths.iter_mut()) {
+                *width = (*width).max(cell.width());
+            }
+        }
+
+        if config.boolean_as_checkbox && config.boolean_columns.is_empty() && !is_header {
+            observe_column_kinds(&mut self.boolean_columns_seen, row, |cell| {
+                parse_bool(cell).is_some()
+            });
+        }
+
+        if config.detects_numeric_columns() && !is_header {
+            observe_column_kinds(&mut self.numeric_columns_seen, row, |cell| {
+                parse_number(cell).is_some()
+            });
+        }
+
+        #[cfg(feature = "dates")]
+        if config.date_format.is_some() && !is_header {
+            if self.date_formats_seen.len() < row.len() {
+                self.date_formats_seen.resize(row.len(), (u16::MAX, false));
+            }
+            for (cell, seen) in row.iter().zip(self.date_formats_seen.iter_mut()) {
+                if !cell.trim().is_empty() {
+                    *seen = (seen.0 & dates::matching_formats(cell), true);
+                }
+            }
+        }
+
+        if !config.sparkline_columns.is_empty() && !is_header {
+            self.sparkline_ranges
+                .resize(config.sparkline_columns.len(), None);
+            for (&column, range) in config
+                .sparkline_columns
+                .iter()
+                .zip(&mut self.sparkline_ranges)
+            {
+                let Some(value) = row.get(column).and_then(|cell| sparkline_value(cell)) else {
+                    continue;
+                };
+                *range = Some(match *range {
+                    Some((min, max)) => (min.min(value), max.max(value)),
+                    None => (value, value),
+                });
+            }
+        }
+    }
+
+    /// Account for the alignments read from an alignment row, if there was one.
+    fn observe_alignments(&mut self, alignments: Option<&[HeaderAlignment]>) {
+        if let Some(alignments) = alignments {
+            self.alignments = alignments.to_vec();
+        }
+    }
+
+    /// Apply the configured constraints once every row has been observed.
+    fn finish(mut self, config: &Config) -> Self {
+        if config.trim_trailing_empty_columns {
+            // Keep a single column for a table of empty cells, so it's still valid
+            self.columns = self.non_empty_columns.max(self.columns.min(1));
+        }
+
+        if !config.alignment_row && config.types_row.is_none() {
+            self.alignments = config.column_alignments.clone();
+        }
+
+        if config.bold_first_column && self.alignments.is_empty() {
+            self.alignments.push(HeaderAlignment::Left);
+        }
+
+        if let Some(min_columns) = config.min_columns {
+            self.columns = self.columns.max(min_columns);
+        }
+
+        if config.boolean_as_checkbox {
+            self.checkbox_columns = if config.boolean_columns.is_empty() {
+                columns_of_kind(&self.boolean_columns_seen).collect()
+            } else {
+                config.boolean_columns.clone()
+            };
+        }
+
+        self.numeric_columns = columns_of_kind(&self.numeric_columns_seen).collect();
+        if config.auto_align_numeric {
+            let explicit = self.alignments.len();
+            for &column in self.numeric_columns.iter().filter(|&&i| i >= explicit) {
+                self.alignments.resize(column + 1, config.header_alignment);
+                self.alignments[column] = HeaderAlignment::Right;
+            }
+        }
+
+        #[cfg(feature = "dates")]
+        {
+            self.date_columns = self
+                .date_formats_seen
+                .iter()
+                .enumerate()
+                .filter(|(_, &(formats, any))| formats != 0 && any)
+                .map(|(i, &(formats, _))| (i, formats.trailing_zeros() as usize))
+                .collect();
+        }
+
+        self
+    }
+}
+
+/// Track, for each column, whether every non-empty cell seen so far matches
+/// `is_kind`, and whether any did.
+fn observe_column_kinds(
+    seen: &mut Vec<(bool, bool)>,
+    row: &[String],
+    is_kind: impl Fn(&str) -> bool,
+) {
+    if seen.len() < row.len() {
+        seen.resize(row.len(), (true, false));
+    }
+
+    for (cell, seen) in row.iter().zip(seen.iter_mut()) {
+        if cell.trim().is_empty() {
+            continue;
+        }
+        if is_kind(cell) {
+            seen.1 = true;
+        } else {
+            seen.0 = false;
+        }
+    }
+}
+
+/// The columns where every non-empty cell matched, as tracked by
+/// [`observe_column_kinds`], ignoring columns which were always empty.
+fn columns_of_kind(seen: &[(bool, bool)]) -> impl Iterator<Item = usize> + '_ {
+    seen.iter()
+        .enumerate()
+        .filter(|(_, &(all, any))| all && any)
+        .map(|(i, _)| i)
+}
+
+/// Writes processed rows as Markdown table lines, adding the header separator
+/// after the header row.
+struct TableWriter<'a> {
+    layout: Cow<'a, TableLayout>,
+    config: Cow<'a, Config>,
+    rows_written: usize,
+    /// The header row as written, kept to repeat at the start of each table
+    /// when paginating.
+    header: Option<Vec<String>>,
+    /// Contents of cells moved into footnotes, in order of their numbers.
+    footnotes: Vec<String>,
+}
+
+impl<'a> TableWriter<'a> {
+    fn new(layout: &'a TableLayout, config: &'a Config) -> Self {
+        Self::with_layout(Cow::Borrowed(layout), Cow::Borrowed(config))
+    }
+
+    fn with_layout(layout: Cow<'a, TableLayout>, config: Cow<'a, Config>) -> Self {
+        Self {
+            layout,
+            config,
+            rows_written: 0,
+            header: None,
+            footnotes: Vec::new(),
+        }
+    }
+
+    /// Create a writer for single-pass streaming, taking the table's layout
+    /// from its first row.
+    fn from_first_row(first: &[String], config: Cow<'a, Config>) -> Self {
+        let mut layout = TableLayout::default();
+        layout.observe(first, &config);
+        let layout = layout.finish(&config);
+        Self::with_layout(Cow::Owned(layout), config)
+    }
+
+    /// Check that a row streamed after the first fits in the table's columns.
+    fn check_row_width(&self, row: &[String], row_number: usize) -> Result<()> {
+        if row.len() > self.layout.columns {
+            return Err(CsvMdError::ColumnCountChanged {
+                row: row_number,
+                expected: self.layout.columns,
+                found: row.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Write a single row, followed by the header separator if it's the header.
+    fn write_row<W: Write>(&mut self, output: &mut W, row: &[String]) -> Result<()> {
+        let is_header = self.rows_written == 0 && self.config.has_headers;
+
+        if self.layout.is_header_only(&self.config)
+            && self.config.empty_table_behavior == EmptyTableBehavior::Nothing
+        {
+            return Ok(());
+        }
+
+        if self.rows_written == 0 {
+            write_front_matter(output, &self.layout, &self.config)?;
+            write_caption(output, &self.config)?;
+            if self.config.emit_width_hints {
+                self.write_width_hints(output)?;
+            }
+            if self.config.synthetic_headers && !self.config.has_headers {
+                self.write_synthetic_header(output)?;
+            }
+        }
+
+        let mut row = Cow::Borrowed(row);
+
+        if is_header && self.config.bold_headers {
+            row = Cow::Owned(bold_cells(&row));
+        }
+
+        if !is_header {
+            // Measured before the numbers are formatted
+            let bars: Vec<(usize, String)> = self
+                .config
+                .sparkline_columns
+                .iter()
+                .zip(&self.layout.sparkline_ranges)
+                .filter_map(|(&column, range)| {
+                    let value = sparkline_value(row.get(column)?)?;
+                    Some((column, sparkline_bar(value, (*range)?)))
+                })
+                .collect();
+
+            for &column in &self.layout.checkbox_columns {
+                let checkbox = match row.get(column).and_then(|cell| parse_bool(cell)) {
+                    Some(true) => "[x]",
+                    Some(false) => "[ ]",
+                    None => continue,
+                };
+                row.to_mut()[column] = checkbox.to_string();
+            }
+
+            for &column in &self.layout.numeric_columns {
+                let format = self.config.column_number_format.get(column);
+                let formatted = match (format, row.get(column)) {
+                    (Some(Some(format)), Some(cell)) => format.format(cell),
+                    _ => None,
+                };
+                if let Some(formatted) = formatted {
+                    row.to_mut()[column] = formatted;
+                }
+            }
+
+            #[cfg(feature = "dates")]
+            if let Some(pattern) = &self.config.date_format {
+                for &(column, input) in &self.layout.date_columns {
+                    let Some(cell) = row.get(column) else {
+                        continue;
+                    };
+                    if let Some(formatted) = dates::reformat(cell, input, pattern)? {
+                        row.to_mut()[column] = formatted;
+                    }
+                }
+            }
+
+            if let Some(separator) = self.config.thousands_separator {
+                for &column in &self.layout.numeric_columns {
+                    let Some(cell) = row.get(column) else {
+                        continue;
+                    };
+                    if let Some(grouped) = group_thousands(cell, separator) {
+                        row.to_mut()[column] = grouped;
+                    }
+                }
+            }
+
+            for (column, bar) in bars {
+                let cell = &mut row.to_mut()[column];
+                cell.push(' ');
+                cell.push_str(&bar);
+            }
+
+            if let Some(max_length) = self.config.footnote_over {
+                for column in 0..row.len() {
+                    if row[column].chars().count() > max_length {
+                        let content = std::mem::take(&mut row.to_mut()[column]);
+                        self.footnotes.push(content);
+                        row.to_mut()[column] = format!("[^{}]", self.footnotes.len());
+                    }
+                }
+            }
+
+            if self.config.bold_first_column && !row.is_empty() {
+                let first = bold_cells(&row[..1]).remove(0);
+                row.to_mut()[0] = first;
+            }
+        }
+
+        let rows_per_table = self.config.rows_per_table.filter(|&rows| rows > 0);
+        if let Some(rows_per_table) = rows_per_table.filter(|_| !is_header) {
+            let data_row = self.rows_written - usize::from(self.config.has_headers);
+            if data_row > 0 && data_row.is_multiple_of(rows_per_table) {
+                self.start_next_table(output)?;
+            }
+        }
+
+        self.write_line(output, &row, is_header)?;
+
+        if is_header && rows_per_table.is_some() {
+            self.header = Some(row.into_owned());
+        }
+
+        self.rows_written += 1;
+        Ok(())
+    }
+
+    /// Write a header row naming the columns `Column 1`, `Column 2` and so on.
+    fn write_synthetic_header<W: Write>(&mut self, output: &mut W) -> Result<()> {
+        let mut header = synthetic_header(self.layout.columns);
+        if self.config.bold_headers {
+            header = bold_cells(&header);
+        }
+
+        self.write_line(output, &header, true)?;
+        if self.config.rows_per_table.is_some() {
+            self.header = Some(header);
+        }
+        Ok(())
+    }
+
+    /// Write the divider between tables when paginating, followed by the
+    /// header again if there is one.
+    fn start_next_table<W: Write>(&self, output: &mut W) -> Result<()> {
+        write!(output, "\n{}\n\n", self.config.page_divider)?;
+        if let Some(header) = &self.header {
+            self.write_line(output, header, true)?;
+        }
+        Ok(())
+    }
+
+    /// Write a row's cells, along with its number and the header separator
+    /// where they're needed.
+    fn write_line<W: Write>(&self, output: &mut W, row: &[String], is_header: bool) -> Result<()> {
+        let separator_before = self.config.separator_position == SeparatorPosition::BeforeFirst;
+        if is_header && separator_before {
+            self.write_separator(output)?;
+        }
+
+        if self.config.row_numbers {
+            let number = if is_header {
+                "#".to_string()
+            } else {
+                let index = self.rows_written - usize::from(self.config.has_headers);
+                let number = self.config.row_number_start.saturating_add(index as i64);
+                if self.config.zero_pad_row_numbers {
+                    format!("{:0width$}", number, width = self.row_number_width())
+                } else {
+                    number.to_string()
+                }
+            };
+            let missing = match self.config.pad_side {
+                PadSide::End => 0,
+                PadSide::Start => self.layout.columns.saturating_sub(row.len()),
+            };
+            let numbered: Vec<String> = std::iter::once(number)
+                .chain(std::iter::repeat_n(String::new(), missing))
+                .chain(row.iter().take(self.layout.columns).cloned())
+                .collect();
+            write_table_row(output, &numbered, self.columns(), &self.config)?;
+        } else {
+            write_table_row(output, row, self.columns(), &self.config)?;
+        }
+
+        // Add header separator after first row if configured
+        if is_header && !separator_before {
+            self.write_separator(output)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the width of the widest cell in each column as an HTML comment.
+    fn write_width_hints<W: Write>(&self, output: &mut W) -> Result<()> {
+        let mut widths = Vec::with_capacity(self.columns());
+        if self.config.row_numbers {
+            widths.push("#".len().max(self.row_number_width()).to_string());
+        }
+        let synthetic_header = if self.config.synthetic_headers && !self.config.has_headers {
+            synthetic_header(self.layout.columns)
+        } else {
+            Vec::new()
+        };
+        for column in 0..self.layout.columns {
+            let width = self.layout.widths.get(column).copied().unwrap_or(0);
+            let header_width = synthetic_header.get(column).map_or(0, |name| name.width());
+            widths.push(width.max(header_width).to_string());
+        }
+
+        writeln!(output, "<!-- csvmd:widths={} -->", widths.join(","))?;
+        Ok(())
+    }
+
+    /// Width of the widest row number, from the first or last data row.
+    fn row_number_width(&self) -> usize {
+        let data_rows = self.layout.data_rows(&self.config);
+        let last = self
+            .config
+            .row_number_start
+            .saturating_add(data_rows.saturating_sub(1) as i64);
+        let first = self.config.row_number_start.to_string().len();
+        first.max(last.to_string().len())
+    }
+
+    /// Number of columns in each line, including any row numbers.
+    fn columns(&self) -> usize {
+        self.layout.columns + usize::from(self.config.row_numbers)
+    }
+
+    /// Write the header separator line.
+    fn write_separator<W: Write>(&self, output: &mut W) -> Result<()> {
+        let alignments = if self.config.row_numbers && !self.layout.alignments.is_empty() {
+            let mut alignments = vec![self.config.header_alignment];
+            alignments.extend_from_slice(&self.layout.alignments);
+            Cow::Owned(alignments)
+        } else {
+            Cow::Borrowed(self.layout.alignments.as_slice())
+        };
+
+        write_header_separator(output, self.columns(), &alignments, &self.config)
+    }
+
+    /// Finish the table once every row has been written.
+    fn finish<W: Write>(&mut self, output: &mut W) -> Result<()> {
+        if self.layout.is_header_only(&self.config)
+            && self.config.empty_table_behavior == EmptyTableBehavior::HeaderWithEmptyRow
+        {
+            write_table_row(output, &[], self.columns(), &self.config)?;
+        }
+
+        write_footnotes(output, &self.footnotes)
+    }
+}
+
+/// Write the cells moved into footnotes by `Config::footnote_over` below the
+/// table, numbered in order.
+fn write_footnotes<W: Write>(output: &mut W, footnotes: &[String]) -> Result<()> {
+    if !footnotes.is_empty() {
+        writeln!(output)?;
+        for (i, content) in footnotes.iter().enumerate() {
+            writeln!(output, "[^{}]: {}", i + 1, content)?;
+        }
+    }
+    Ok(())
+}
+
+/// Wrap `output` in the writers applying the whole-output options from
+/// `config`.
+fn output_writer<W: Write>(output: W, config: &Config) -> CalloutWriter<TrailingNewlineWriter<W>> {
+    CalloutWriter::new(
+        TrailingNewlineWriter::new(output, config.ends_with_newline()),
+        config.callout,
+    )
+}
+
+/// Finish writing to an [`output_writer`], adding the `source_footer` and the
+/// blank line after the output outside of any callout.
+fn finish_output<W: Write>(
+    output: &mut CalloutWriter<TrailingNewlineWriter<W>>,
+    config: &Config,
+) -> std::io::Result<()> {
+    let inner = output.get_mut();
+    if config.source_footer && inner.written {
+        writeln!(inner, "\n{}", source_footer(config, SystemTime::now()))?;
+    }
+    if config.trailing_blank_line && inner.written {
+        inner.write_all(b"\n")?;
+    }
+    output.flush()
+}
+
+/// The attribution line for `Config::source_footer`, for output written at
+/// `time`.
+fn source_footer(config: &Config, time: SystemTime) -> String {
+    match &config.source_name {
+        Some(name) => format!("_Generated from {} on {}_", name, iso8601_utc(time)),
+        None => format!("_Generated on {}_", iso8601_utc(time)),
+    }
+}
+
+/// Format `time` in ISO 8601 in UTC, to the second, like
+/// `2024-01-01T12:00:00Z`.
+fn iso8601_utc(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let (days, seconds) = (seconds / 86_400, seconds % 86_400);
+
+    // Turn days since 1970-01-01 into a date, counting in 400-year eras
+    // which start on the 1st of March, so leap days come at the end
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// A writer which can leave out the final newline of the output, by holding
+/// back each newline at the end of a write until something follows it.
+struct TrailingNewlineWriter<W> {
+    inner: W,
+    trailing_newline: bool,
+    pending_newline: bool,
+    written: bool,
+}
+
+impl<W: Write> TrailingNewlineWriter<W> {
+    fn new(inner: W, trailing_newline: bool) -> Self {
+        Self {
+            inner,
+            trailing_newline,
+            pending_newline: false,
+            written: false,
+        }
+    }
+
+    fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+}
+
+impl<W: Write> Write for TrailingNewlineWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.written |= !buf.is_empty();
+        if self.trailing_newline || buf.is_empty() {
+            return self.inner.write(buf);
+        }
+
+        if self.pending_newline {
+            self.inner.write_all(b"\n")?;
+            self.pending_newline = false;
+        }
+
+        match buf.strip_suffix(b"\n") {
+            Some(content) => {
+                self.inner.write_all(content)?;
+                self.pending_newline = true;
+            }
+            None => self.inner.write_all(buf)?,
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A writer which wraps everything written through it in a callout, writing
+/// the callout's marker line first and `> ` at the start of every line.
+///
+/// Without a callout, writes are passed through unchanged.
+struct CalloutWriter<W> {
+    inner: W,
+    callout: Option<CalloutKind>,
+    started: bool,
+    at_line_start: bool,
+}
+
+impl<W: Write> CalloutWriter<W> {
+    fn new(inner: W, callout: Option<CalloutKind>) -> Self {
+        Self {
+            inner,
+            callout,
+            started: false,
+            at_line_start: true,
+        }
+    }
+
+    fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+}
+
+impl<W: Write> Write for CalloutWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let Some(callout) = self.callout else {
+            return self.inner.write(buf);
+        };
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if !self.started {
+            writeln!(self.inner, "> {}", callout.marker())?;
+            self.started = true;
+        }
+
+        for line in buf.split_inclusive(|&byte| byte == b'\n') {
+            if self.at_line_start {
+                // Blank lines still need a `>` to stay inside the callout
+                let prefix: &[u8] = if line == b"\n" { b">" } else { b"> " };
+                self.inner.write_all(prefix)?;
+            }
+            self.inner.write_all(line)?;
+            self.at_line_start = line.ends_with(b"\n");
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Names for the columns of a table without a header row, starting from
+/// `Column 1`.
+fn synthetic_header(columns: usize) -> Vec<String> {
+    (1..=columns).map(|i| format!("Column {}", i)).collect()
+}
+
+/// Wrap the text of each non-empty cell in `**`.
+fn bold_cells(row: &[String]) -> Vec<String> {
+    row.iter()
+        .map(|cell| match cell.trim() {
+            "" => String::new(),
+            text => format!("**{}**", text),
+        })
+        .collect()
+}
+
+/// Write the YAML front matter block, if configured.
+fn write_front_matter<W: Write>(
+    output: &mut W,
+    layout: &TableLayout,
+    config: &Config,
+) -> Result<()> {
+    let Some(front_matter) = &config.front_matter else {
+        return Ok(());
+    };
+
+    writeln!(output, "---")?;
+    for (key, value) in front_matter {
+        writeln!(output, "{}: {}", yaml_key(key), yaml_string(value))?;
+    }
+    if !front_matter.contains_key("columns") {
+        writeln!(output, "columns: {}", layout.columns)?;
+    }
+    if !front_matter.contains_key("rows") {
+        writeln!(output, "rows: {}", layout.data_rows(config))?;
+    }
+    writeln!(output, "---")?;
+    writeln!(output)?;
+
+    Ok(())
+}
+
+/// Write the caption as a bold paragraph, if configured.
+fn write_caption<W: Write>(output: &mut W, config: &Config) -> Result<()> {
+    if let Some(caption) = &config.caption {
+        writeln!(output, "**{}**\n", caption)?;
+    }
+    Ok(())
+}
+
+/// Format a YAML mapping key, quoting it unless it's a plain identifier.
+fn yaml_key(key: &str) -> Cow<'_, str> {
+    let is_plain = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+
+    if is_plain {
+        Cow::Borrowed(key)
+    } else {
+        Cow::Owned(yaml_string(key))
+    }
+}
+
+/// Format a value as a double-quoted YAML string.
+fn yaml_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Write a `Row | Value` table for each column, under a heading with the
+/// column's name.
+fn write_column_tables<W: Write>(
+    output: &mut W,
+    records: &[Vec<String>],
+    layout: &TableLayout,
+    config: &Config,
+) -> Result<()> {
+    write_front_matter(output, layout, config)?;
+    write_caption(output, config)?;
+
+    let (header, data) = match records.split_first() {
+        Some((header, data)) if config.has_headers => (Some(header), data),
+        _ => (None, records),
+    };
+
+    for column in 0..layout.columns {
+        if column > 0 {
+            writeln!(output)?;
+        }
+
+        match header.and_then(|header| header.get(column)) {
+            Some(name) if !name.trim().is_empty() => writeln!(output, "## {}", name.trim())?,
+            _ => writeln!(output, "## Column {}", column + 1)?,
+        }
+        writeln!(output)?;
+
+        write_table_row(output, &["Row".to_string(), "Value".to_string()], 2, config)?;
+        write_header_separator(output, 2, &[], config)?;
+        for (i, row) in data.iter().enumerate() {
+            let value = row.get(column).cloned().unwrap_or_default();
+            write_table_row(output, &[(i + 1).to_string(), value], 2, config)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Write a `Column | Inferred Type` table with a row for each column, for
+/// `schema_table`.
+fn write_schema_table<W: Write>(
+    output: &mut W,
+    records: &[Vec<String>],
+    layout: &TableLayout,
+    config: &Config,
+) -> Result<()> {
+    let (header, data) = match records.split_first() {
+        Some((header, data)) if config.has_headers => (Some(header), data),
+        _ => (None, records),
+    };
+
+    let title = ["Column".to_string(), "Inferred Type".to_string()];
+    write_table_row(output, &title, 2, config)?;
+    write_header_separator(output, 2, &[], config)?;
+    for column in 0..layout.columns {
+        let name = match header.and_then(|header| header.get(column)) {
+            Some(name) if !name.trim().is_empty() => name.trim().to_string(),
+            _ => format!("Column {}", column + 1),
+        };
+        let cells = data.iter().filter_map(|row| row.get(column));
+        let kind = infer_column_type(cells.map(String::as_str)).to_string();
+        write_table_row(output, &[name, kind], 2, config)?;
+    }
+
+    Ok(())
+}
+
+/// The type of a column for `schema_table`: the first of `Integer`,
+/// `Number`, `Boolean` and `Date` (with the `dates` feature) which every
+/// non-empty cell is, or else `Text`, including for a column of empty cells.
+fn infer_column_type<'a>(cells: impl Iterator<Item = &'a str>) -> &'static str {
+    let cells: Vec<&str> = cells
+        .map(str::trim)
+        .filter(|cell| !cell.is_empty())
+        .collect();
+    if cells.is_empty() {
+        return "Text";
+    }
+
+    if cells.iter().all(|cell| cell.parse::<i64>().is_ok()) {
+        "Integer"
+    } else if cells.iter().all(|cell| {
+        matches!(
+            parse_number(cell),
+            Some(NumberKind::Decimal | NumberKind::Scientific)
+        )
+    }) {
+        "Number"
+    } else if cells.iter().all(|cell| parse_bool(cell).is_some()) {
+        "Boolean"
+    } else if is_date_column(&cells) {
+        "Date"
+    } else {
+        "Text"
+    }
+}
+
+/// Whether every cell is a date in the same format.
+#[cfg(feature = "dates")]
+fn is_date_column(cells: &[&str]) -> bool {
+    cells.iter().fold(u16::MAX, |formats, cell| {
+        formats & dates::matching_formats(cell)
+    }) != 0
+}
+
+/// Dates aren't recognised without the `dates` feature.
+#[cfg(not(feature = "dates"))]
+fn is_date_column(_cells: &[&str]) -> bool {
+    false
+}
+
+/// Write a table for each distinct value in the `group_by` column, under a
+/// heading with the value, optionally after a table of contents.
+fn write_grouped_tables<W: Write>(
+    output: &mut W,
+    records: &[Vec<String>],
+    group_by: &ColumnRef,
+    layout: &TableLayout,
+    config: &Config,
+) -> Result<()> {
+    let (header, data) = match records.split_first() {
+        Some((header, data)) if config.has_headers => (Some(header), data),
+        _ => (None, records),
+    };
+
+    let column = match group_by {
+        ColumnRef::Index(index) => {
+            let columns = records.iter().map(Vec::len).max().unwrap_or(0);
+            if *index >= columns {
+                return Err(CsvMdError::ColumnNotFound(index.to_string()));
+            }
+            *index
+        }
+        ColumnRef::Name(name) => header
+            .and_then(|header| header.iter().position(|cell| cell == name))
+            .ok_or_else(|| CsvMdError::ColumnNotFound(name.clone()))?,
+    };
+
+    // Group the rows, keeping the groups in the order they first appear
+    let mut groups: Vec<(&str, Vec<&Vec<String>>)> = Vec::new();
+    let mut group_indices = BTreeMap::new();
+    for row in data {
+        let value = row.get(column).map(|cell| cell.trim()).unwrap_or("");
+        let index = *group_indices.entry(value).or_insert_with(|| {
+            groups.push((value, Vec::new()));
+            groups.len() - 1
+        });
+        groups[index].1.push(row);
+    }
+
+    let headings: Vec<&str> = groups
+        .iter()
+        .map(|(value, _)| if value.is_empty() { "(empty)" } else { value })
+        .collect();
+
+    write_front_matter(output, layout, config)?;
+    write_caption(output, config)?;
+
+    if config.toc && !groups.is_empty() {
+        let mut seen = BTreeMap::new();
+        for heading in &headings {
+            writeln!(
+                output,
+                "- [{}](#{})",
+                heading,
+                github_anchor(heading, &mut seen)
+            )?;
+        }
+        writeln!(output)?;
+    }
+
+    // Each group's table is written like a whole table, except that the front
+    // matter and caption only come once, at the top, and the footnotes are
+    // numbered across the groups and written at the end
+    let table_config = Config {
+        front_matter: None,
+        caption: None,
+        ..config.clone()
+    };
+    let mut footnotes = Vec::new();
+    for (i, ((_, rows), heading)) in groups.iter().zip(&headings).enumerate() {
+        if i > 0 {
+            writeln!(output)?;
+        }
+
+        writeln!(output, "## {}", heading)?;
+        writeln!(output)?;
+
+        let mut writer = TableWriter::new(layout, &table_config);
+        writer.footnotes = footnotes;
+        for row in header.into_iter().chain(rows.iter().copied()) {
+            writer.write_row(output, row)?;
+        }
+        footnotes = writer.footnotes;
+    }
+    write_footnotes(output, &footnotes)?;
+
+    Ok(())
+}
+
+/// Generate the anchor GitHub links a heading to: lowercased, with spaces
+/// turned into hyphens and other punctuation removed. Repeated anchors get a
+/// numbered suffix, counted in `seen`.
+fn github_anchor(heading: &str, seen: &mut BTreeMap<String, usize>) -> String {
+    let anchor: String = heading
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .collect();
+
+    let count = seen.entry(anchor.clone()).or_insert(0);
+    *count += 1;
+    match *count {
+        1 => anchor,
+        n => format!("{}-{}", anchor, n - 1),
+    }
+}
+
+/// How a number in a cell is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NumberKind {
+    /// Plain decimal notation, like `-1234.5`.
+    Decimal,
+    /// Scientific notation, like `1e10`.
+    Scientific,
+    /// `NaN` or infinity.
+    NonFinite,
+}
+
+/// Parse a cell as a number, accepting signs, scientific notation, `NaN` and
+/// infinity.
+fn parse_number(cell: &str) -> Option<NumberKind> {
+    let cell = cell.trim();
+    cell.parse::<f64>().ok()?;
+
+    Some(if !cell.contains(|c: char| c.is_ascii_digit()) {
+        NumberKind::NonFinite
+    } else if cell.contains(['e', 'E']) {
+        NumberKind::Scientific
+    } else {
+        NumberKind::Decimal
+    })
+}
+
+/// The number in a cell for `Config::sparkline_columns`, if it's finite.
+fn sparkline_value(cell: &str) -> Option<f64> {
+    cell.trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+}
+
+/// A bar of up to eight block characters showing where `value`
+/// falls in `(min, max)`, drawn in eighths of a character. The smallest
+/// value gets a sliver, so every number has a bar, and the largest gets the
+/// whole width, as do all of them when they're the same.
+fn sparkline_bar(value: f64, (min, max): (f64, f64)) -> String {
+    const SPARKLINE_WIDTH: usize = 8;
+    const EIGHTHS: [char; 8] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+
+    let fraction = if max > min {
+        (value - min) / (max - min)
+    } else {
+        1.0
+    };
+    let steps = SPARKLINE_WIDTH * 8 - 1;
+    let eighths = 1 + (fraction * steps as f64).round() as usize;
+
+    let mut bar = "█".repeat(eighths / 8);
+    match eighths % 8 {
+        0 => {}
+        partial => bar.push(EIGHTHS[partial - 1]),
+    }
+    bar
+}
+
+/// Build the summary row for `rows`, the data rows of a table.
+fn summary_row(rows: &[Vec<String>], kind: SummaryKind) -> Vec<String> {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0).max(1);
+    let mut summary: Vec<String> = (0..columns)
+        .map(|column| summarise_column(rows, column, kind).unwrap_or_default())
+        .collect();
+    summary[0] = kind.label().to_string();
+    summary
+}
+
+/// Aggregate the values in a column, or return `None` if any non-empty cell
+/// isn't a finite number.
+fn summarise_column(rows: &[Vec<String>], column: usize, kind: SummaryKind) -> Option<String> {
+    let mut values = Vec::new();
+    let mut decimals = 0;
+    let mut scientific = false;
+
+    let cells = rows
+        .iter()
+        .filter_map(|row| row.get(column))
+        .map(|cell| cell.trim())
+        .filter(|cell| !cell.is_empty());
+    for cell in cells {
+        match parse_number(cell)? {
+            NumberKind::NonFinite => return None,
+            NumberKind::Scientific => scientific = true,
+            NumberKind::Decimal => {
+                let places = cell
+                    .split_once('.')
+                    .map_or(0, |(_, fraction)| fraction.len());
+                decimals = decimals.max(places);
+            }
+        }
+        values.push(cell.parse::<f64>().ok()?);
+    }
+
+    if values.is_empty() {
+        return None;
+    }
+
+    let sum: f64 = values.iter().sum();
+    Some(match kind {
+        SummaryKind::Count => values.len().to_string(),
+        SummaryKind::Sum if scientific => sum.to_string(),
+        // Match the precision of the values, avoiding float noise like `0.30000000000000004`
+        SummaryKind::Sum => format!("{:.*}", decimals, sum),
+        SummaryKind::Mean if scientific => (sum / values.len() as f64).to_string(),
+        SummaryKind::Mean => {
+            let mean = format!("{:.*}", decimals + 2, sum / values.len() as f64);
+            mean.trim_end_matches('0').trim_end_matches('.').to_string()
+        }
+    })
+}
+
+/// Group the integer digits of a decimal number into thousands, returning
+/// `None` if the cell isn't a decimal number, has a leading zero or doesn't
+/// need separators.
+fn group_thousands(cell: &str, separator: char) -> Option<String> {
+    if parse_number(cell)? != NumberKind::Decimal {
+        return None;
+    }
+
+    let cell = cell.trim();
+    let (sign, unsigned) = match cell.strip_prefix(['-', '+']) {
+        Some(rest) => cell.split_at(cell.len() - rest.len()),
+        None => ("", cell),
+    };
+    let (integer, fraction) = match unsigned.find('.') {
+        Some(dot) => unsigned.split_at(dot),
+        None => (unsigned, ""),
+    };
+    if integer.len() <= 3 || integer.starts_with('0') {
+        return None;
+    }
+
+    let mut grouped = String::with_capacity(cell.len() + integer.len() / 3);
+    grouped.push_str(sign);
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped.push_str(fraction);
+    Some(grouped)
+}
+
+/// Parse a cell as a boolean, accepting `true`/`false`, `yes`/`no` and `1`/`0`
+/// in any case.
+fn parse_bool(cell: &str) -> Option<bool> {
+    let cell = cell.trim();
+    if ["true", "yes", "1"]
+        .iter()
+        .any(|v| cell.eq_ignore_ascii_case(v))
+    {
+        Some(true)
+    } else if ["false", "no", "0"]
+        .iter()
+        .any(|v| cell.eq_ignore_ascii_case(v))
+    {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Escape Markdown special characters in a CSV cell.
+///
+/// This function handles:
+/// - Pipe characters (`|`) → escaped as `\|`, or `&#124;` with
+///   [`PipeEscape::Entity`]
+/// - Newlines (`\n`) → converted to `<br>` tags
+/// - Carriage returns (`\r`) → removed
+pub(crate) fn escape_markdown_cell(field: &str, pipe_escape: PipeEscape) -> Cow<'_, str> {
+    if !field.contains(['|', '\n', '\r']) {
+        return Cow::Borrowed(field);
+    }
+
+    let mut escaped = String::with_capacity(field.len() + 8);
+    for c in field.chars() {
+        match c {
+            '|' => escaped.push_str(match pipe_escape {
+                PipeEscape::Backslash => "\\|",
+                PipeEscape::Entity => "&#124;",
+            }),
+            '\n' => escaped.push_str("<br>"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Check that every table in `markdown` follows the rules for GitHub Flavored
+/// Markdown tables: a header row, then a separator row, then rows with the
+/// same number of cells as the header.
+///
+/// Tables are runs of lines starting with `|`, inside a callout if there is
+/// one. Any other lines, like headings and footnotes, aren't checked.
+fn validate_markdown(markdown: &str) -> Result<()> {
+    let mut header: Option<usize> = None;
+    let mut rows_in_table = 0;
+
+    for (i, line) in markdown.lines().enumerate() {
+        let invalid = |reason: String| CsvMdError::InvalidOutput {
+            line: i + 1,
+            reason,
+        };
+
+        let line = line.strip_prefix("> ").unwrap_or(line);
+        if !line.starts_with('|') {
+            if rows_in_table == 1 {
+                return Err(CsvMdError::InvalidOutput {
+                    line: i,
+                    reason: "table has no separator row".to_string(),
+                });
+            }
+            header = None;
+            rows_in_table = 0;
+            continue;
+        }
+
+        let cells = split_table_row(line)
+            .ok_or_else(|| invalid("row doesn't end with an unescaped `|`".to_string()))?;
+        rows_in_table += 1;
+
+        let Some(expected) = header else {
+            header = Some(cells.len());
+            continue;
+        };
+        if cells.len() != expected {
+            return Err(invalid(format!(
+                "row has {} cells but the header has {}",
+                cells.len(),
+                expected
+            )));
+        }
+        if rows_in_table == 2 && !cells.iter().all(|cell| is_separator_cell(cell)) {
+            return Err(invalid("table has no separator row".to_string()));
+        }
+    }
+
+    if rows_in_table == 1 {
+        return Err(CsvMdError::InvalidOutput {
+            line: markdown.lines().count(),
+            reason: "table has no separator row".to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Split a table row into its cells, treating a `|` after a backslash as
+/// part of a cell. Returns `None` if the row doesn't end with a `|`.
+fn split_table_row(line: &str) -> Option<Vec<&str>> {
+    let line = line.trim_end().strip_prefix('|')?;
+
+    let mut cells = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '|' => {
+                cells.push(&line[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    line[start..].is_empty().then_some(cells)
+}
+
+/// Replace the lines of every table in `markdown` with the lines `rewrite`
+/// returns for its rows, which are given with the prefix of each line.
+///
+/// Tables are found like in [`validate_markdown`], and any with rows of
+/// different lengths are left as they are.
+fn rewrite_tables<F>(markdown: &str, mut rewrite: F) -> String
+where
+    F: FnMut(&[(&str, Vec<&str>)]) -> Vec<String>,
+{
+    let mut lines = Vec::new();
+    let mut table: Vec<(&str, Vec<&str>)> = Vec::new();
+
+    let body = markdown.strip_suffix('\n').unwrap_or(markdown);
+    for line in body.split('\n') {
+        let (prefix, content) = match line.strip_prefix("> ") {
+            Some(content) => ("> ", content),
+            None => ("", line),
+        };
+        let cells = content
+            .starts_with('|')
+            .then(|| split_table_row(content))
+            .flatten();
+        if let Some(cells) = &cells {
+            if table
+                .first()
+                .is_none_or(|(_, first)| first.len() == cells.len())
+            {
+                table.push((prefix, cells.clone()));
+                continue;
+            }
+        }
+
+        lines.extend(rewrite(&table));
+        table.clear();
+        match cells {
+            Some(cells) => table.push((prefix, cells)),
+            None => lines.push(line.to_string()),
+        }
+    }
+    lines.extend(rewrite(&table));
+
+    let mut rewritten = lines.join("\n");
+    if markdown.ends_with('\n') {
+        rewritten.push('\n');
+    }
+    rewritten
+}
+
+/// The lines of a table with its cells padded to the width of their column,
+/// each after its line's prefix, for `Config::pretty`.
+fn pad_table(rows: &[(&str, Vec<&str>)], config: &Config) -> Vec<String> {
+    let separator = rows
+        .iter()
+        .position(|(_, cells)| cells.iter().all(|cell| is_separator_cell(cell)));
+    let columns = rows.first().map_or(0, |(_, cells)| cells.len());
+
+    let mut widths = vec![0; columns];
+    for (i, (_, cells)) in rows.iter().enumerate() {
+        for (cell, width) in cells.iter().zip(widths.iter_mut()) {
+            let cell = cell.trim();
+            // Separators can shrink to the widest cell, but not below their dashes
+            *width = (*width).max(if Some(i) == separator {
+                cell.len()
+            } else {
+                cell.width()
+            });
+        }
+    }
+
+    let padding = if config.pads_cells() { " " } else { "" };
+    let mut lines = Vec::with_capacity(rows.len());
+    for (i, (prefix, cells)) in rows.iter().enumerate() {
+        let mut padded = format!("{}|", prefix);
+        for (cell, &width) in cells.iter().zip(&widths) {
+            let cell = cell.trim();
+            let cell = if Some(i) == separator {
+                let left = if cell.starts_with(':') { ":" } else { "" };
+                let right = if cell.len() > 1 && cell.ends_with(':') {
+                    ":"
+                } else {
+                    ""
+                };
+                let dashes = "-".repeat(width - left.len() - right.len());
+                format!("{}{}{}", left, dashes, right)
+            } else {
+                let fill = " ".repeat(width - cell.width());
+                if config.numeric_pad_right && parse_number(cell).is_some() {
+                    format!("{}{}", fill, cell)
+                } else {
+                    format!("{}{}", cell, fill)
+                }
+            };
+            padded.push_str(padding);
+            padded.push_str(&cell);
+            padded.push_str(closing_padding(&cell, padding));
+            padded.push('|');
+        }
+        lines.push(padded);
+    }
+    lines
+}
+
+/// The lines of a table with its cells cut short so every line fits within
+/// `max_width`, for `Config::max_table_width`, padded like [`pad_table`] with
+/// `Config::pretty`.
+fn fit_table(rows: &[(&str, Vec<&str>)], max_width: usize, config: &Config) -> Vec<String> {
+    let separator = rows
+        .iter()
+        .position(|(_, cells)| cells.iter().all(|cell| is_separator_cell(cell)));
+    let columns = rows.first().map_or(0, |(_, cells)| cells.len());
+
+    let mut widths = vec![0; columns];
+    let mut minimums = vec![0; columns];
+    for (i, (_, cells)) in rows.iter().enumerate() {
+        for (column, cell) in cells.iter().enumerate() {
+            let cell = cell.trim();
+            if Some(i) == separator {
+                minimums[column] = cell.len();
+            }
+            widths[column] = widths[column].max(cell.width());
+        }
+    }
+
+    let prefix = rows.first().map_or(0, |(prefix, _)| prefix.width());
+    let per_column = if config.pads_cells() { 3 } else { 1 };
+    let available = max_width.saturating_sub(prefix + 1 + per_column * columns);
+    let widths = share_width(&widths, &minimums, available);
+
+    let fitted: Vec<(&str, Vec<Cow<'_, str>>)> = rows
+        .iter()
+        .map(|(prefix, cells)| {
+            let cells = cells.iter().zip(&widths);
+            let cells = cells.map(|(cell, &width)| truncate_cell(cell.trim(), width));
+            (*prefix, cells.collect())
+        })
+        .collect();
+
+    if config.pretty {
+        let fitted: Vec<(&str, Vec<&str>)> = fitted
+            .iter()
+            .map(|(prefix, cells)| (*prefix, cells.iter().map(AsRef::as_ref).collect()))
+            .collect();
+        return pad_table(&fitted, config);
+    }
+
+    let padding = if config.pads_cells() { " " } else { "" };
+    fitted
+        .iter()
+        .map(|(prefix, cells)| {
+            let mut line = format!("{}|", prefix);
+            for cell in cells {
+                line.push_str(padding);
+                line.push_str(cell);
+                line.push_str(closing_padding(cell, padding));
+                line.push('|');
+            }
+            line
+        })
+        .collect()
+}
+
+/// Share `available` columns of space between columns with the given
+/// `widths`, in proportion to their widths but no less than their
+/// `minimums`.
+fn share_width(widths: &[usize], minimums: &[usize], available: usize) -> Vec<usize> {
+    let total: usize = widths.iter().sum();
+    if total <= available {
+        return widths.to_vec();
+    }
+
+    let mut shares: Vec<usize> = widths
+        .iter()
+        .zip(minimums)
+        .map(|(&width, &minimum)| {
+            (width * available / total)
+                .max(minimum)
+                .min(width.max(minimum))
+        })
+        .collect();
+
+    // Rounding down and the minimums leave the total a little off, so take
+    // from, or give back to, whichever column it makes the most difference to
+    loop {
+        let used: usize = shares.iter().sum();
+        let column = if used > available {
+            (0..shares.len())
+                .filter(|&i| shares[i] > minimums[i])
+                .max_by_key(|&i| shares[i])
+        } else if used < available {
+            (0..shares.len())
+                .filter(|&i| shares[i] < widths[i])
+                .max_by_key(|&i| widths[i] - shares[i])
+        } else {
+            None
+        };
+        match column {
+            Some(i) if used > available => shares[i] -= 1,
+            Some(i) => shares[i] += 1,
+            None => return shares,
+        }
+    }
+}
+
+/// Cut `cell` short with `…` if it's wider than `width`, without splitting a
+/// grapheme, or an escape like `\|`, `<br>` or `&#124;`.
+fn truncate_cell(cell: &str, width: usize) -> Cow<'_, str> {
+    if cell.width() <= width {
+        return Cow::Borrowed(cell);
+    }
+
+    let mut end = 0;
+    let mut used = 0;
+    for (i, grapheme) in cell.grapheme_indices(true) {
+        used += grapheme.width();
+        // Leave room for the `…`
+        if used >= width {
+            break;
+        }
+        end = i + grapheme.len();
+    }
+
+    let mut kept = &cell[..end];
+    if (kept.len() - kept.trim_end_matches('\\').len()) % 2 == 1 {
+        kept = &kept[..kept.len() - 1];
+    }
+    for (open, close) in [('<', '>'), ('&', ';')] {
+        let unclosed = |&start: &usize| {
+            let rest = &kept[start..];
+            !rest.contains(close) && !rest.contains(char::is_whitespace)
+        };
+        if let Some(start) = kept.rfind(open).filter(unclosed) {
+            kept = &kept[..start];
+        }
+    }
+    Cow::Owned(format!("{}…", kept.trim_end()))
+}
+
+/// Whether a cell from a separator row is made of dashes, with optional
+/// colons at either end.
+fn is_separator_cell(cell: &str) -> bool {
+    let cell = cell.trim();
+    let cell = cell.strip_prefix(':').unwrap_or(cell);
+    let cell = cell.strip_suffix(':').unwrap_or(cell);
+    !cell.is_empty() && cell.bytes().all(|byte| byte == b'-')
+}
+
+/// Write a table row to a writer, padding it to `max_cols` cells.
+fn write_table_row<W: Write>(
+    output: &mut W,
+    row: &[String],
+    max_cols: usize,
+    config: &Config,
+) -> Result<()> {
+    write!(output, "|")?;
+
+    let padding = if config.pads_cells() { " " } else { "" };
+    let missing = match config.pad_side {
+        PadSide::End => 0,
+        PadSide::Start => max_cols.saturating_sub(row.len()),
+    };
+    for i in 0..max_cols {
+        let cell = i
+            .checked_sub(missing)
+            .and_then(|i| row.get(i))
+            .map_or("", String::as_str);
+        write!(
+            output,
+            "{}{}{}|",
+            padding,
+            cell,
+            closing_padding(cell, padding)
+        )?;
+    }
+
+    writeln!(output)?;
+    Ok(())
+}
+
+/// The padding to write between `cell` and the `|` after it. Without
+/// padding, a cell ending in an odd number of backslashes would escape the
+/// `|`, so it keeps a space instead.
+fn closing_padding<'a>(cell: &str, padding: &'a str) -> &'a str {
+    let backslashes = cell.len() - cell.trim_end_matches('\\').len();
+    if padding.is_empty() && backslashes % 2 == 1 {
+        " "
+    } else {
+        padding
+    }
+}
+
+/// Write the header separator line to a writer, using the alignment for each
+/// column from `alignments` or `default` for columns without one.
+fn write_header_separator<W: Write>(
+    output: &mut W,
+    max_cols: usize,
+    alignments: &[HeaderAlignment],
+    config: &Config,
+) -> Result<()> {
+    let dashes = "-".repeat(config.separator_dash_count());
+    let padding = if config.pads_cells() { " " } else { "" };
+
+    if config.org_mode {
+        let column = "-".repeat(dashes.len() + 2 * padding.len());
+        writeln!(output, "|{}|", vec![column; max_cols].join("+"))?;
+        return Ok(());
+    }
+
+    write!(output, "|")?;
+
+    for column in 0..max_cols {
+        let (before, after) = match alignments
+            .get(column)
+            .copied()
+            .unwrap_or(config.header_alignment)
+        {
+            HeaderAlignment::Left => ("", ""),
+            HeaderAlignment::Center => (":", ":"),
+            HeaderAlignment::Right => ("", ":"),
+        };
+        write!(
+            output,
+            "{}{}{}{}{}|",
+            padding, before, dashes, after, padding
+        )?;
+    }
+
+    writeln!(output)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_escape_markdown_cell() {
+        let escape = |field| escape_markdown_cell(field, PipeEscape::Backslash);
+        assert_eq!(escape("simple"), "simple");
+        assert_eq!(escape("with|pipe"), "with\\|pipe");
+        assert_eq!(escape("with\nlinebreak"), "with<br>linebreak");
+        assert_eq!(escape("with\r\nwindows"), "with<br>windows");
+        assert_eq!(escape(""), "");
+    }
+
+    #[test]
+    fn test_pipe_escape() {
+        assert_eq!(escape_markdown_cell("a|b", PipeEscape::Backslash), "a\\|b");
+        assert_eq!(escape_markdown_cell("a|b", PipeEscape::Entity), "a&#124;b");
+
+        let config = Config {
+            pipe_escape: PipeEscape::Entity,
+            validate_output: true,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(Cursor::new("Op\na|b"), config).unwrap();
+        assert_eq!(result, "| Op |\n| --- |\n| a&#124;b |\n");
+    }
+
+    #[test]
+    fn test_simple_csv() {
+        let csv_data = "Name,Age\nJohn,25\nJane,30";
+        let input = Cursor::new(csv_data);
+        let config = Config::default();
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected = "| Name | Age |\n| --- | --- |\n| John | 25 |\n| Jane | 30 |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_empty_csv() {
+        let csv_data = "";
+        let input = Cursor::new(csv_data);
+        let config = Config::default();
+        let result = csv_to_markdown(input, config).unwrap();
+
+        assert_eq!(result, "");
+    }
+
+    #[test]
+    fn test_csv_with_line_breaks() {
+        let csv_data = "Name,Description\nJohn,\"Line 1\nLine 2\"";
+        let input = Cursor::new(csv_data);
+        let config = Config::default();
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected = "| Name | Description |\n| --- | --- |\n| John | Line 1<br>Line 2 |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_csv_with_pipes() {
+        let csv_data = "Name,Description\nJohn,\"Has | pipe\"";
+        let input = Cursor::new(csv_data);
+        let config = Config::default();
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected = "| Name | Description |\n| --- | --- |\n| John | Has \\| pipe |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_csv_with_uneven_columns() {
+        let csv_data = "A,B,C\nX,Y\nP,Q,R,S";
+        let input = Cursor::new(csv_data);
+        let config = Config::default();
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected =
+            "| A | B | C |  |\n| --- | --- | --- | --- |\n| X | Y |  |  |\n| P | Q | R | S |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_single_row_csv() {
+        let csv_data = "Name,Age,City";
+        let input = Cursor::new(csv_data);
+        let config = Config::default();
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected = "| Name | Age | City |\n| --- | --- | --- |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_csv_with_empty_cells() {
+        let csv_data = "Name,Age,City\nJohn,,NYC\n,25,";
+        let input = Cursor::new(csv_data);
+        let config = Config::default();
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected =
+            "| Name | Age | City |\n| --- | --- | --- |\n| John |  | NYC |\n|  | 25 |  |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_csv_with_quotes_and_commas() {
+        let csv_data = "Name,Description\nJohn,\"Smith, Jr.\"\nJane,\"O'Connor\"";
+        let input = Cursor::new(csv_data);
+        let config = Config::default();
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected =
+            "| Name | Description |\n| --- | --- |\n| John | Smith, Jr. |\n| Jane | O'Connor |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_csv_with_special_characters() {
+        let csv_data = "Symbol,Unicode\n★,\"U+2605\"\n♠,\"U+2660\"";
+        let input = Cursor::new(csv_data);
+        let config = Config::default();
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected = "| Symbol | Unicode |\n| --- | --- |\n| ★ | U+2605 |\n| ♠ | U+2660 |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_csv_with_whitespace() {
+        let csv_data = " Name , Age \n John , 25 \n Jane , 30 ";
+        let input = Cursor::new(csv_data);
+        let config = Config::default();
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected = "|  Name  |  Age  |\n| --- | --- |\n|  John  |  25  |\n|  Jane  |  30  |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_no_headers_config() {
+        let csv_data = "Data1,Data2\nValue1,Value2";
+        let input = Cursor::new(csv_data);
+        let config = Config {
+            has_headers: false,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        // Should not have separator line when no headers
+        let expected = "| Data1 | Data2 |\n| Value1 | Value2 |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_custom_delimiter() {
+        let csv_data = "Name;Age\nJohn;25\nJane;30";
+        let input = Cursor::new(csv_data);
+        let config = Config {
+            delimiter: b';',
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected = "| Name | Age |\n| --- | --- |\n| John | 25 |\n| Jane | 30 |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_streaming_mode() {
+        let csv_data = "Name,Age\nJohn,25\nJane,30";
+        let input = Cursor::new(csv_data);
+        let mut output = Vec::new();
+        let config = Config::default();
+
+        csv_to_markdown_streaming(input, &mut output, config).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        let expected = "| Name | Age |\n| --- | --- |\n| John | 25 |\n| Jane | 30 |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_streaming_mode_no_headers() {
+        let csv_data = "Data1,Data2\nValue1,Value2";
+        let input = Cursor::new(csv_data);
+        let mut output = Vec::new();
+        let config = Config {
+            has_headers: false,
+            ..Config::default()
+        };
+
+        csv_to_markdown_streaming(input, &mut output, config).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        let expected = "| Data1 | Data2 |\n| Value1 | Value2 |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_streaming_spill_to_disk() {
+        let mut csv_data = String::from("id,name,notes\n");
+        for i in 0..50_000 {
+            csv_data.push_str(&format!("{},name {},\"a | b\nc\"\n", i, i));
+        }
+
+        let mut in_memory = Vec::new();
+        csv_to_markdown_streaming(csv_data.as_bytes(), &mut in_memory, Config::default()).unwrap();
+
+        let mut spilled = Vec::new();
+        let config = Config {
+            spill_to_disk: true,
+            ..Config::default()
+        };
+        csv_to_markdown_streaming(csv_data.as_bytes(), &mut spilled, config).unwrap();
+
+        assert_eq!(spilled.len(), in_memory.len());
+        assert!(spilled == in_memory);
+    }
+
+    #[test]
+    fn test_optimistic_streaming_uniform_rows() {
+        let config = Config {
+            optimistic_streaming: true,
+            ..Config::default()
+        };
+        let mut output = Vec::new();
+        csv_to_markdown_streaming("Name,Age\nJohn,25\nJane,30".as_bytes(), &mut output, config)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "| Name | Age |\n| --- | --- |\n| John | 25 |\n| Jane | 30 |\n"
+        );
+    }
+
+    #[test]
+    fn test_peek_header() {
+        struct Failing;
+        impl Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("input failed"))
+            }
+        }
+
+        // Reading any further than the header would fail
+        let input = "\u{feff}Name,Age\nJohn,25\nJane,30\n"
+            .as_bytes()
+            .chain(Failing);
+        let config = Config::default();
+        assert_eq!(peek_header(input, &config).unwrap(), ["Name", "Age"]);
+
+        let config = Config {
+            header_row_index: Some(1),
+            ..Config::default()
+        };
+        let input = "sep=;\nExported today\nName;Age\nJohn;25\n"
+            .as_bytes()
+            .chain(Failing);
+        assert_eq!(peek_header(input, &config).unwrap(), ["Name", "Age"]);
+
+        assert!(peek_header("".as_bytes(), &config).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_optimistic_streaming_writes_rows_as_they_are_read() {
+        struct Failing;
+        impl Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("input failed"))
+            }
+        }
+
+        // A single pass writes the rows before the rest of the input is read
+        let input = "Name,Age\nJohn,25\n".as_bytes().chain(Failing);
+        let config = Config {
+            optimistic_streaming: true,
+            ..Config::default()
+        };
+        let mut output = Vec::new();
+        let result = csv_to_markdown_streaming(input, &mut output, config);
+
+        assert!(result.is_err());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "| Name | Age |\n| --- | --- |\n| John | 25 |\n"
+        );
+    }
+
+    #[test]
+    fn test_optimistic_streaming_ragged_rows() {
+        let config = Config {
+            optimistic_streaming: true,
+            ..Config::default()
+        };
+        let mut output = Vec::new();
+        let result = csv_to_markdown_streaming_seekable(
+            Cursor::new("Name,Age\nJohn\nJane,30,London"),
+            &mut output,
+            config,
+        );
+
+        assert!(matches!(
+            result,
+            Err(CsvMdError::ColumnCountChanged {
+                row: 3,
+                expected: 2,
+                found: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn test_optimistic_streaming_rejects_options_needing_every_row() {
+        let config = Config {
+            optimistic_streaming: true,
+            auto_align_numeric: true,
+            ..Config::default()
+        };
+        let result = csv_to_markdown_streaming("A\n1".as_bytes(), Vec::new(), config);
+
+        assert!(matches!(result, Err(CsvMdError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_callout() {
+        let config = Config {
+            callout: Some(CalloutKind::Note),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(Cursor::new("Name,Age\nJohn,25"), config.clone()).unwrap();
+
+        let expected = "> [!NOTE]\n> | Name | Age |\n> | --- | --- |\n> | John | 25 |\n";
+        assert_eq!(result, expected);
+
+        let mut streamed = Vec::new();
+        csv_to_markdown_streaming(Cursor::new("Name,Age\nJohn,25"), &mut streamed, config).unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_caption() {
+        let config = Config {
+            caption: Some("Sales by region".to_string()),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(Cursor::new("Region\nNorth"), config.clone()).unwrap();
+
+        let expected = "**Sales by region**\n\n| Region |\n| --- |\n| North |\n";
+        assert_eq!(result, expected);
+
+        let mut streamed = Vec::new();
+        csv_to_markdown_streaming(Cursor::new("Region\nNorth"), &mut streamed, config.clone())
+            .unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+
+        assert_eq!(csv_to_markdown(Cursor::new(""), config).unwrap(), "");
+    }
+
+    #[test]
+    fn test_callout_keeps_blank_lines_inside() {
+        let config = Config {
+            callout: Some(CalloutKind::Warning),
+            footnote_over: Some(3),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(Cursor::new("Name\nJohnathan"), config).unwrap();
+
+        assert_eq!(
+            result,
+            "> [!WARNING]\n> | Name |\n> | --- |\n> | [^1] |\n>\n> [^1]: Johnathan\n"
+        );
+    }
+
+    #[test]
+    fn test_callout_with_empty_input() {
+        let config = Config {
+            callout: Some(CalloutKind::Tip),
+            ..Config::default()
+        };
+
+        assert_eq!(csv_to_markdown(Cursor::new(""), config).unwrap(), "");
+    }
+
+    #[test]
+    fn test_separator_dashes() {
+        let config = Config {
+            separator_dashes: 1,
+            column_alignments: vec![
+                HeaderAlignment::Left,
+                HeaderAlignment::Center,
+                HeaderAlignment::Right,
+            ],
+            ..Config::default()
+        };
+        let result = csv_to_markdown(Cursor::new("A,B,C"), config).unwrap();
+
+        assert_eq!(result, "| A | B | C |\n| - | :-: | -: |\n");
+    }
+
+    #[test]
+    fn test_separator_follows_cell_padding() {
+        for (cell_padding, alignment, expected) in [
+            (true, HeaderAlignment::Left, "| A |\n| --- |\n| 1 |\n"),
+            (true, HeaderAlignment::Center, "| A |\n| :---: |\n| 1 |\n"),
+            (true, HeaderAlignment::Right, "| A |\n| ---: |\n| 1 |\n"),
+            (false, HeaderAlignment::Left, "|A|\n|---|\n|1|\n"),
+            (false, HeaderAlignment::Center, "|A|\n|:---:|\n|1|\n"),
+            (false, HeaderAlignment::Right, "|A|\n|---:|\n|1|\n"),
+        ] {
+            let config = Config {
+                cell_padding,
+                header_alignment: alignment,
+                ..Config::default()
+            };
+            let result = csv_to_markdown(Cursor::new("A\n1"), config.clone()).unwrap();
+            assert_eq!(result, expected);
+
+            let mut streamed = Vec::new();
+            csv_to_markdown_streaming(Cursor::new("A\n1"), &mut streamed, config.clone()).unwrap();
+            assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+
+            // Pretty printing pads the other rows to the separator's width
+            let separator = expected.lines().nth(1).unwrap();
+            let pretty = Config {
+                pretty: true,
+                ..config.clone()
+            };
+            let result = csv_to_markdown(Cursor::new("A\n1"), pretty).unwrap();
+            assert_eq!(result.lines().nth(1), Some(separator));
+            assert!(result.lines().all(|line| line.len() == separator.len()));
+
+            let column_tables = Config {
+                column_tables: true,
+                ..config
+            };
+            let result = csv_to_markdown(Cursor::new("A\n1"), column_tables).unwrap();
+            let two_columns = format!("{}{}", separator, &separator[1..]);
+            assert_eq!(result.lines().nth(3), Some(two_columns.as_str()));
+        }
+    }
+
+    #[test]
+    fn test_separator_dashes_clamped_to_one() {
+        for (alignment, separator) in [
+            (HeaderAlignment::Left, "| - |\n"),
+            (HeaderAlignment::Center, "| :-: |\n"),
+            (HeaderAlignment::Right, "| -: |\n"),
+        ] {
+            let config = Config {
+                separator_dashes: 0,
+                header_alignment: alignment,
+                ..Config::default()
+            };
+            assert!(config.validate().is_err());
+
+            let result = csv_to_markdown(Cursor::new("A"), config).unwrap();
+            assert_eq!(result, format!("| A |\n{}", separator));
+        }
+    }
+
+    #[test]
+    fn test_record_source() {
+        struct Fixed(std::vec::IntoIter<Result<Vec<String>>>);
+        impl RecordSource for Fixed {
+            fn next_record(&mut self) -> Option<Result<Vec<String>>> {
+                self.0.next()
+            }
+        }
+        let fixed = |records: Vec<Result<Vec<&str>>>| {
+            let records: Vec<_> = records
+                .into_iter()
+                .map(|record| Ok(record?.into_iter().map(String::from).collect()))
+                .collect();
+            Fixed(records.into_iter())
+        };
+
+        let config = Config {
+            columns: Some(vec!["B".to_string(), "A".to_string()]),
+            ..Config::default()
+        };
+        let mut output = Vec::new();
+        let source = fixed(vec![
+            Ok(vec!["A", "B"]),
+            Ok(vec!["1", "x|y"]),
+            Ok(vec!["2"]),
+        ]);
+        convert_records_into(source, &config, &mut Vec::new(), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "| B | A |\n| --- | --- |\n| x\\|y | 1 |\n|  | 2 |\n"
+        );
+
+        let source = fixed(vec![
+            Ok(vec!["A", "B"]),
+            Err(CsvMdError::InvalidConfig("unreadable".to_string())),
+        ]);
+        let result = convert_records_into(source, &config, &mut Vec::new(), &mut Vec::new());
+        assert!(
+            matches!(result, Err(CsvMdError::InvalidConfig(message)) if message == "unreadable")
+        );
+    }
+
+    #[test]
+    fn test_csv_to_markdown_flat_map() {
+        let input = Cursor::new("Name,Count\nJohn,once\n,once\nJane | Doe,twice\n\"\",twice");
+        let result = csv_to_markdown_flat_map(input, Config::default(), |row| {
+            match (row[0].as_str(), row[1].as_str()) {
+                ("", _) => vec![],
+                (_, "twice") => vec![row.to_vec(), row.to_vec()],
+                _ => vec![row.to_vec()],
+            }
+        })
+        .unwrap();
+
+        assert_eq!(
+            result,
+            "| Name | Count |\n| --- | --- |\n| John | once |\n\
+             | Jane \\| Doe | twice |\n| Jane \\| Doe | twice |\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_to_markdown_flat_map_resizes_table() {
+        let input = Cursor::new("A\n1\n2");
+        let result = csv_to_markdown_flat_map(input, Config::default(), |row| {
+            vec![vec![row[0].clone(), format!("{}0", row[0])]]
+        })
+        .unwrap();
+
+        assert_eq!(result, "| A |  |\n| --- | --- |\n| 1 | 10 |\n| 2 | 20 |\n");
+    }
+
+    #[test]
+    fn test_csv_to_records_map() {
+        let input = Cursor::new("Name,Age,Name\nJohn,25,Smith\n\"Jane | Doe\",30,Doe");
+        let rows = csv_to_records_map(input, &Config::default()).unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["Name"], "John");
+        assert_eq!(rows[0]["Age"], "25");
+        assert_eq!(rows[0]["Name_2"], "Smith");
+        assert_eq!(rows[1]["Name"], "Jane | Doe");
+    }
+
+    #[test]
+    fn test_csv_to_records_map_short_row() {
+        let input = Cursor::new("Name,Age,City\nJohn,25\nJane,30,Paris");
+        let rows = csv_to_records_map(input, &Config::default()).unwrap();
+
+        assert_eq!(rows[0].len(), 2);
+        assert_eq!(rows[0].get("City"), None);
+        assert_eq!(rows[1]["City"], "Paris");
+    }
+
+    #[test]
+    fn test_csv_to_records_map_without_headers() {
+        let config = Config {
+            has_headers: false,
+            ..Config::default()
+        };
+        let rows = csv_to_records_map(Cursor::new("John,25"), &config).unwrap();
+
+        assert_eq!(rows[0]["0"], "John");
+        assert_eq!(rows[0]["1"], "25");
+    }
+
+    #[test]
+    fn test_diff_by_key() {
+        let old = "ID,Name,Age\n1,John,25\n2,Jane,30\n3,Bob,40";
+        let new = "ID,Name,Age\n1,John,26\n3,Bob,40\n4,Ann,35";
+        let result = diff_to_markdown(
+            Cursor::new(old),
+            Cursor::new(new),
+            Config::default(),
+            Some(0),
+        )
+        .unwrap();
+
+        assert_eq!(
+            result,
+            "| Status | ID | Name | Age |\n| --- | --- | --- | --- |\n\
+             | ~ | 1 | John | 25 → 26 |\n\
+             | + | 4 | Ann | 35 |\n\
+             | - | 2 | Jane | 30 |\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_by_row() {
+        let old = "Name,Age\nJohn,25\nJane,30\nJane,30";
+        let new = "Name,Age\nJane,30\nJohn,26";
+        let result =
+            diff_to_markdown(Cursor::new(old), Cursor::new(new), Config::default(), None).unwrap();
+
+        assert_eq!(
+            result,
+            "| Status | Name | Age |\n| --- | --- | --- |\n\
+             | + | John | 26 |\n\
+             | - | John | 25 |\n\
+             | - | Jane | 30 |\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_key_not_found() {
+        let result = diff_to_markdown(
+            Cursor::new("Name\nJohn"),
+            Cursor::new("Name\nJane"),
+            Config::default(),
+            Some(1),
+        );
+
+        assert!(matches!(result, Err(CsvMdError::ColumnNotFound(key)) if key == "1"));
+    }
+
+    #[test]
+    fn test_trim_mode() {
+        let csv_data = "Code\n\"  if x:  \n    y()  \"";
+        for (trim_mode, cell) in [
+            (TrimMode::None, "  if x:  <br>    y()  "),
+            (TrimMode::Both, "if x:  <br>    y()"),
+            (TrimMode::Trailing, "  if x:  <br>    y()"),
+            (TrimMode::Leading, "if x:  <br>    y()  "),
+        ] {
+            let config = Config {
+                trim_mode,
+                ..Config::default()
+            };
+            let result = csv_to_markdown(Cursor::new(csv_data), config).unwrap();
+
+            assert_eq!(result, format!("| Code |\n| --- |\n| {} |\n", cell));
+        }
+    }
+
+    #[test]
+    fn test_rows_per_table() {
+        let config = Config {
+            rows_per_table: Some(3),
+            ..Config::default()
+        };
+        let csv_data = "N\n1\n2\n3\n4\n5";
+        let result = csv_to_markdown(Cursor::new(csv_data), config.clone()).unwrap();
+
+        let expected =
+            "| N |\n| --- |\n| 1 |\n| 2 |\n| 3 |\n\n---\n\n| N |\n| --- |\n| 4 |\n| 5 |\n";
+        assert_eq!(result, expected);
+
+        let mut streamed = Vec::new();
+        csv_to_markdown_streaming(Cursor::new(csv_data), &mut streamed, config).unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_rows_per_table_with_divider_and_row_numbers() {
+        let config = Config {
+            rows_per_table: Some(2),
+            page_divider: "<!-- pagebreak -->".to_string(),
+            row_numbers: true,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(Cursor::new("N\na\nb\nc"), config).unwrap();
+
+        assert_eq!(
+            result,
+            "| # | N |\n| --- | --- |\n| 1 | a |\n| 2 | b |\n\n<!-- pagebreak -->\n\n\
+             | # | N |\n| --- | --- |\n| 3 | c |\n"
+        );
+    }
+
+    #[test]
+    fn test_suggest_delimiter() {
+        let config = Config::default();
+        assert_eq!(
+            suggest_delimiter(b"Name;Age\r\nJohn;25\r\n", &config),
+            Some(b';')
+        );
+        assert_eq!(
+            suggest_delimiter(b"Name\tAge\nJohn\t25", &config),
+            Some(b'\t')
+        );
+
+        // The delimiter already works, or the other one isn't on every line
+        assert_eq!(suggest_delimiter(b"Name,Age\nJohn;Smith,25", &config), None);
+        assert_eq!(suggest_delimiter(b"Name\nJohn;Smith\nJane", &config), None);
+        assert_eq!(suggest_delimiter(b"", &config), None);
+
+        let config = Config {
+            delimiter: b';',
+            ..Config::default()
+        };
+        assert_eq!(suggest_delimiter(b"Name,Age\nJohn,25", &config), Some(b','));
+    }
+
+    #[test]
+    fn test_config_as_cache_key() {
+        use std::collections::HashSet;
+
+        let config = Config {
+            columns: Some(vec!["Name".to_string()]),
+            filter: Some("Age==25".parse().unwrap()),
+            column_number_format: vec![None, Some("#,##0.00".parse().unwrap())],
+            ..Config::default()
+        };
+
+        let mut configs = HashSet::new();
+        configs.insert(config.clone());
+        configs.insert(config);
+        assert_eq!(configs.len(), 1);
+
+        configs.insert(Config::default());
+        assert_eq!(configs.len(), 2);
+    }
+
+    #[test]
+    fn test_synthetic_headers() {
+        let config = Config {
+            has_headers: false,
+            synthetic_headers: true,
+            ..Config::default()
+        };
+        let csv_data = "John,25,London\nJane,30,Paris";
+        let result = csv_to_markdown(Cursor::new(csv_data), config.clone()).unwrap();
+
+        let expected = "| Column 1 | Column 2 | Column 3 |\n| --- | --- | --- |\n\
+                        | John | 25 | London |\n| Jane | 30 | Paris |\n";
+        assert_eq!(result, expected);
+
+        let mut streamed = Vec::new();
+        csv_to_markdown_streaming(Cursor::new(csv_data), &mut streamed, config).unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_synthetic_headers_ignored_with_headers() {
+        let config = Config {
+            synthetic_headers: true,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(Cursor::new("Name\nJohn"), config).unwrap();
+
+        assert_eq!(result, "| Name |\n| --- |\n| John |\n");
+    }
+
+    #[test]
+    fn test_csv_to_markdown_lines() {
+        let input = "Log line\n\"quoted\", with a comma\r\nfailed | retrying\n\nlast";
+        let mut output = Vec::new();
+        csv_to_markdown_lines(input.as_bytes(), &mut output, Config::default()).unwrap();
+
+        let config = Config {
+            single_column_mode: true,
+            ..Config::default()
+        };
+        let expected = csv_to_markdown(Cursor::new(input), config).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+        assert!(expected.contains("| \"quoted\", with a comma |"));
+
+        // Options which need every row are refused rather than ignored
+        for config in [
+            Config {
+                pretty: true,
+                ..Config::default()
+            },
+            Config {
+                group_by: Some(ColumnRef::Index(0)),
+                ..Config::default()
+            },
+            Config {
+                validate_output: true,
+                ..Config::default()
+            },
+        ] {
+            let mut output = Vec::new();
+            let result = csv_to_markdown_lines(input.as_bytes(), &mut output, config);
+            assert!(matches!(result, Err(CsvMdError::InvalidConfig(_))));
+            assert!(output.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_markdown_reader_in_small_chunks() {
+        let input = "Name,Note\nJohn,\"a | b\"\nJane,\"line\nbreak\"\n";
+        let config = Config {
+            callout: Some(CalloutKind::Tip),
+            row_numbers: true,
+            ..Config::default()
+        };
+        let mut reader = MarkdownReader::new(Cursor::new(input), config.clone()).unwrap();
+
+        let mut output = Vec::new();
+        let mut chunk = [0; 3];
+        loop {
+            let len = reader.read(&mut chunk).unwrap();
+            if len == 0 {
+                break;
+            }
+            output.extend_from_slice(&chunk[..len]);
+        }
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            csv_to_markdown(Cursor::new(input), config).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_markdown_reader_errors() {
+        let config = Config {
+            auto_align_numeric: true,
+            ..Config::default()
+        };
+        assert!(matches!(
+            MarkdownReader::new(Cursor::new("A\n1"), config),
+            Err(CsvMdError::InvalidConfig(_))
+        ));
+
+        let mut reader = MarkdownReader::new(
+            Cursor::new("Name,Age\nJohn\nJane,30,London"),
+            Config::default(),
+        )
+        .unwrap();
+        let err = reader.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("Row 3 has 3 columns"));
+    }
+
+    #[test]
+    fn test_detect_markdown_passes_tables_through() {
+        let config = Config {
+            detect_markdown: true,
+            ..Config::default()
+        };
+        for markdown in [
+            "| Name | Age |\n| --- | ---: |\n| John | 25 |\n",
+            "\nName | Age\n:-- | --\nJohn | 25",
+        ] {
+            let result = csv_to_markdown(Cursor::new(markdown), config.clone()).unwrap();
+            assert_eq!(result, markdown);
+
+            let mut streamed = Vec::new();
+            csv_to_markdown_streaming(markdown.as_bytes(), &mut streamed, config.clone()).unwrap();
+            assert_eq!(String::from_utf8(streamed).unwrap(), markdown);
+
+            let mut streamed = Vec::new();
+            csv_to_markdown_streaming_seekable(
+                Cursor::new(markdown),
+                &mut streamed,
+                config.clone(),
+            )
+            .unwrap();
+            assert_eq!(String::from_utf8(streamed).unwrap(), markdown);
+        }
+    }
+
+    #[test]
+    fn test_detect_markdown_converts_csv() {
+        let config = Config {
+            detect_markdown: true,
+            ..Config::default()
+        };
+        let expected = "| Name | Range |\n| --- | --- |\n| John | 1-2 |\n";
+        for csv_data in ["Name,Range\nJohn,1-2", "Name|Range\nJohn|1-2"] {
+            let config = Config {
+                delimiter: csv_data.as_bytes()[4],
+                ..config.clone()
+            };
+            let result = csv_to_markdown(Cursor::new(csv_data), config.clone()).unwrap();
+            assert_eq!(result, expected);
+
+            let mut streamed = Vec::new();
+            csv_to_markdown_streaming_seekable(Cursor::new(csv_data), &mut streamed, config)
+                .unwrap();
+            assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+        }
+
+        // Without the option, a Markdown table is converted like any other input
+        let result = csv_to_markdown(Cursor::new("| A |\n| --- |"), Config::default()).unwrap();
+        assert_eq!(result, "| \\| A \\| |\n| --- |\n| \\| --- \\| |\n");
+    }
+
+    #[test]
+    fn test_minify() {
+        let csv_data = "Name,Age,City\nJohn,25,\nJane,30,Paris";
+        let config = Config {
+            minify: true,
+            column_alignments: vec![HeaderAlignment::Left, HeaderAlignment::Right],
+            header_alignment: HeaderAlignment::Center,
+            ..Config::default()
+        };
+        let minified = csv_to_markdown(Cursor::new(csv_data), config.clone()).unwrap();
+
+        let expected = "|Name|Age|City|\n|-|-:|:-:|\n|John|25||\n|Jane|30|Paris|";
+        assert_eq!(minified, expected);
+
+        let mut streamed = Vec::new();
+        csv_to_markdown_streaming(Cursor::new(csv_data), &mut streamed, config).unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+
+        let default = csv_to_markdown(Cursor::new(csv_data), Config::default()).unwrap();
+        assert!(minified.len() * 3 < default.len() * 2);
+    }
+
+    #[test]
+    fn test_unpadded_cell_ending_in_backslash() {
+        let csv_data = "A,B
+x\\,y
+x\\\\,y";
+        for config in [
+            Config {
+                minify: true,
+                ..Config::default()
+            },
+            Config {
+                cell_padding: false,
+                pretty: true,
+                ..Config::default()
+            },
+            Config {
+                cell_padding: false,
+                max_table_width: Some(12),
+                ..Config::default()
+            },
+        ] {
+            let result = csv_to_markdown(Cursor::new(csv_data), config).unwrap();
+            let cells: Vec<Vec<&str>> = result
+                .lines()
+                .skip(2)
+                .map(|line| split_table_row(line).unwrap())
+                .map(|cells| cells.into_iter().map(str::trim).collect())
+                .collect();
+            assert_eq!(cells, [["x\\", "y"], ["x\\\\", "y"]], "{}", result);
+        }
+    }
+
+    #[test]
+    fn test_cell_padding_and_trailing_newline() {
+        let config = Config {
+            cell_padding: false,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(Cursor::new("A\n1"), config).unwrap();
+        assert_eq!(result, "|A|\n|---|\n|1|\n");
+
+        let config = Config {
+            trailing_newline: false,
+            footnote_over: Some(1),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(Cursor::new("A\n12"), config).unwrap();
+        assert_eq!(result, "| A |\n| --- |\n| [^1] |\n\n[^1]: 12");
+    }
+
+    #[test]
+    fn test_pretty() {
+        let config = Config {
+            pretty: true,
+            header_alignment: HeaderAlignment::Center,
+            ..Config::default()
+        };
+        let input = "Item,Cost\nRent,1500\nA | B,20";
+        let result = csv_to_markdown(Cursor::new(input), config.clone()).unwrap();
+
+        let expected =
+            "| Item   | Cost  |\n| :----: | :---: |\n| Rent   | 1500  |\n| A \\| B | 20    |\n";
+        assert_eq!(result, expected);
+
+        let mut streamed = Vec::new();
+        csv_to_markdown_streaming(Cursor::new(input), &mut streamed, config).unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_numeric_pad_right() {
+        let config = Config {
+            pretty: true,
+            numeric_pad_right: true,
+            callout: Some(CalloutKind::Note),
+            trailing_newline: false,
+            ..Config::default()
+        };
+        let input = "Value\n1500\n2\nn/a\n-3.5";
+        let result = csv_to_markdown(Cursor::new(input), config).unwrap();
+
+        // Numbers are padded on the left and text on the right
+        let expected = "> [!NOTE]\n> | Value |\n> | ----- |\n> |  1500 |\n> |     2 |\n\
+                        > | n/a   |\n> |  -3.5 |";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_max_table_width() {
+        let input = "ID,Name,Description\n\
+                     1,Widget,A small widget for fastening things together\n\
+                     2,Gadget à la mode,Does something | else entirely\n";
+        let expected = "| ID | Name | Description |\n| --- | --- | --- |\n\
+                        | 1 | Widget | A small widget for… |\n\
+                        | 2 | Gadget… | Does something \\| e… |\n";
+        let pretty = "| ID  | Name    | Description          |\n\
+                      | --- | ------- | -------------------- |\n\
+                      | 1   | Widget  | A small widget for…  |\n\
+                      | 2   | Gadget… | Does something \\| e… |\n";
+        for (config, expected) in [
+            (Config::default(), expected),
+            (
+                Config {
+                    pretty: true,
+                    ..Config::default()
+                },
+                pretty,
+            ),
+        ] {
+            let config = Config {
+                max_table_width: Some(40),
+                ..config
+            };
+            let result = csv_to_markdown(Cursor::new(input), config).unwrap();
+            assert_eq!(result, expected);
+            assert!(result.lines().all(|line| line.width() <= 40));
+        }
+    }
+
+    #[test]
+    fn test_truncate_cell() {
+        assert_eq!(truncate_cell("short", 5), "short");
+        assert_eq!(truncate_cell("a\\|b", 3), "a…");
+        assert_eq!(truncate_cell("one<br>two", 6), "one…");
+        assert_eq!(truncate_cell("a &#124; b", 7), "a…");
+        assert_eq!(truncate_cell("e\u{301}e\u{301}e\u{301}", 2), "e\u{301}…");
+        assert_eq!(truncate_cell("東京都", 5), "東京…");
+    }
+
+    #[test]
+    fn test_source_footer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sales.csv");
+        std::fs::write(&path, "Item,Cost\nRent,500\n").unwrap();
+        let config = Config {
+            source_footer: true,
+            ..Config::default()
+        };
+
+        let result = convert_file(&path, config.clone()).unwrap();
+        let footer = result
+            .strip_prefix("| Item | Cost |\n| --- | --- |\n| Rent | 500 |\n\n")
+            .unwrap();
+        assert!(footer.starts_with("_Generated from sales.csv on "));
+        assert!(footer.ends_with("Z_\n"));
+
+        // Without a file name, like stdin, the footer only has the time
+        let mut streamed = Vec::new();
+        csv_to_markdown_streaming(Cursor::new("A\n1"), &mut streamed, config.clone()).unwrap();
+        let streamed = String::from_utf8(streamed).unwrap();
+        assert!(streamed.starts_with("| A |\n| --- |\n| 1 |\n\n_Generated on "));
+
+        let config = Config {
+            trailing_newline: false,
+            ..config
+        };
+        let result = csv_to_markdown(Cursor::new("A\n1"), config).unwrap();
+        assert!(result.ends_with("Z_"));
+    }
+
+    #[test]
+    fn test_iso8601_utc() {
+        let at = |seconds| SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(seconds);
+        assert_eq!(iso8601_utc(at(0)), "1970-01-01T00:00:00Z");
+        assert_eq!(iso8601_utc(at(951_782_400)), "2000-02-29T00:00:00Z");
+        assert_eq!(iso8601_utc(at(1_704_112_496)), "2024-01-01T12:34:56Z");
+        assert_eq!(iso8601_utc(at(4_102_444_799)), "2099-12-31T23:59:59Z");
+    }
+
+    #[test]
+    fn test_trailing_blank_line() {
+        let config = Config {
+            trailing_blank_line: true,
+            callout: Some(CalloutKind::Note),
+            ..Config::default()
+        };
+        let expected = "> [!NOTE]\n> | A |\n> | --- |\n> | 1 |\n\n";
+        let result = csv_to_markdown(Cursor::new("A\n1"), config.clone()).unwrap();
+        assert_eq!(result, expected);
+
+        let mut streamed = Vec::new();
+        csv_to_markdown_streaming(Cursor::new("A\n1"), &mut streamed, config).unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+
+        let result = csv_to_markdown(Cursor::new("A\n1"), Config::default()).unwrap();
+        assert!(result.ends_with("|\n") && !result.ends_with("\n\n"));
+
+        let config = Config {
+            trailing_blank_line: true,
+            trailing_newline: false,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(Cursor::new("A\n1"), config.clone()).unwrap();
+        assert_eq!(result, "| A |\n| --- |\n| 1 |\n");
+        assert_eq!(csv_to_markdown(Cursor::new(""), config).unwrap(), "");
+    }
+
+    #[test]
+    fn test_rename_headers() {
+        let config = Config {
+            rename_headers: BTreeMap::from([
+                ("usr_id".to_string(), "User ID".to_string()),
+                ("missing".to_string(), "Missing".to_string()),
+            ]),
+            columns: Some(vec!["name".to_string(), "usr_id".to_string()]),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(Cursor::new("usr_id,name\nusr_id,Jane"), config).unwrap();
+
+        assert_eq!(
+            result,
+            "| name | User ID |\n| --- | --- |\n| Jane | usr_id |\n"
+        );
+    }
+
+    #[test]
+    fn test_header_row_index() {
+        let config = Config {
+            header_row_index: Some(2),
+            ..Config::default()
+        };
+        let csv_data = "Staff list\nExported 2024-01-01,\nName,Age\nJohn,25\nJane,30";
+        let result = csv_to_markdown(Cursor::new(csv_data), config.clone()).unwrap();
+
+        let expected = "| Name | Age |\n| --- | --- |\n| John | 25 |\n| Jane | 30 |\n";
+        assert_eq!(result, expected);
+
+        let mut streamed = Vec::new();
+        csv_to_markdown_streaming(Cursor::new(csv_data), &mut streamed, config.clone()).unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+
+        let rows = csv_to_records_map(Cursor::new(csv_data), &config).unwrap();
+        assert_eq!(rows[1]["Name"], "Jane");
+
+        // Blank lines aren't counted
+        let csv_data = "Staff list\n\nName,Age\n\nJohn,25\nJane,30";
+        let config = Config {
+            header_row_index: Some(1),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(Cursor::new(csv_data), config).unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_header_row_index_with_columns_and_filter() {
+        let config = Config {
+            header_row_index: Some(1),
+            columns: Some(vec!["Age".to_string()]),
+            filter: Some("Name==Jane".parse().unwrap()),
+            ..Config::default()
+        };
+        let result =
+            csv_to_markdown(Cursor::new("Title\nName,Age\nJohn,25\nJane,30"), config).unwrap();
+
+        assert_eq!(result, "| Age |\n| --- |\n| 30 |\n");
+    }
+
+    #[test]
+    fn test_summary_row() {
+        let csv_data = "Item,Price,Quantity,Notes\nApple,0.1,3,Red\nPear,0.2,,12\nPlum,,4,";
+        for (kind, summary) in [
+            (SummaryKind::Sum, "| Total | 0.3 | 7 |  |"),
+            (SummaryKind::Mean, "| Mean | 0.15 | 3.5 |  |"),
+            (SummaryKind::Count, "| Count | 2 | 2 |  |"),
+        ] {
+            let config = Config {
+                summary_row: Some(kind),
+                ..Config::default()
+            };
+            let result = csv_to_markdown(Cursor::new(csv_data), config.clone()).unwrap();
+
+            assert_eq!(
+                result,
+                format!(
+                    "| Item | Price | Quantity | Notes |\n| --- | --- | --- | --- |\n\
+                     | Apple | 0.1 | 3 | Red |\n| Pear | 0.2 |  | 12 |\n| Plum |  | 4 |  |\n\
+                     {}\n",
+                    summary
+                )
+            );
+
+            let mut streamed = Vec::new();
+            csv_to_markdown_streaming(Cursor::new(csv_data), &mut streamed, config).unwrap();
+            assert_eq!(String::from_utf8(streamed).unwrap(), result);
+        }
+    }
+
+    #[test]
+    fn test_summary_row_without_data_rows() {
+        let config = Config {
+            summary_row: Some(SummaryKind::Sum),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(Cursor::new("Item,Price"), config).unwrap();
+
+        assert_eq!(result, "| Item | Price |\n| --- | --- |\n");
+    }
+
+    #[test]
+    fn test_max_field_bytes() {
+        // A quote that's never closed makes the rest of the input one field
+        let input = format!("Name,Note\nJohn,\"{}\nJane,ok\n", "x,".repeat(10_000));
+        let config = Config {
+            max_field_bytes: Some(1024),
+            ..Config::default()
+        };
+
+        let result = csv_to_markdown(Cursor::new(&input), config.clone());
+        assert!(matches!(
+            result,
+            Err(CsvMdError::FieldTooLarge {
+                record: 2,
+                limit: 1024,
+                ..
+            })
+        ));
+
+        let mut output = Vec::new();
+        let result = csv_to_markdown_streaming(Cursor::new(&input), &mut output, config.clone());
+        assert!(matches!(result, Err(CsvMdError::FieldTooLarge { .. })));
+
+        // The field is caught while it's being read, before the rest of an
+        // endless input is buffered
+        let input = "Name,Note\nJohn,\"".as_bytes().chain(std::io::repeat(b'x'));
+        let result = csv_to_markdown(input, config.clone());
+        assert!(matches!(
+            result,
+            Err(CsvMdError::FieldTooLarge {
+                record: 2,
+                size: 1025,
+                limit: 1024,
+            })
+        ));
+
+        let input = "\"x\"\"y\",\"abc".as_bytes().chain(std::io::repeat(b'x'));
+        let result = read_records(input, &config).collect::<Result<Vec<_>>>();
+        assert!(matches!(
+            result,
+            Err(CsvMdError::FieldTooLarge { record: 1, .. })
+        ));
+
+        // Quotes, delimiters and line breaks aren't counted
+        let config = Config {
+            max_field_bytes: Some(2),
+            ..Config::default()
+        };
+        assert!(csv_to_markdown(Cursor::new("ab,c\r\nd,ef"), config.clone()).is_ok());
+        assert!(csv_to_markdown(Cursor::new("\"ab\",\"\"\"\"\"\"\nd,ef"), config.clone()).is_ok());
+        assert!(csv_to_markdown(Cursor::new("ab,c\nd,efg"), config).is_err());
+    }
+
+    #[test]
+    fn test_strip_all_bom() {
+        // Two BOM-prefixed files joined together
+        let csv_data = "\u{feff}Name,Age\nJohn,25\n\u{feff}Name,Age\nJane,3\u{feff}0\n";
+        let config = Config {
+            strip_all_bom: true,
+            ..Config::default()
+        };
+        let expected =
+            "| Name | Age |\n| --- | --- |\n| John | 25 |\n| Name | Age |\n| Jane | 30 |\n";
+        let result = csv_to_markdown(Cursor::new(csv_data), config.clone()).unwrap();
+        assert_eq!(result, expected);
+
+        let rows = csv_to_records_map(Cursor::new(csv_data), &config).unwrap();
+        assert_eq!(rows[1]["Name"], "Name");
+
+        // Only the BOM at the start of the input is removed by default
+        let result = csv_to_markdown(Cursor::new(csv_data), Config::default()).unwrap();
+        assert!(result.contains("| \u{feff}Name | Age |\n"));
+    }
+
+    #[test]
+    fn test_detect_delimiter() {
+        assert_eq!(detect_delimiter(b"Name;Age\nJohn;25\nJane;30"), b';');
+        assert_eq!(detect_delimiter(b"Name\tAge\nJohn\t25\n"), b'\t');
+        assert_eq!(detect_delimiter(b"Name|Age\r\nJohn|25\r\n"), b'|');
+
+        // The header has a quoted semicolon, but only commas split every row
+        assert_eq!(
+            detect_delimiter(b"\"Name;Full\",\"Age;Years\"\nJohn,25\nJane,30\n"),
+            b','
+        );
+
+        // Inconsistent column counts fall back to a comma
+        assert_eq!(detect_delimiter(b"A;B\n1\n2;3;4\n"), b',');
+        assert_eq!(detect_delimiter(b""), b',');
+
+        // A cut-off final line doesn't count against a delimiter
+        assert_eq!(detect_delimiter(b"A;B\n1;2\n3"), b';');
+    }
+
+    #[test]
+    fn test_detect_delimiter_option() {
+        let config = Config {
+            detect_delimiter: true,
+            ..Config::default()
+        };
+        let csv_data = "\"Name;Full\",Age\nJohn,25\nJane,30";
+        let expected = "| Name;Full | Age |\n| --- | --- |\n| John | 25 |\n| Jane | 30 |\n";
+
+        let result = csv_to_markdown(Cursor::new(csv_data), config.clone()).unwrap();
+        assert_eq!(result, expected);
+
+        let result = csv_to_markdown(Cursor::new("Name;Age\nJohn;25"), config.clone()).unwrap();
+        assert_eq!(result, "| Name | Age |\n| --- | --- |\n| John | 25 |\n");
+
+        let mut streamed = Vec::new();
+        csv_to_markdown_streaming(csv_data.as_bytes(), &mut streamed, config.clone()).unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+
+        let mut streamed = Vec::new();
+        csv_to_markdown_streaming_seekable(Cursor::new(csv_data), &mut streamed, config).unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_detect_header() {
+        let config = Config {
+            delimiter: b'|',
+            detect_header: true,
+            ..Config::default()
+        };
+
+        let headered = "level|time|message\ninfo|12:00|started\nwarn|12:01|slow";
+        let expected = "| level | time | message |\n| --- | --- | --- |\n\
+                        | info | 12:00 | started |\n| warn | 12:01 | slow |\n";
+        let result = csv_to_markdown(Cursor::new(headered), config.clone()).unwrap();
+        assert_eq!(result, expected);
+        let mut streamed = Vec::new();
+        csv_to_markdown_streaming_seekable(Cursor::new(headered), &mut streamed, config.clone())
+            .unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+
+        let headerless = "info|200|started\nwarn|503|slow";
+        let expected = "| info | 200 | started |\n| warn | 503 | slow |\n";
+        let result = csv_to_markdown(Cursor::new(headerless), config.clone()).unwrap();
+        assert_eq!(result, expected);
+        let mut streamed = Vec::new();
+        csv_to_markdown_streaming(headerless.as_bytes(), &mut streamed, config.clone()).unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+
+        assert!(!detect_header(Cursor::new("info||started"), &config).unwrap());
+        assert!(!detect_header(Cursor::new("ok|ok|fail"), &config).unwrap());
+        assert!(!detect_header(Cursor::new(""), &config).unwrap());
+        assert_eq!(
+            peek_header(Cursor::new(headerless), &config).unwrap(),
+            ["info", "200", "started"]
+        );
+    }
+
+    #[test]
+    fn test_sep_directive() {
+        let csv_data = "sep=;\r\nName;Age\r\nJohn;25\r\n";
+        let expected = "| Name | Age |\n| --- | --- |\n| John | 25 |\n";
+
+        let result = csv_to_markdown(Cursor::new(csv_data), Config::default()).unwrap();
+        assert_eq!(result, expected);
+
+        let mut streamed = Vec::new();
+        csv_to_markdown_streaming(csv_data.as_bytes(), &mut streamed, Config::default()).unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+
+        let mut streamed = Vec::new();
+        csv_to_markdown_streaming_seekable(Cursor::new(csv_data), &mut streamed, Config::default())
+            .unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+
+        assert_eq!(
+            suggest_delimiter(csv_data.as_bytes(), &Config::default()),
+            None
+        );
+
+        let config = Config {
+            honor_sep_directive: false,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(Cursor::new("sep=;\nA;B"), config).unwrap();
+        assert_eq!(result, "| sep=; |\n| --- |\n| A;B |\n");
+
+        // Only a single-character `sep=` line is a directive
+        let result = csv_to_markdown(Cursor::new("sep=ab\n1"), Config::default()).unwrap();
+        assert_eq!(result, "| sep=ab |\n| --- |\n| 1 |\n");
+    }
+
+    #[test]
+    fn test_validate_output() {
+        // Cells ending in a backslash must never escape the `|` after them
+        let csv_data = "Team,Name\nRed,\"A | B\"\nBlue,C\\\nRed\\\\\\";
+        for config in [
+            Config::default(),
+            Config {
+                minify: true,
+                ..Config::default()
+            },
+            Config {
+                cell_padding: false,
+                pretty: true,
+                ..Config::default()
+            },
+            Config {
+                cell_padding: false,
+                max_table_width: Some(12),
+                ..Config::default()
+            },
+            Config {
+                callout: Some(CalloutKind::Tip),
+                rows_per_table: Some(1),
+                footnote_over: Some(0),
+                ..Config::default()
+            },
+            Config {
+                group_by: Some(ColumnRef::Name("Team".to_string())),
+                ..Config::default()
+            },
+        ] {
+            let config = Config {
+                validate_output: true,
+                ..config
+            };
+            assert!(csv_to_markdown(Cursor::new(csv_data), config).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_validate_output_catches_invalid_tables() {
+        let config = Config {
+            validate_output: true,
+            ..Config::default()
+        };
+
+        // Without a header there's no separator row
+        let err = csv_to_markdown(
+            Cursor::new("a,b\n1,2"),
+            Config {
+                has_headers: false,
+                ..config.clone()
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, CsvMdError::InvalidOutput { line: 2, .. }));
+
+        let err = csv_to_markdown(
+            Cursor::new(""),
+            Config {
+                empty_input_message: Some("| none |".to_string()),
+                ..config
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid output on line 1: table has no separator row"
+        );
+    }
+
+    #[test]
+    fn test_checklist_from() {
+        let csv_data = "Task,Owner,Done\nWrite docs,Jane,yes\nShip it,John,no\nCelebrate,Jim,";
+        let config = Config {
+            checklist_from: Some(2),
+            ..Config::default()
+        };
+
+        let result = csv_to_markdown(Cursor::new(csv_data), config.clone()).unwrap();
+        assert_eq!(
+            result,
+            "| Done | Task | Owner | Done |\n| --- | --- | --- | --- |\n\
+             | [x] | Write docs | Jane | yes |\n| [ ] | Ship it | John | no |\n\
+             | [ ] | Celebrate | Jim |  |\n"
+        );
+
+        let config = Config {
+            checklist_hide_source: true,
+            ..config
+        };
+        let result = csv_to_markdown(Cursor::new(csv_data), config.clone()).unwrap();
+        assert_eq!(
+            result,
+            "| Done | Task | Owner |\n| --- | --- | --- |\n| [x] | Write docs | Jane |\n\
+             | [ ] | Ship it | John |\n| [ ] | Celebrate | Jim |\n"
+        );
+
+        let mut output = Vec::new();
+        csv_to_markdown_streaming(Cursor::new(csv_data), &mut output, config).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), result);
+    }
+
+    #[test]
+    fn test_null_values() {
+        let csv_data = "Name,Age,Score\nJohn,\\N,NULL\nJane,30,null\nJim,\\n,7";
+        let config = Config {
+            null_values: vec!["\\N".to_string(), "NULL".to_string()],
+            auto_align_numeric: true,
+            ..Config::default()
+        };
+
+        let result = csv_to_markdown(Cursor::new(csv_data), config.clone()).unwrap();
+        assert_eq!(
+            result,
+            "| Name | Age | Score |\n| --- | --- | --- |\n| John |  |  |\n\
+             | Jane | 30 | null |\n| Jim | \\n | 7 |\n"
+        );
+
+        let config = Config {
+            null_case_insensitive: true,
+            ..config
+        };
+        let result = csv_to_markdown(Cursor::new(csv_data), config.clone()).unwrap();
+        assert_eq!(
+            result,
+            "| Name | Age | Score |\n| --- | ---: | ---: |\n| John |  |  |\n\
+             | Jane | 30 |  |\n| Jim |  | 7 |\n"
+        );
+
+        // Nulls are matched ignoring whitespace, but never in the header row
+        let csv_data = "Name,NULL\nJohn, NULL \nJane,\t\\N\n";
+        let result = csv_to_markdown(Cursor::new(csv_data), config.clone()).unwrap();
+        assert_eq!(
+            result,
+            "| Name | NULL |\n| --- | --- |\n| John |  |\n| Jane |  |\n"
+        );
+
+        let config = Config {
+            has_headers: false,
+            ..config
+        };
+        let result = csv_to_markdown(Cursor::new("NULL,1\n"), config).unwrap();
+        assert_eq!(result, "|  | 1 |\n");
+    }
+
+    #[test]
+    fn test_escape_block_markers() {
+        let config = Config {
+            escape_block_markers: true,
+            ..Config::default()
+        };
+        for (cell, expected) in [
+            ("> quote", "\\> quote"),
+            ("# head", "\\# head"),
+            ("### head", "\\### head"),
+            ("- item", "\\- item"),
+            ("+ item", "\\+ item"),
+            ("1. list", "1\\. list"),
+            ("  12. list", "  12\\. list"),
+            (">", "\\>"),
+            // These can't start a block, so they're left alone
+            ("-5", "-5"),
+            ("+44 20", "+44 20"),
+            ("#hashtag", "#hashtag"),
+            ("1.5", "1.5"),
+            ("plain", "plain"),
+        ] {
+            let result =
+                csv_to_markdown(Cursor::new(format!("A\n\"{}\"", cell)), config.clone()).unwrap();
+            assert_eq!(
+                result,
+                format!("| A |\n| --- |\n| {} |\n", expected),
+                "{}",
+                cell
+            );
+        }
+    }
+
+    #[test]
+    fn test_block_markers_kept_by_default() {
+        let result = csv_to_markdown(Cursor::new("A\n> quote"), Config::default()).unwrap();
+        assert_eq!(result, "| A |\n| --- |\n| > quote |\n");
+    }
+
+    #[test]
+    fn test_trim_trailing_empty_columns() {
+        let input = Cursor::new("Name,Age,\nJohn,25,\nJane,30,");
+        let config = Config {
+            trim_trailing_empty_columns: true,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected = "| Name | Age |\n| --- | --- |\n| John | 25 |\n| Jane | 30 |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_trim_trailing_empty_columns_keeps_partly_filled_columns() {
+        let input = Cursor::new("Name,Age,\nJohn,25,x\nJane,30,,");
+        let config = Config {
+            trim_trailing_empty_columns: true,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected =
+            "| Name | Age |  |\n| --- | --- | --- |\n| John | 25 | x |\n| Jane | 30 |  |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_row_numbers() {
+        let input = Cursor::new("Name,Age\nJohn,25\nJane,30");
+        let config = Config {
+            row_numbers: true,
+            column_alignments: vec![HeaderAlignment::Left, HeaderAlignment::Right],
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected =
+            "| # | Name | Age |\n| --- | --- | ---: |\n| 1 | John | 25 |\n| 2 | Jane | 30 |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_row_number_start() {
+        for (start, first, second) in [(0, "0", "1"), (-1, "-1", "0"), (100, "100", "101")] {
+            let input = Cursor::new("Name\nJohn\nJane");
+            let mut output = Vec::new();
+            let config = Config {
+                row_numbers: true,
+                row_number_start: start,
+                ..Config::default()
+            };
+
+            csv_to_markdown_streaming(input, &mut output, config).unwrap();
+
+            let result = String::from_utf8(output).unwrap();
+            let expected = format!(
+                "| # | Name |\n| --- | --- |\n| {} | John |\n| {} | Jane |\n",
+                first, second
+            );
+            assert_eq!(result, expected);
+        }
+    }
+
+    #[test]
+    fn test_zero_pad_row_numbers() {
+        let csv: String = std::iter::once("Value".to_string())
+            .chain((0..120).map(|i| i.to_string()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let config = Config {
+            row_numbers: true,
+            zero_pad_row_numbers: true,
+            ..Config::default()
+        };
+
+        let result = csv_to_markdown(Cursor::new(&csv), config.clone()).unwrap();
+        let mut streamed = Vec::new();
+        csv_to_markdown_streaming(Cursor::new(&csv), &mut streamed, config).unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), result);
+
+        let lines: Vec<&str> = result.lines().collect();
+        assert_eq!(lines[0], "| # | Value |");
+        assert_eq!(lines[2], "| 001 | 0 |");
+        assert_eq!(lines[11], "| 010 | 9 |");
+        assert_eq!(lines[101], "| 100 | 99 |");
+        assert_eq!(lines[121], "| 120 | 119 |");
+    }
+
+    #[test]
+    fn test_row_numbers_without_headers() {
+        let input = Cursor::new("John,25\nJane");
+        let config = Config {
+            has_headers: false,
+            row_numbers: true,
+            row_number_start: 0,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        assert_eq!(result, "| 0 | John | 25 |\n| 1 | Jane |  |\n");
+    }
+
+    #[test]
+    fn test_width_hints() {
+        let input = Cursor::new("Name,Age,City\nJohn,25,東京\nJacqueline,3");
+        let config = Config {
+            emit_width_hints: true,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected = "<!-- csvmd:widths=10,3,4 -->\n| Name | Age | City |\n| --- | --- | --- |\n| John | 25 | 東京 |\n| Jacqueline | 3 |  |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_width_hints_with_row_numbers() {
+        let input = Cursor::new("Name\nA\nB");
+        let config = Config {
+            emit_width_hints: true,
+            row_numbers: true,
+            row_number_start: 9,
+            min_columns: Some(2),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        assert!(result.starts_with("<!-- csvmd:widths=2,4,0 -->\n| # | Name |  |\n"));
+    }
+
+    #[test]
+    fn test_single_column_mode() {
+        let csv_data = "Log line\nGET /a,b \"quoted\"\r\nPOST | c\n\ndone";
+        let config = Config {
+            single_column_mode: true,
+            ..Config::default()
+        };
+        let expected =
+            "| Log line |\n| --- |\n| GET /a,b \"quoted\" |\n| POST \\| c |\n|  |\n| done |\n";
+
+        let result = csv_to_markdown(Cursor::new(csv_data), config.clone()).unwrap();
+        assert_eq!(result, expected);
+
+        let mut output = Vec::new();
+        csv_to_markdown_streaming(Cursor::new(csv_data), &mut output, config).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_single_column_mode_without_headers() {
+        let input = Cursor::new("a,b\nc,d\n");
+        let config = Config {
+            single_column_mode: true,
+            has_headers: false,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        assert_eq!(result, "| a,b |\n| c,d |\n");
+    }
+
+    #[test]
+    fn test_collapse_delimiters() {
+        let csv_data = "Name   Size  Kind\n  a   b   c  \nd \"e\" f\n";
+        let config = Config {
+            delimiter: b' ',
+            collapse_delimiters: true,
+            ..Config::default()
+        };
+        let expected =
+            "| Name | Size | Kind |\n| --- | --- | --- |\n| a | b | c |\n| d | \"e\" | f |\n";
+
+        let result = csv_to_markdown(Cursor::new(csv_data), config.clone()).unwrap();
+        assert_eq!(result, expected);
+
+        let mut output = Vec::new();
+        csv_to_markdown_streaming(Cursor::new(csv_data), &mut output, config).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_streaming_mode_uneven_columns() {
+        // This test exposes the current streaming bug: early rows are malformed
+        // when later rows have more columns
+        let csv_data = "A,B\nX,Y,Z\nP,Q,R,S";
+        let input = Cursor::new(csv_data);
+        let mut output = Vec::new();
+        let config = Config::default();
+
+        csv_to_markdown_streaming(input, &mut output, config).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+
+        // Expected: all rows should have 4 columns (max from any row)
+        let expected =
+            "| A | B |  |  |\n| --- | --- | --- | --- |\n| X | Y | Z |  |\n| P | Q | R | S |\n";
+
+        // Fixed: streaming now uses two-pass approach to determine max_cols correctly
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_pad_side_start() {
+        let csv_data = "A,B,C,D\nX,Y\nP,Q,R,S";
+        let config = Config {
+            pad_side: PadSide::Start,
+            ..Config::default()
+        };
+        let expected =
+            "| A | B | C | D |\n| --- | --- | --- | --- |\n|  |  | X | Y |\n| P | Q | R | S |\n";
+
+        let result = csv_to_markdown(Cursor::new(csv_data), config.clone()).unwrap();
+        assert_eq!(result, expected);
+
+        let mut output = Vec::new();
+        csv_to_markdown_streaming(Cursor::new(csv_data), &mut output, config.clone()).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+
+        // The row number stays in the first column
+        let config = Config {
+            row_numbers: true,
+            ..config
+        };
+        let result = csv_to_markdown(Cursor::new("A,B,C\nX"), config).unwrap();
+        assert_eq!(
+            result,
+            "| # | A | B | C |\n| --- | --- | --- | --- |\n| 1 |  |  | X |\n"
+        );
+    }
+
+    #[test]
+    fn test_header_alignment_left() {
+        let csv_data = "Name,Age\nJohn,25\nJane,30";
+        let input = Cursor::new(csv_data);
+        let config = Config {
+            header_alignment: HeaderAlignment::Left,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected = "| Name | Age |\n| --- | --- |\n| John | 25 |\n| Jane | 30 |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_header_alignment_center() {
+        let csv_data = "Name,Age\nJohn,25\nJane,30";
+        let input = Cursor::new(csv_data);
+        let config = Config {
+            header_alignment: HeaderAlignment::Center,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected = "| Name | Age |\n| :---: | :---: |\n| John | 25 |\n| Jane | 30 |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_header_alignment_right() {
+        let csv_data = "Name,Age\nJohn,25\nJane,30";
+        let input = Cursor::new(csv_data);
+        let config = Config {
+            header_alignment: HeaderAlignment::Right,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected = "| Name | Age |\n| ---: | ---: |\n| John | 25 |\n| Jane | 30 |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_streaming_header_alignment_center() {
+        let csv_data = "Name,Age\nJohn,25\nJane,30";
+        let input = Cursor::new(csv_data);
+        let mut output = Vec::new();
+        let config = Config {
+            header_alignment: HeaderAlignment::Center,
+            ..Config::default()
+        };
+
+        csv_to_markdown_streaming(input, &mut output, config).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        let expected = "| Name | Age |\n| :---: | :---: |\n| John | 25 |\n| Jane | 30 |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_column_alignments() {
+        let input = Cursor::new("A,B,C\n1,2,3");
+        let config = Config {
+            header_alignment: HeaderAlignment::Center,
+            column_alignments: vec![HeaderAlignment::Right, HeaderAlignment::Left],
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected = "| A | B | C |\n| ---: | --- | :---: |\n| 1 | 2 | 3 |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_alignment_row() {
+        let csv_data = "Name,Status,Amount,Notes\n:,:-:,-:,\nJohn,active,10,hi\nJane,inactive,200,";
+        let expected = "| Name | Status | Amount | Notes |\n| --- | :---: | ---: | --- |\n| John | active | 10 | hi |\n| Jane | inactive | 200 |  |\n";
+
+        let result = csv_to_markdown(
+            Cursor::new(csv_data),
+            Config {
+                alignment_row: true,
+                ..Config::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(result, expected);
+
+        let mut output = Vec::new();
+        csv_to_markdown_streaming(
+            Cursor::new(csv_data),
+            &mut output,
+            Config {
+                alignment_row: true,
+                ..Config::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_alignment_row_follows_selected_columns() {
+        let input = Cursor::new("A,B\n-:,:-:\n1,2");
+        let config = Config {
+            alignment_row: true,
+            columns: Some(vec!["B".to_string(), "A".to_string()]),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        assert_eq!(result, "| B | A |\n| :---: | ---: |\n| 2 | 1 |\n");
+    }
+
+    #[test]
+    fn test_types_row() {
+        let csv_data = "Name,Age,Joined,Score\nstring,int,date,FLOAT\nJohn,25,2024-01-02,1.5";
+        let expected = "| Name | Age | Joined | Score |\n| --- | ---: | ---: | ---: |\n\
+                        | John | 25 | 2024-01-02 | 1.5 |\n";
+        let config = Config {
+            types_row: Some(1),
+            ..Config::default()
+        };
+
+        let result = csv_to_markdown(Cursor::new(csv_data), config.clone()).unwrap();
+        assert_eq!(result, expected);
+
+        let mut output = Vec::new();
+        csv_to_markdown_streaming(Cursor::new(csv_data), &mut output, config.clone()).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+
+        let result = csv_to_markdown(
+            Cursor::new("A\n1\nwhatever"),
+            Config {
+                types_row: Some(2),
+                ..config
+            },
+        );
+        assert!(
+            matches!(result, Err(CsvMdError::InvalidConfig(message)) if message.contains("`whatever`"))
+        );
+    }
+
+    #[test]
+    fn test_alignment_row_invalid_marker() {
+        let input = Cursor::new("A,B\n1,2\n3,4");
+        let config = Config {
+            alignment_row: true,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config);
+
+        assert!(
+            matches!(result, Err(CsvMdError::InvalidConfig(message)) if message.contains("`1`"))
+        );
+    }
+
+    #[test]
+    fn test_stats_max_line_width_counts_wide_characters() {
+        let input = Cursor::new("City,Code\n東京都庁,13");
+        let (markdown, stats) = csv_to_markdown_with_stats(input, Config::default()).unwrap();
+
+        assert_eq!(
+            markdown,
+            "| City | Code |\n| --- | --- |\n| 東京都庁 | 13 |\n"
+        );
+        // `| 東京都庁 | 13 |` is 13 characters, but each CJK character is two columns wide
+        assert_eq!(stats.max_line_width, 17);
+        assert_eq!(stats.rows, 1);
+        assert_eq!(stats.columns, 2);
+    }
+
+    #[test]
+    fn test_separator_before_first_row() {
+        let input = Cursor::new("A,B\n1,2");
+        let config = Config {
+            separator_position: SeparatorPosition::BeforeFirst,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        assert_eq!(result, "| --- | --- |\n| A | B |\n| 1 | 2 |\n");
+    }
+
+    #[test]
+    fn test_separator_position_ignored_without_headers() {
+        let input = Cursor::new("A,B\n1,2");
+        let config = Config {
+            has_headers: false,
+            separator_position: SeparatorPosition::BeforeFirst,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        assert_eq!(result, "| A | B |\n| 1 | 2 |\n");
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("1e10"), Some(NumberKind::Scientific));
+        assert_eq!(parse_number("-3.14"), Some(NumberKind::Decimal));
+        assert_eq!(parse_number("NaN"), Some(NumberKind::NonFinite));
+        assert_eq!(parse_number("-inf"), Some(NumberKind::NonFinite));
+        assert_eq!(parse_number(" +0 "), Some(NumberKind::Decimal));
+        assert_eq!(parse_number("12abc"), None);
+        assert_eq!(parse_number(""), None);
+    }
+
+    #[test]
+    fn test_auto_align_numeric() {
+        let input =
+            Cursor::new("Name,Score,Ratio,Code\nJohn,1e10,-3.14,A1\nJane,NaN,+0,\nJim,,inf,7");
+        let config = Config {
+            auto_align_numeric: true,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        assert!(result.contains("\n| --- | ---: | ---: | --- |\n"));
+    }
+
+    #[test]
+    fn test_auto_align_numeric_keeps_explicit_alignments() {
+        let input = Cursor::new("A,B\n1,2");
+        let config = Config {
+            auto_align_numeric: true,
+            column_alignments: vec![HeaderAlignment::Center],
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        assert!(result.contains("\n| :---: | ---: |\n"));
+    }
+
+    #[test]
+    fn test_thousands_separator() {
+        let input = Cursor::new(
+            "1000,Value,Note\nA,1234567.891,2024\nB,-1234,n/a\nC,NaN,\nD,1e10,\nE,+0,\n\
+             F,+12345,\nG,999,\nH,01234,",
+        );
+        let config = Config {
+            thousands_separator: Some(','),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        // Only columns of numbers are grouped, and never codes with a leading zero
+        let expected = "| 1000 | Value | Note |\n| --- | --- | --- |\n\
+                        | A | 1,234,567.891 | 2024 |\n| B | -1,234 | n/a |\n| C | NaN |  |\n\
+                        | D | 1e10 |  |\n| E | +0 |  |\n| F | +12,345 |  |\n| G | 999 |  |\n\
+                        | H | 01234 |  |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_column_number_format() {
+        let input = Cursor::new(
+            "Item,Price,Qty,Code\nTea,3.14159,1234.5,12\nCake,1.005,2.5,n/a\nJam,NaN,-0.4,7\nPie,1e3,,",
+        );
+        let config = Config {
+            column_number_format: vec![
+                None,
+                Some(NumberFormat {
+                    decimals: 2,
+                    thousands_separator: None,
+                }),
+                Some("#,##0".parse().unwrap()),
+                Some("0.0".parse().unwrap()),
+            ],
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        // Ties round away from zero, and only columns of numbers are formatted
+        let expected = "| Item | Price | Qty | Code |\n| --- | --- | --- | --- |\n\
+                        | Tea | 3.14 | 1,235 | 12 |\n| Cake | 1.01 | 3 | n/a |\n\
+                        | Jam | NaN | 0 | 7 |\n| Pie | 1000.00 |  |  |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_round_decimal() {
+        for (number, decimals, expected) in [
+            ("2.5", 0, "3"),
+            ("-2.5", 0, "-3"),
+            ("0.125", 2, "0.13"),
+            ("-0.001", 2, "0.00"),
+            ("+9.99", 1, "10.0"),
+            (".5", 0, "1"),
+            ("007", 1, "7.0"),
+        ] {
+            assert_eq!(round_decimal(number, decimals), expected, "{}", number);
+        }
+    }
+
+    #[test]
+    fn test_parse_number_format() {
+        let format: NumberFormat = "0.00".parse().unwrap();
+        assert_eq!(format.decimals, 2);
+        assert_eq!(format.thousands_separator, None);
 
-    // Rough estimate: (avg_cell_size + 3) * cols * rows + separators
-    (avg_cell_size + 3) * max_cols * records.len() + (max_cols * 6) + 100
-}
+        let format: NumberFormat = "# ##0.0".parse().unwrap();
+        assert_eq!(format.decimals, 1);
+        assert_eq!(format.thousands_separator, Some(' '));
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::io::Cursor;
+        for invalid in ["", "abc", "0.0#", "#,##0.", ".00x", "#,# ##0"] {
+            assert!(invalid.parse::<NumberFormat>().is_err(), "{:?}", invalid);
+        }
+    }
 
     #[test]
-    fn test_escape_markdown_cell() {
-        assert_eq!(escape_markdown_cell("simple"), "simple");
-        assert_eq!(escape_markdown_cell("with|pipe"), "with\\|pipe");
-        assert_eq!(escape_markdown_cell("with\nlinebreak"), "with<br>linebreak");
-        assert_eq!(escape_markdown_cell("with\r\nwindows"), "with<br>windows");
-        assert_eq!(escape_markdown_cell(""), "");
+    fn test_empty_input_message() {
+        for csv_data in ["", "\n\n", "  \n\t\n"] {
+            let config = Config {
+                empty_input_message: Some("_No data_".to_string()),
+                ..Config::default()
+            };
+
+            let result = csv_to_markdown(Cursor::new(csv_data), config.clone()).unwrap();
+            assert_eq!(result, "_No data_\n");
+
+            let mut output = Vec::new();
+            csv_to_markdown_streaming(Cursor::new(csv_data), &mut output, config).unwrap();
+            assert_eq!(String::from_utf8(output).unwrap(), "_No data_\n");
+        }
+    }
+
+    #[test]
+    fn test_empty_input_without_message() {
+        assert_eq!(
+            csv_to_markdown(Cursor::new(""), Config::default()).unwrap(),
+            ""
+        );
+        assert_eq!(
+            csv_to_markdown(Cursor::new("\n\n"), Config::default()).unwrap(),
+            ""
+        );
+        assert_eq!(
+            csv_to_markdown(Cursor::new("  \n"), Config::default()).unwrap(),
+            "|    |\n| --- |\n"
+        );
+    }
+
+    #[test]
+    fn test_empty_input_message_not_used_for_header_only_input() {
+        let input = Cursor::new("Name,Age");
+        let config = Config {
+            empty_input_message: Some("_No data_".to_string()),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        assert_eq!(result, "| Name | Age |\n| --- | --- |\n");
+    }
+
+    #[test]
+    fn test_bold_headers() {
+        let input = Cursor::new("Name,a|b,\nJohn,25,x");
+        let config = Config {
+            bold_headers: true,
+            header_alignment: HeaderAlignment::Center,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected =
+            "| **Name** | **a\\|b** |  |\n| :---: | :---: | :---: |\n| John | 25 | x |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_bold_first_column() {
+        let input = Cursor::new("Plan,Price,Seats\nFree,0,1\n,10,5\nTeam,20,");
+        let config = Config {
+            bold_first_column: true,
+            header_alignment: HeaderAlignment::Right,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected = "| Plan | Price | Seats |\n| --- | ---: | ---: |\n| **Free** | 0 | 1 |\n\
+                        |  | 10 | 5 |\n| **Team** | 20 |  |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_bold_headers_without_headers() {
+        let input = Cursor::new("Name,Age\nJohn,25");
+        let config = Config {
+            has_headers: false,
+            bold_headers: true,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        assert_eq!(result, "| Name | Age |\n| John | 25 |\n");
+    }
+
+    #[test]
+    fn test_header_alignment_no_headers() {
+        let csv_data = "Data1,Data2\nValue1,Value2";
+        let input = Cursor::new(csv_data);
+        let config = Config {
+            has_headers: false,
+            header_alignment: HeaderAlignment::Center, // Should be ignored
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        // Should not have separator line when no headers, regardless of alignment
+        let expected = "| Data1 | Data2 |\n| Value1 | Value2 |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_select_columns_with_duplicate_names() {
+        let csv_data = "A,B,A\n1,2,3\n4,5,6";
+        let input = Cursor::new(csv_data);
+        let config = Config {
+            columns: Some(vec!["A".to_string()]),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        // Every column named "A" is kept, in its original order
+        let expected = "| A | A |\n| --- | --- |\n| 1 | 3 |\n| 4 | 6 |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_select_columns_first_match_only() {
+        let csv_data = "A,B,A\n1,2,3\n4,5,6";
+        let input = Cursor::new(csv_data);
+        let config = Config {
+            columns: Some(vec!["A".to_string()]),
+            first_match_only: true,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected = "| A |\n| --- |\n| 1 |\n| 4 |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_select_columns_reorders_and_streams() {
+        let csv_data = "A,B,A\n1,2,3";
+        let input = Cursor::new(csv_data);
+        let mut output = Vec::new();
+        let config = Config {
+            columns: Some(vec!["B".to_string(), "A".to_string()]),
+            ..Config::default()
+        };
+
+        csv_to_markdown_streaming(input, &mut output, config).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        let expected = "| B | A | A |\n| --- | --- | --- |\n| 2 | 1 | 3 |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    #[cfg(feature = "dates")]
+    fn test_date_format() {
+        let config = Config {
+            date_format: Some("%Y-%m-%d".to_string()),
+            ..Config::default()
+        };
+        let input = "Day,Due,Note\n01/02/2024,01/02/2024,01/02/2024\n15/03/2024,12/31/2024,n/a\n,,";
+        let result = csv_to_markdown(Cursor::new(input), config).unwrap();
+
+        // Slashed dates are read day first, unless a date in the column only
+        // makes sense month first, and columns with other values are left alone
+        let expected = "| Day | Due | Note |\n| --- | --- | --- |\n\
+                        | 2024-02-01 | 2024-01-02 | 01/02/2024 |\n\
+                        | 2024-03-15 | 2024-12-31 | n/a |\n\
+                        |  |  |  |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    #[cfg(feature = "dates")]
+    fn test_date_format_invalid_pattern() {
+        let config = Config {
+            date_format: Some("%Q".to_string()),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(Cursor::new("Day\n2024-02-01"), config);
+
+        assert!(
+            matches!(result, Err(CsvMdError::InvalidConfig(message)) if message.contains("invalid date format"))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn test_select_columns_regex() {
+        let input = Cursor::new("metric_a,name,metric_b,other\n1,x,2,y");
+        let config = Config {
+            select_columns_regex: Some("^metric_".to_string()),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected = "| metric_a | metric_b |\n| --- | --- |\n| 1 | 2 |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn test_select_columns_invalid_regex() {
+        let input = Cursor::new("A,B\n1,2");
+        let config = Config {
+            select_columns_regex: Some("(".to_string()),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config);
+
+        assert!(
+            matches!(result, Err(CsvMdError::InvalidConfig(message)) if message.contains("invalid column regex"))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn test_select_columns_regex_without_match() {
+        let input = Cursor::new("A,B\n1,2");
+        let config = Config {
+            select_columns_regex: Some("^metric_".to_string()),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config);
+
+        assert!(
+            matches!(result, Err(CsvMdError::ColumnNotFound(pattern)) if pattern == "^metric_")
+        );
+    }
+
+    #[test]
+    fn test_select_unknown_column() {
+        let input = Cursor::new("A,B\n1,2");
+        let config = Config {
+            columns: Some(vec!["C".to_string()]),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config);
+
+        assert!(matches!(result, Err(CsvMdError::ColumnNotFound(name)) if name == "C"));
+    }
+
+    #[test]
+    fn test_tab_width_expands_tabs() {
+        let input = Cursor::new("Code\n\"a\tb\"");
+        let config = Config {
+            tab_width: Some(4),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        assert_eq!(result, "| Code |\n| --- |\n| a    b |\n");
+    }
+
+    #[test]
+    fn test_tabs_kept_without_tab_width() {
+        let input = Cursor::new("Code\n\"a\tb\"");
+        let result = csv_to_markdown(input, Config::default()).unwrap();
+
+        assert_eq!(result, "| Code |\n| --- |\n| a\tb |\n");
+    }
+
+    #[test]
+    fn test_parse_row_filter() {
+        let filter: RowFilter = "status==active".parse().unwrap();
+        assert_eq!(filter.column, ColumnRef::Name("status".to_string()));
+        assert_eq!(filter.op, FilterOp::Equals);
+        assert_eq!(filter.value, "active");
+
+        let filter: RowFilter = "2!=0".parse().unwrap();
+        assert_eq!(filter.column, ColumnRef::Index(2));
+        assert_eq!(filter.op, FilterOp::NotEquals);
+
+        let filter: RowFilter = "name contains Smith".parse().unwrap();
+        assert_eq!(filter.op, FilterOp::Contains);
+        assert_eq!(filter.value, "Smith");
+
+        assert!("nonsense".parse::<RowFilter>().is_err());
+        assert!("==value".parse::<RowFilter>().is_err());
+    }
+
+    #[test]
+    fn test_pin_rows_to_bottom() {
+        let csv_data = "Item,Cost\nRent,500\nTotal,800\nFood,200\nTax,100\nTotal,800";
+        let config = Config {
+            pin_rows_to_bottom: vec!["Total".to_string()],
+            row_numbers: true,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(Cursor::new(csv_data), config.clone()).unwrap();
+
+        let expected = "| # | Item | Cost |\n| --- | --- | --- |\n| 1 | Rent | 500 |\n\
+                        | 2 | Food | 200 |\n| 3 | Tax | 100 |\n| 4 | Total | 800 |\n\
+                        | 5 | Total | 800 |\n";
+        assert_eq!(result, expected);
+
+        let mut streamed = Vec::new();
+        csv_to_markdown_streaming(Cursor::new(csv_data), &mut streamed, config).unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_sparkline_columns() {
+        let csv_data = "Team,Score\nA,40\nB,10\nC,\nD,1000\nE,505";
+        let config = Config {
+            sparkline_columns: vec![1],
+            thousands_separator: Some(','),
+            ..Config::default()
+        };
+        let expected = "| Team | Score |\n| --- | --- |\n| A | 40 ▍ |\n| B | 10 ▏ |\n| C |  |\n\
+                        | D | 1,000 ████████ |\n| E | 505 ████▏ |\n";
+
+        let result = csv_to_markdown(Cursor::new(csv_data), config.clone()).unwrap();
+        assert_eq!(result, expected);
+
+        let mut streamed = Vec::new();
+        csv_to_markdown_streaming(Cursor::new(csv_data), &mut streamed, config).unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_sparkline_bar() {
+        let range = (-5.0, 15.0);
+        let bars: Vec<String> = [-5.0, 0.0, 5.0, 15.0]
+            .into_iter()
+            .map(|value| sparkline_bar(value, range))
+            .collect();
+
+        // The smallest value has the shortest bar and the largest the longest
+        assert_eq!(bars[0], "▏");
+        assert_eq!(bars[3], "████████");
+        assert!(bars
+            .windows(2)
+            .all(|pair| pair[0].chars().count() <= pair[1].chars().count()));
+        assert_eq!(sparkline_bar(3.0, (3.0, 3.0)), "████████");
+    }
+
+    #[test]
+    fn test_preview() {
+        let csv_data = "N,Square\n1,1\n2,4\n3,9\n4,16\n5,25\n6,36\n7,49,extra";
+        let config = Config {
+            preview: Some((2, 2)),
+            ..Config::default()
+        };
+        let expected = "| N | Square |  |\n| --- | --- | --- |\n| 1 | 1 |  |\n| 2 | 4 |  |\n\
+                        | … | … | … |\n| 6 | 36 |  |\n| 7 | 49 | extra |\n";
+
+        let result = csv_to_markdown(Cursor::new(csv_data), config.clone()).unwrap();
+        assert_eq!(result, expected);
+
+        let mut streamed = Vec::new();
+        csv_to_markdown_streaming(Cursor::new(csv_data), &mut streamed, config).unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_preview_of_short_input() {
+        // Nothing is left out, so there's no elision row
+        let csv_data = "N\n1\n2\n3\n4";
+        for preview in [(2, 2), (3, 3), (0, 5)] {
+            let config = Config {
+                preview: Some(preview),
+                ..Config::default()
+            };
+            let result = csv_to_markdown(Cursor::new(csv_data), config).unwrap();
+            assert_eq!(result, "| N |\n| --- |\n| 1 |\n| 2 |\n| 3 |\n| 4 |\n");
+        }
+
+        let config = Config {
+            preview: Some((1, 0)),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(Cursor::new(csv_data), config).unwrap();
+        assert_eq!(result, "| N |\n| --- |\n| 1 |\n| … |\n");
+    }
+
+    #[test]
+    fn test_preview_with_summary_row() {
+        // The summary is of every row, including those left out
+        let csv_data = "Item,Price,Quantity\nApple,1,3\nPear,2,4\nPlum,3,5";
+        let config = Config {
+            preview: Some((1, 0)),
+            summary_row: Some(SummaryKind::Sum),
+            ..Config::default()
+        };
+        let expected = "| Item | Price | Quantity |\n| --- | --- | --- |\n| Apple | 1 | 3 |\n\
+                        | … | … | … |\n| Total | 6 | 12 |\n";
+
+        let result = csv_to_markdown(Cursor::new(csv_data), config.clone()).unwrap();
+        assert_eq!(result, expected);
+
+        let mut streamed = Vec::new();
+        csv_to_markdown_streaming(Cursor::new(csv_data), &mut streamed, config).unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_tty_row_limit() {
+        struct Endless;
+        impl Read for Endless {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                for pair in buf.chunks_exact_mut(2) {
+                    pair.copy_from_slice(b"1\n");
+                }
+                Ok(buf.len() / 2 * 2)
+            }
+        }
+
+        // The conversion stops as soon as there are too many rows
+        let config = Config {
+            tty_row_limit: Some(3),
+            ..Config::default()
+        };
+        let endless = || "N\n".as_bytes().chain(Endless);
+        let err = csv_to_markdown(endless(), config.clone()).unwrap_err();
+        assert!(matches!(
+            err,
+            CsvMdError::TooManyRowsForTerminal { limit: 3 }
+        ));
+
+        let input = Cursor::new("N\n1\n2\n3\n4\n");
+        let err =
+            csv_to_markdown_streaming_seekable(input, std::io::sink(), config.clone()).unwrap_err();
+        assert!(matches!(
+            err,
+            CsvMdError::TooManyRowsForTerminal { limit: 3 }
+        ));
+
+        let err = formats::convert_to(endless(), config.clone(), formats::OutputFormat::Json)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            CsvMdError::TooManyRowsForTerminal { limit: 3 }
+        ));
+
+        // Rows left out of the table don't count
+        let config = Config {
+            filter: Some("N!=2".parse().unwrap()),
+            ..config
+        };
+        let result = csv_to_markdown(Cursor::new("N\n1\n2\n3\n4"), config).unwrap();
+        assert_eq!(result, "| N |\n| --- |\n| 1 |\n| 3 |\n| 4 |\n");
+    }
+
+    #[test]
+    fn test_filter_equals_by_name() {
+        let csv_data = "Name,Status\nJohn,active\nJane,inactive\nJim,active";
+        let input = Cursor::new(csv_data);
+        let config = Config {
+            filter: Some("Status==active".parse().unwrap()),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected = "| Name | Status |\n| --- | --- |\n| John | active |\n| Jim | active |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_filter_contains_skips_short_rows() {
+        // The short rows are too narrow to have column 2, so never match
+        let csv_data = "A,B,C\nx,y\nx,y,zap\nx,y,zip,extra\nx";
+        let input = Cursor::new(csv_data);
+        let config = Config {
+            filter: Some("2 contains z".parse().unwrap()),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected = "| A | B | C |  |\n| --- | --- | --- | --- |\n| x | y | zap |  |\n| x | y | zip | extra |\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_filter_not_equals_short_rows_do_not_match() {
+        let csv_data = "A,B\n1,keep\n2\n3,drop";
+        let input = Cursor::new(csv_data);
+        let mut output = Vec::new();
+        let config = Config {
+            filter: Some("B!=drop".parse().unwrap()),
+            ..Config::default()
+        };
+
+        csv_to_markdown_streaming(input, &mut output, config).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert_eq!(result, "| A | B |\n| --- | --- |\n| 1 | keep |\n");
     }
 
     #[test]
-    fn test_simple_csv() {
-        let csv_data = "Name,Age\nJohn,25\nJane,30";
-        let input = Cursor::new(csv_data);
-        let config = Config::default();
+    fn test_min_columns_pads_narrow_tables() {
+        let input = Cursor::new("A,B\n1,2");
+        let config = Config {
+            min_columns: Some(4),
+            ..Config::default()
+        };
         let result = csv_to_markdown(input, config).unwrap();
 
-        let expected = "| Name | Age |\n| --- | --- |\n| John | 25 |\n| Jane | 30 |\n";
+        let expected = "| A | B |  |  |\n| --- | --- | --- | --- |\n| 1 | 2 |  |  |\n";
         assert_eq!(result, expected);
     }
 
     #[test]
-    fn test_empty_csv() {
-        let csv_data = "";
-        let input = Cursor::new(csv_data);
-        let config = Config::default();
-        let result = csv_to_markdown(input, config).unwrap();
+    fn test_min_columns_does_not_shrink_wider_tables() {
+        let input = Cursor::new("A,B,C\n1,2,3");
+        let mut output = Vec::new();
+        let config = Config {
+            min_columns: Some(2),
+            ..Config::default()
+        };
 
-        assert_eq!(result, "");
+        csv_to_markdown_streaming(input, &mut output, config).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert_eq!(
+            result,
+            "| A | B | C |\n| --- | --- | --- |\n| 1 | 2 | 3 |\n"
+        );
     }
 
     #[test]
-    fn test_csv_with_line_breaks() {
-        let csv_data = "Name,Description\nJohn,\"Line 1\nLine 2\"";
+    fn test_boolean_columns_as_checkboxes() {
+        let csv_data = "Task,Done\nWrite,TRUE\nTest,no\nShip,Yes\nFix,0\nDocs,maybe";
         let input = Cursor::new(csv_data);
-        let config = Config::default();
+        let config = Config {
+            boolean_columns: vec![1],
+            boolean_as_checkbox: true,
+            ..Config::default()
+        };
         let result = csv_to_markdown(input, config).unwrap();
 
-        let expected = "| Name | Description |\n| --- | --- |\n| John | Line 1<br>Line 2 |\n";
+        // Mixed-case booleans are converted, anything else is left untouched
+        let expected = "| Task | Done |\n| --- | --- |\n| Write | [x] |\n| Test | [ ] |\n| Ship | [x] |\n| Fix | [ ] |\n| Docs | maybe |\n";
         assert_eq!(result, expected);
     }
 
     #[test]
-    fn test_csv_with_pipes() {
-        let csv_data = "Name,Description\nJohn,\"Has | pipe\"";
+    fn test_boolean_columns_auto_detected() {
+        let csv_data = "Task,Done,Notes\nWrite,true,yes please\nTest,,\nShip,False,no";
         let input = Cursor::new(csv_data);
-        let config = Config::default();
-        let result = csv_to_markdown(input, config).unwrap();
+        let mut output = Vec::new();
+        let config = Config {
+            boolean_as_checkbox: true,
+            ..Config::default()
+        };
 
-        let expected = "| Name | Description |\n| --- | --- |\n| John | Has \\| pipe |\n";
+        csv_to_markdown_streaming(input, &mut output, config).unwrap();
+
+        // Only "Done" has nothing but booleans, so "Notes" keeps its "no"
+        let result = String::from_utf8(output).unwrap();
+        let expected = "| Task | Done | Notes |\n| --- | --- | --- |\n| Write | [x] | yes please |\n| Test |  |  |\n| Ship | [ ] | no |\n";
         assert_eq!(result, expected);
     }
 
+    fn filtered_to_empty(empty_table_behavior: EmptyTableBehavior) -> Config {
+        Config {
+            filter: Some("Status==missing".parse().unwrap()),
+            empty_table_behavior,
+            ..Config::default()
+        }
+    }
+
     #[test]
-    fn test_csv_with_uneven_columns() {
-        let csv_data = "A,B,C\nX,Y\nP,Q,R,S";
-        let input = Cursor::new(csv_data);
-        let config = Config::default();
+    fn test_empty_table_header_only() {
+        let input = Cursor::new("Name,Status\nJohn,active");
+        let config = filtered_to_empty(EmptyTableBehavior::HeaderOnly);
         let result = csv_to_markdown(input, config).unwrap();
 
-        let expected =
-            "| A | B | C |  |\n| --- | --- | --- | --- |\n| X | Y |  |  |\n| P | Q | R | S |\n";
-        assert_eq!(result, expected);
+        assert_eq!(result, "| Name | Status |\n| --- | --- |\n");
     }
 
     #[test]
-    fn test_single_row_csv() {
-        let csv_data = "Name,Age,City";
-        let input = Cursor::new(csv_data);
-        let config = Config::default();
+    fn test_empty_table_header_with_empty_row() {
+        let input = Cursor::new("Name,Status\nJohn,active");
+        let config = filtered_to_empty(EmptyTableBehavior::HeaderWithEmptyRow);
         let result = csv_to_markdown(input, config).unwrap();
 
-        let expected = "| Name | Age | City |\n| --- | --- | --- |\n";
-        assert_eq!(result, expected);
+        assert_eq!(result, "| Name | Status |\n| --- | --- |\n|  |  |\n");
+
+        let input = Cursor::new("Name,Status\nJohn,active");
+        let mut output = Vec::new();
+        let config = filtered_to_empty(EmptyTableBehavior::HeaderWithEmptyRow);
+        csv_to_markdown_streaming(input, &mut output, config).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), result);
     }
 
     #[test]
-    fn test_csv_with_empty_cells() {
-        let csv_data = "Name,Age,City\nJohn,,NYC\n,25,";
-        let input = Cursor::new(csv_data);
-        let config = Config::default();
+    fn test_empty_table_nothing() {
+        let input = Cursor::new("Name,Status\nJohn,active");
+        let config = filtered_to_empty(EmptyTableBehavior::Nothing);
         let result = csv_to_markdown(input, config).unwrap();
 
-        let expected =
-            "| Name | Age | City |\n| --- | --- | --- |\n| John |  | NYC |\n|  | 25 |  |\n";
-        assert_eq!(result, expected);
+        assert_eq!(result, "");
+
+        let input = Cursor::new("Name,Status\nJohn,active");
+        let mut output = Vec::new();
+        let config = filtered_to_empty(EmptyTableBehavior::Nothing);
+        csv_to_markdown_streaming(input, &mut output, config).unwrap();
+
+        assert!(output.is_empty());
     }
 
     #[test]
-    fn test_csv_with_quotes_and_commas() {
-        let csv_data = "Name,Description\nJohn,\"Smith, Jr.\"\nJane,\"O'Connor\"";
-        let input = Cursor::new(csv_data);
-        let config = Config::default();
-        let result = csv_to_markdown(input, config).unwrap();
+    fn test_schema_table() {
+        let input =
+            "Name,Age,Score,Active,Notes,\nJohn,25,1.5,yes,,\nJane,30,2e3,no,,\nJim,,7,true,x,";
+        let config = Config {
+            schema_table: true,
+            summary_row: Some(SummaryKind::Sum),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(Cursor::new(input), config).unwrap();
 
-        let expected =
-            "| Name | Description |\n| --- | --- |\n| John | Smith, Jr. |\n| Jane | O'Connor |\n";
+        let expected = "| Column | Inferred Type |\n| --- | --- |\n| Name | Text |\n\
+                        | Age | Integer |\n| Score | Number |\n| Active | Boolean |\n\
+                        | Notes | Text |\n| Column 6 | Text |\n";
         assert_eq!(result, expected);
     }
 
     #[test]
-    fn test_csv_with_special_characters() {
-        let csv_data = "Symbol,Unicode\n★,\"U+2605\"\n♠,\"U+2660\"";
-        let input = Cursor::new(csv_data);
-        let config = Config::default();
-        let result = csv_to_markdown(input, config).unwrap();
+    #[cfg(feature = "dates")]
+    fn test_schema_table_dates() {
+        let config = Config {
+            schema_table: true,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(Cursor::new("Day\n01/02/2024\n2024-02-03"), config.clone());
+        assert!(result.unwrap().ends_with("| Day | Text |\n"));
 
-        let expected = "| Symbol | Unicode |\n| --- | --- |\n| ★ | U+2605 |\n| ♠ | U+2660 |\n";
-        assert_eq!(result, expected);
+        let result = csv_to_markdown(Cursor::new("Day\n01/02/2024\n12/31/2024"), config);
+        assert!(result.unwrap().ends_with("| Day | Date |\n"));
     }
 
     #[test]
-    fn test_csv_with_whitespace() {
-        let csv_data = " Name , Age \n John , 25 \n Jane , 30 ";
-        let input = Cursor::new(csv_data);
-        let config = Config::default();
+    fn test_column_tables() {
+        let input = Cursor::new("Name,Age\nJohn,25\nJane,30");
+        let config = Config {
+            column_tables: true,
+            ..Config::default()
+        };
         let result = csv_to_markdown(input, config).unwrap();
 
-        let expected = "|  Name  |  Age  |\n| --- | --- |\n|  John  |  25  |\n|  Jane  |  30  |\n";
+        let expected = "## Name\n\n| Row | Value |\n| --- | --- |\n| 1 | John |\n| 2 | Jane |\n\n## Age\n\n| Row | Value |\n| --- | --- |\n| 1 | 25 |\n| 2 | 30 |\n";
         assert_eq!(result, expected);
     }
 
     #[test]
-    fn test_no_headers_config() {
-        let csv_data = "Data1,Data2\nValue1,Value2";
-        let input = Cursor::new(csv_data);
+    fn test_column_tables_streaming_without_headers() {
+        let input = Cursor::new("John,25\nJane,30");
+        let mut output = Vec::new();
         let config = Config {
             has_headers: false,
+            column_tables: true,
             ..Config::default()
         };
-        let result = csv_to_markdown(input, config).unwrap();
 
-        // Should not have separator line when no headers
-        let expected = "| Data1 | Data2 |\n| Value1 | Value2 |\n";
+        csv_to_markdown_streaming(input, &mut output, config).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        let expected = "## Column 1\n\n| Row | Value |\n| --- | --- |\n| 1 | John |\n| 2 | Jane |\n\n## Column 2\n\n| Row | Value |\n| --- | --- |\n| 1 | 25 |\n| 2 | 30 |\n";
         assert_eq!(result, expected);
     }
 
     #[test]
-    fn test_custom_delimiter() {
-        let csv_data = "Name;Age\nJohn;25\nJane;30";
-        let input = Cursor::new(csv_data);
+    fn test_group_by() {
+        let input = Cursor::new("Name,Team\nJohn,Red\nJane,Blue\nJim,Red\nJo,");
         let config = Config {
-            delimiter: b';',
+            group_by: Some(ColumnRef::Name("Team".to_string())),
             ..Config::default()
         };
         let result = csv_to_markdown(input, config).unwrap();
 
-        let expected = "| Name | Age |\n| --- | --- |\n| John | 25 |\n| Jane | 30 |\n";
+        let expected = "## Red\n\n| Name | Team |\n| --- | --- |\n| John | Red |\n| Jim | Red |\n\n## Blue\n\n| Name | Team |\n| --- | --- |\n| Jane | Blue |\n\n## (empty)\n\n| Name | Team |\n| --- | --- |\n| Jo |  |\n";
         assert_eq!(result, expected);
     }
 
     #[test]
-    fn test_streaming_mode() {
-        let csv_data = "Name,Age\nJohn,25\nJane,30";
-        let input = Cursor::new(csv_data);
+    fn test_group_by_with_toc() {
+        let input = Cursor::new("Name,Group\nJohn,My Group 1\nJane,Ops & Admin\nJim,my group 1!");
         let mut output = Vec::new();
-        let config = Config::default();
+        let config = Config {
+            group_by: Some(ColumnRef::Index(1)),
+            toc: true,
+            ..Config::default()
+        };
 
         csv_to_markdown_streaming(input, &mut output, config).unwrap();
 
         let result = String::from_utf8(output).unwrap();
-        let expected = "| Name | Age |\n| --- | --- |\n| John | 25 |\n| Jane | 30 |\n";
+        let toc = "- [My Group 1](#my-group-1)\n- [Ops & Admin](#ops--admin)\n- [my group 1!](#my-group-1-1)\n\n";
+        assert!(result.starts_with(toc));
+        assert!(result.contains("\n## My Group 1\n"));
+        assert!(result.contains("\n## my group 1!\n"));
+    }
+
+    #[test]
+    fn test_group_by_formats_cells_like_a_whole_table() {
+        let input = Cursor::new("Name,Amount,Done,Team\nJohn,1234567,yes,Red\nJane,89,no,Blue");
+        let config = Config {
+            group_by: Some(ColumnRef::Name("Team".to_string())),
+            thousands_separator: Some(','),
+            boolean_columns: vec![2],
+            boolean_as_checkbox: true,
+            bold_first_column: true,
+            footnote_over: Some(6),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        // Footnotes are numbered across the groups, and written at the end
+        let expected = "## Red\n\n| Name | Amount | Done | Team |\n| --- | --- | --- | --- |\n\
+                        | **John** | [^1] | [x] | Red |\n\n\
+                        ## Blue\n\n| Name | Amount | Done | Team |\n| --- | --- | --- | --- |\n\
+                        | **Jane** | 89 | [ ] | Blue |\n\n[^1]: 1,234,567\n";
         assert_eq!(result, expected);
     }
 
     #[test]
-    fn test_streaming_mode_no_headers() {
-        let csv_data = "Data1,Data2\nValue1,Value2";
-        let input = Cursor::new(csv_data);
-        let mut output = Vec::new();
+    fn test_group_by_unknown_column() {
+        let input = Cursor::new("Name,Team\nJohn,Red");
         let config = Config {
-            has_headers: false,
+            group_by: Some(ColumnRef::Name("Missing".to_string())),
             ..Config::default()
         };
+        let result = csv_to_markdown(input, config);
 
-        csv_to_markdown_streaming(input, &mut output, config).unwrap();
+        assert!(matches!(result, Err(CsvMdError::ColumnNotFound(name)) if name == "Missing"));
 
-        let result = String::from_utf8(output).unwrap();
-        let expected = "| Data1 | Data2 |\n| Value1 | Value2 |\n";
+        let input = Cursor::new("Name,Team\nJohn,Red");
+        let config = Config {
+            group_by: Some(ColumnRef::Index(2)),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config);
+
+        assert!(matches!(result, Err(CsvMdError::ColumnNotFound(index)) if index == "2"));
+    }
+
+    #[test]
+    fn test_front_matter() {
+        let input = Cursor::new("Name,Age\nJohn,25\nJane,30");
+        let config = Config {
+            front_matter: Some(BTreeMap::from([(
+                "title".to_string(),
+                "My \"Data\": 2024".to_string(),
+            )])),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected = "---\ntitle: \"My \\\"Data\\\": 2024\"\ncolumns: 2\nrows: 2\n---\n\n| Name | Age |\n| --- | --- |\n| John | 25 |\n| Jane | 30 |\n";
         assert_eq!(result, expected);
+
+        let block = result.split("---\n").nth(1).unwrap();
+        let yaml: BTreeMap<String, serde_yaml::Value> = serde_yaml::from_str(block).unwrap();
+        assert_eq!(yaml["title"], "My \"Data\": 2024");
+        assert_eq!(yaml["columns"], 2);
+        assert_eq!(yaml["rows"], 2);
     }
 
     #[test]
-    fn test_streaming_mode_uneven_columns() {
-        // This test exposes the current streaming bug: early rows are malformed
-        // when later rows have more columns
-        let csv_data = "A,B\nX,Y,Z\nP,Q,R,S";
-        let input = Cursor::new(csv_data);
+    fn test_front_matter_keeps_explicit_counts() {
+        let input = Cursor::new("Name\nJohn");
         let mut output = Vec::new();
-        let config = Config::default();
+        let config = Config {
+            front_matter: Some(BTreeMap::from([
+                ("rows".to_string(), "many".to_string()),
+                ("source file".to_string(), "a\\b.csv".to_string()),
+            ])),
+            ..Config::default()
+        };
 
         csv_to_markdown_streaming(input, &mut output, config).unwrap();
 
         let result = String::from_utf8(output).unwrap();
+        let block = result.split("---\n").nth(1).unwrap();
+        let yaml: BTreeMap<String, serde_yaml::Value> = serde_yaml::from_str(block).unwrap();
+        assert_eq!(yaml["rows"], "many");
+        assert_eq!(yaml["columns"], 1);
+        assert_eq!(yaml["source file"], "a\\b.csv");
+        assert!(result.ends_with("---\n\n| Name |\n| --- |\n| John |\n"));
+    }
 
-        // Expected: all rows should have 4 columns (max from any row)
-        let expected =
-            "| A | B |  |  |\n| --- | --- | --- | --- |\n| X | Y | Z |  |\n| P | Q | R | S |\n";
+    #[test]
+    fn test_zero_width_records_produce_no_output() {
+        // Selecting no columns leaves every record without any cells
+        let config = Config {
+            columns: Some(Vec::new()),
+            ..Config::default()
+        };
+        let result = csv_to_markdown(Cursor::new("A,B\n1,2"), config.clone()).unwrap();
+        assert_eq!(result, "");
 
-        // Fixed: streaming now uses two-pass approach to determine max_cols correctly
-        assert_eq!(result, expected);
+        let mut output = Vec::new();
+        csv_to_markdown_streaming(Cursor::new("A,B\n1,2"), &mut output, config.clone()).unwrap();
+        assert!(output.is_empty());
+
+        let mut output = Vec::new();
+        csv_to_markdown_streaming_seekable(Cursor::new("A,B\n1,2"), &mut output, config).unwrap();
+        assert!(output.is_empty());
     }
 
     #[test]
-    fn test_header_alignment_left() {
-        let csv_data = "Name,Age\nJohn,25\nJane,30";
-        let input = Cursor::new(csv_data);
+    fn test_max_newlines_per_cell() {
+        let cell = vec!["line"; 1001].join("\n");
+        let input = Cursor::new(format!("Text\n\"{}\"", cell));
         let config = Config {
-            header_alignment: HeaderAlignment::Left,
+            max_newlines_per_cell: Some(10),
             ..Config::default()
         };
         let result = csv_to_markdown(input, config).unwrap();
 
-        let expected = "| Name | Age |\n| --- | --- |\n| John | 25 |\n| Jane | 30 |\n";
-        assert_eq!(result, expected);
+        let data_row = result.lines().nth(2).unwrap();
+        assert_eq!(data_row.matches("<br>").count(), 10);
+        assert!(data_row.starts_with("| line<br>line<br>"));
+        assert!(data_row.ends_with("line line line |"));
+        assert_eq!(data_row.matches("line").count(), 1001);
     }
 
     #[test]
-    fn test_header_alignment_center() {
-        let csv_data = "Name,Age\nJohn,25\nJane,30";
-        let input = Cursor::new(csv_data);
+    fn test_max_newlines_per_cell_under_limit() {
+        let input = Cursor::new("Text\n\"a\r\nb\"");
         let config = Config {
-            header_alignment: HeaderAlignment::Center,
+            max_newlines_per_cell: Some(1),
             ..Config::default()
         };
         let result = csv_to_markdown(input, config).unwrap();
 
-        let expected = "| Name | Age |\n| :---: | :---: |\n| John | 25 |\n| Jane | 30 |\n";
+        assert_eq!(result, "| Text |\n| --- |\n| a<br>b |\n");
+    }
+
+    #[test]
+    fn test_control_char_policy() {
+        let csv_data = "Text\nring\x07 \x1b[31mred\tend";
+        let cases = [
+            (ControlCharPolicy::Keep, "ring\x07 \x1b[31mred\tend"),
+            (ControlCharPolicy::Strip, "ring [31mredend"),
+            (
+                ControlCharPolicy::Escape,
+                "ring\\u0007 \\u001B[31mred\\u0009end",
+            ),
+        ];
+
+        for (policy, cell) in cases {
+            let input = Cursor::new(csv_data);
+            let config = Config {
+                control_char_policy: policy,
+                ..Config::default()
+            };
+            let result = csv_to_markdown(input, config).unwrap();
+
+            assert_eq!(result, format!("| Text |\n| --- |\n| {} |\n", cell));
+        }
+    }
+
+    #[test]
+    fn test_sanitize_formulas() {
+        let input = Cursor::new("Formula,Value\n=SUM(A1),+1\n@cmd,-2\nplain,a=b");
+        let config = Config {
+            sanitize_formulas: true,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        let expected = "| Formula | Value |\n| --- | --- |\n| '=SUM(A1) | '+1 |\n| '@cmd | '-2 |\n| plain | a=b |\n";
         assert_eq!(result, expected);
     }
 
     #[test]
-    fn test_header_alignment_right() {
-        let csv_data = "Name,Age\nJohn,25\nJane,30";
+    fn test_formulas_kept_by_default() {
+        let input = Cursor::new("Formula\n=SUM(A1)");
+        let result = csv_to_markdown(input, Config::default()).unwrap();
+
+        assert_eq!(result, "| Formula |\n| --- |\n| =SUM(A1) |\n");
+    }
+
+    #[test]
+    fn test_control_char_policy_keeps_newlines() {
+        let input = Cursor::new("Text\n\"a\r\nb\x00\"");
+        let config = Config {
+            control_char_policy: ControlCharPolicy::Strip,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        assert_eq!(result, "| Text |\n| --- |\n| a<br>b |\n");
+    }
+
+    #[test]
+    fn test_multiple_header_rows_are_merged() {
+        let csv_data = "Sales,,Costs\nQ1,Q2,Q1\n10,20,5";
         let input = Cursor::new(csv_data);
         let config = Config {
-            header_alignment: HeaderAlignment::Right,
+            header_rows: 2,
             ..Config::default()
         };
         let result = csv_to_markdown(input, config).unwrap();
 
-        let expected = "| Name | Age |\n| ---: | ---: |\n| John | 25 |\n| Jane | 30 |\n";
+        let expected = "| Sales / Q1 | Q2 | Costs / Q1 |\n| --- | --- | --- |\n| 10 | 20 | 5 |\n";
         assert_eq!(result, expected);
     }
 
     #[test]
-    fn test_streaming_header_alignment_center() {
-        let csv_data = "Name,Age\nJohn,25\nJane,30";
+    fn test_multiple_header_rows_select_and_stream() {
+        let csv_data = "Sales,,Costs\nQ1,Q2,Q1\n10,20,5";
         let input = Cursor::new(csv_data);
         let mut output = Vec::new();
         let config = Config {
-            header_alignment: HeaderAlignment::Center,
+            header_rows: 2,
+            header_row_separator: ": ".to_string(),
+            columns: Some(vec!["Costs: Q1".to_string()]),
             ..Config::default()
         };
 
         csv_to_markdown_streaming(input, &mut output, config).unwrap();
 
         let result = String::from_utf8(output).unwrap();
-        let expected = "| Name | Age |\n| :---: | :---: |\n| John | 25 |\n| Jane | 30 |\n";
-        assert_eq!(result, expected);
+        assert_eq!(result, "| Costs: Q1 |\n| --- |\n| 5 |\n");
     }
 
     #[test]
-    fn test_header_alignment_no_headers() {
-        let csv_data = "Data1,Data2\nValue1,Value2";
+    fn test_header_rows_longer_than_input() {
+        let input = Cursor::new("Sales,Costs");
+        let config = Config {
+            header_rows: 3,
+            ..Config::default()
+        };
+        let result = csv_to_markdown(input, config).unwrap();
+
+        assert_eq!(result, "| Sales | Costs |\n| --- | --- |\n");
+    }
+
+    #[test]
+    fn test_converter_reuses_buffers() {
+        let mut converter = Converter::new(Config::default());
+
+        let first = converter.convert(Cursor::new("Name,Age\nJohn,25")).unwrap();
+        assert_eq!(first, "| Name | Age |\n| --- | --- |\n| John | 25 |\n");
+
+        // A shorter second conversion doesn't include anything from the first
+        let second = converter.convert(Cursor::new("A\n1")).unwrap();
+        assert_eq!(second, "| A |\n| --- |\n| 1 |\n");
+
+        let empty = converter.convert(Cursor::new("")).unwrap();
+        assert_eq!(empty, "");
+    }
+
+    #[test]
+    fn test_long_cells_become_footnotes() {
+        let csv_data =
+            "Name,Description\nJohn,A very long description\nJane,Short\nJim,Another long one";
         let input = Cursor::new(csv_data);
         let config = Config {
-            has_headers: false,
-            header_alignment: HeaderAlignment::Center, // Should be ignored
+            footnote_over: Some(10),
             ..Config::default()
         };
         let result = csv_to_markdown(input, config).unwrap();
 
-        // Should not have separator line when no headers, regardless of alignment
-        let expected = "| Data1 | Data2 |\n| Value1 | Value2 |\n";
+        let expected = "| Name | Description |\n| --- | --- |\n| John | [^1] |\n| Jane | Short |\n| Jim | [^2] |\n\n[^1]: A very long description\n[^2]: Another long one\n";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_footnotes_when_streaming() {
+        let csv_data = "Long header name\nlong value one\nlong value two";
+        let input = Cursor::new(csv_data);
+        let mut output = Vec::new();
+        let config = Config {
+            footnote_over: Some(5),
+            ..Config::default()
+        };
+
+        csv_to_markdown_streaming(input, &mut output, config).unwrap();
+
+        // Header cells are never moved into footnotes
+        let result = String::from_utf8(output).unwrap();
+        let expected = "| Long header name |\n| --- |\n| [^1] |\n| [^2] |\n\n[^1]: long value one\n[^2]: long value two\n";
         assert_eq!(result, expected);
     }
 }
@@ -1,75 +1,35 @@
 //! CSV to Markdown table converter CLI tool.
 
-use clap::{Parser, ValueEnum};
-use csvmd::error::Result;
-use csvmd::{csv_to_markdown_streaming, Config, HeaderAlignment};
-use std::fs::File;
-use std::io::{self, IsTerminal, Read};
-use std::path::PathBuf;
+mod benchmark;
+
+use csvmd::cli::Args;
+use csvmd::error::{CsvMdError, Result};
+use csvmd::formats::OutputFormat;
+use csvmd::{csv_to_markdown_streaming, Config};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{self, Receiver, TryRecvError};
 use std::thread;
 use std::time::{Duration, Instant};
-
-#[derive(Clone, Copy, Debug, ValueEnum)]
-enum ClapAlignment {
-    /// Left-aligned headers
-    Left,
-    /// Center-aligned headers
-    Center,
-    /// Center-aligned headers (British spelling)
-    Centre,
-    /// Right-aligned headers
-    Right,
-}
-
-impl From<ClapAlignment> for HeaderAlignment {
-    fn from(clap_align: ClapAlignment) -> Self {
-        match clap_align {
-            ClapAlignment::Left => HeaderAlignment::Left,
-            ClapAlignment::Center | ClapAlignment::Centre => HeaderAlignment::Center,
-            ClapAlignment::Right => HeaderAlignment::Right,
-        }
-    }
-}
-
-#[derive(Parser)]
-#[command(name = "csvmd")]
-#[command(about = "Convert a CSV to a Markdown table, outputted to stdout")]
-#[command(version)]
-struct Args {
-    /// The path of the file to read - if not provided, reads from stdin
-    file: Option<PathBuf>,
-
-    /// The delimiter between columns
-    #[arg(short, long, default_value = ",")]
-    delimiter: char,
-
-    /// Treat the first row as data, not headers
-    #[arg(long)]
-    no_headers: bool,
-
-    /// Enable streaming mode to improve performance for large inputs
-    #[arg(long)]
-    stream: bool,
-
-    /// Customise the alignment of values within cells
-    #[arg(long, default_value = "left")]
-    align: ClapAlignment,
-}
+use tempfile::NamedTempFile;
 
 /// A wrapper around stdin that shows a spinner after a timeout if it's interactive
 struct InteractiveStdin {
     buffer: Vec<u8>,
     position: usize,
     initialized: bool,
+    /// Never show the spinner, for `--plain`
+    plain: bool,
 }
 
 impl InteractiveStdin {
-    fn new() -> Self {
+    fn new(plain: bool) -> Self {
         Self {
             buffer: Vec::new(),
             position: 0,
             initialized: false,
+            plain,
         }
     }
 
@@ -81,7 +41,7 @@ impl InteractiveStdin {
         self.initialized = true;
 
         // Check if stdin is interactive (TTY)
-        if !std::io::stdin().is_terminal() {
+        if self.plain || !std::io::stdin().is_terminal() {
             // Not interactive, read all input immediately
             io::stdin().read_to_end(&mut self.buffer)?;
             return Ok(());
@@ -123,10 +83,7 @@ impl InteractiveStdin {
                 self.show_spinner_and_wait(rx)?;
             }
             Err(TryRecvError::Disconnected) => {
-                return Err(io::Error::new(
-                    io::ErrorKind::BrokenPipe,
-                    "Input thread disconnected",
-                ));
+                return Err(io::Error::other("Input thread disconnected"));
             }
         }
 
@@ -165,10 +122,7 @@ impl InteractiveStdin {
                 }
                 Err(TryRecvError::Disconnected) => {
                     eprint!("\r{}\r", " ".repeat(85));
-                    return Err(io::Error::new(
-                        io::ErrorKind::BrokenPipe,
-                        "Input thread disconnected",
-                    ));
+                    return Err(io::Error::other("Input thread disconnected"));
                 }
             }
         }
@@ -193,39 +147,666 @@ impl Read for InteractiveStdin {
 }
 
 fn main() -> Result<()> {
-    let args = Args::parse();
+    match run() {
+        // The reader closed the pipe early, like `csvmd data.csv | head`, so
+        // there's nowhere left to write to and nothing went wrong
+        Err(CsvMdError::Io(err)) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        // Written out in full, so the hint for what to do instead is shown
+        Err(err @ CsvMdError::TooManyRowsForTerminal { .. }) => {
+            eprintln!("Error: {}", err);
+            std::process::exit(1);
+        }
+        result => result,
+    }
+}
+
+fn run() -> Result<()> {
+    let args = Args::parse_with_config_file()?;
+
+    if args.self_benchmark {
+        return benchmark::run();
+    }
+
+    if args.files.len() > 1 && !args.separate_tables {
+        return Err(CsvMdError::InvalidConfig(
+            "converting more than one file requires `--separate-tables`".to_string(),
+        ));
+    }
+
+    let config = Config::from_args(&args);
 
+    if args.pretty_print_config {
+        eprintln!("{:#?}", config);
+        return Ok(());
+    }
+
+    let start = Instant::now();
+
+    #[cfg(feature = "clipboard")]
+    if args.clipboard {
+        let mut output = Vec::new();
+        convert(&args, config, &mut output)?;
+        copy_to_clipboard(String::from_utf8_lossy(&output).into_owned())?;
+        eprintln!("Copied the Markdown table to the clipboard");
+    } else {
+        write_output(&args, config)?;
+    }
+
+    #[cfg(not(feature = "clipboard"))]
+    write_output(&args, config)?;
+
+    if args.measure {
+        report_measurements(start.elapsed());
+    }
+
+    Ok(())
+}
+
+/// Convert the inputs and write the results to the `--output` file or stdout,
+/// failing if there are more rows than `--tty-row-limit` for a terminal.
+fn write_output(args: &Args, config: Config) -> Result<()> {
+    let mut output = Output::open(args)?;
+    if !limits_terminal_rows(args) {
+        convert(args, config, &mut output)?;
+        return output.finish();
+    }
+
+    // Held back until every row has been read, so nothing is written if there
+    // are too many, but the conversion stops as soon as there are
     let config = Config {
-        has_headers: !args.no_headers,
-        flexible: true,
-        delimiter: args.delimiter as u8,
-        header_alignment: args.align.into(),
+        tty_row_limit: Some(args.tty_row_limit),
+        ..config
     };
+    let too_many = || CsvMdError::TooManyRowsForTerminal {
+        limit: args.tty_row_limit,
+    };
+    let mut converted = Vec::new();
+    match convert(args, config, &mut converted) {
+        Ok(rows) if rows > args.tty_row_limit => return Err(too_many()),
+        Ok(_) => {}
+        // Later tables are only given what's left of the limit, and tables
+        // converted in parallel fail naming their file, so the error is
+        // given again for the whole limit
+        Err(CsvMdError::TooManyRowsForTerminal { .. }) => return Err(too_many()),
+        Err(CsvMdError::InFile { source, .. })
+            if matches!(*source, CsvMdError::TooManyRowsForTerminal { .. }) =>
+        {
+            return Err(too_many())
+        }
+        Err(err) => return Err(err),
+    }
+    output.write_all(&converted)?;
+    output.finish()
+}
+
+/// Whether the output is going to a terminal and `--tty-row-limit` applies,
+/// which it never does with `--plain`.
+fn limits_terminal_rows(args: &Args) -> bool {
+    args.output.is_none() && !args.force && !args.plain && io::stdout().is_terminal()
+}
+
+/// Where the output is written: stdout, or the `--output` file, which is
+/// left as it was unless the conversion succeeds or writes something.
+enum Output {
+    Stdout(io::Stdout),
+    /// A temporary file next to `path`, which replaces it once finished.
+    Replace {
+        file: BufWriter<NamedTempFile>,
+        path: PathBuf,
+    },
+    /// The file at `path` to add to, opened once there's output for it.
+    Append {
+        file: Option<BufWriter<File>>,
+        path: PathBuf,
+    },
+}
+
+impl Output {
+    /// Open the `--output` file selected by `args`, or stdout if there isn't
+    /// one, refusing to write over one of the inputs.
+    fn open(args: &Args) -> Result<Self> {
+        let Some(path) = &args.output else {
+            return Ok(Output::Stdout(io::stdout()));
+        };
+        check_output_isnt_input(args, path)?;
+
+        // Replace the file a symlink points to, rather than the symlink
+        let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+        if args.append {
+            return Ok(Output::Append { file: None, path });
+        }
+
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let mut builder = tempfile::Builder::new();
+        builder.prefix(".csvmd");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            builder.permissions(std::fs::Permissions::from_mode(0o666));
+        }
+        let file = builder.tempfile_in(dir)?;
+        if let Ok(metadata) = std::fs::metadata(&path) {
+            file.as_file().set_permissions(metadata.permissions())?;
+        }
+
+        Ok(Output::Replace {
+            file: BufWriter::new(file),
+            path,
+        })
+    }
+
+    /// Finish writing, replacing the `--output` file with what was written.
+    fn finish(self) -> Result<()> {
+        match self {
+            Output::Stdout(mut stdout) => stdout.flush()?,
+            Output::Replace { file, path } => {
+                let file = file.into_inner().map_err(|err| err.into_error())?;
+                file.persist(path).map_err(|err| err.error)?;
+            }
+            Output::Append { file, .. } => {
+                if let Some(mut file) = file {
+                    file.flush()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Stdout(stdout) => stdout.write(buf),
+            Output::Replace { file, .. } => file.write(buf),
+            Output::Append { file, path } => {
+                if buf.is_empty() {
+                    return Ok(0);
+                }
+                let file = match file {
+                    Some(file) => file,
+                    None => {
+                        let mut opened =
+                            OpenOptions::new().create(true).append(true).open(&*path)?;
+                        // Leave a blank line between the existing content and the new table
+                        if opened.metadata()?.len() > 0 {
+                            writeln!(opened)?;
+                        }
+                        file.insert(BufWriter::new(opened))
+                    }
+                };
+                file.write(buf)
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Stdout(stdout) => stdout.flush(),
+            Output::Replace { file, .. } => file.flush(),
+            Output::Append { file, .. } => file.as_mut().map_or(Ok(()), Write::flush),
+        }
+    }
+}
+
+/// Fail if the `--output` file at `path` is also one of the inputs, which
+/// would be overwritten or added to while it's being read.
+fn check_output_isnt_input(args: &Args, path: &Path) -> Result<()> {
+    let Ok(output) = std::fs::canonicalize(path) else {
+        return Ok(());
+    };
+    let inputs = args
+        .input_files()
+        .into_iter()
+        .flatten()
+        .chain(args.diff.iter().flatten().map(PathBuf::as_path));
+    for input in inputs {
+        if std::fs::canonicalize(input).is_ok_and(|input| input == output) {
+            return Err(CsvMdError::InvalidConfig(format!(
+                "the `--output` file {} is also an input, so it can't be written to",
+                path.display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Convert the inputs selected by `args` and write the results to `output`,
+/// returning the number of data rows written.
+fn convert<W: Write>(args: &Args, config: Config, mut output: W) -> Result<usize> {
+    if let Some([old, new]) = args.diff.as_deref() {
+        let (markdown, stats) = csvmd::diff_to_markdown_with_stats(
+            open_input(old)?,
+            open_input(new)?,
+            config,
+            args.diff_key,
+        )?;
+        output.write_all(markdown.as_bytes())?;
+        output.flush()?;
+        return Ok(stats.rows);
+    }
+
+    let format = output_format(args);
+    if args.stream && !matches!(format, OutputFormat::Markdown | OutputFormat::OrgMode) {
+        return Err(CsvMdError::InvalidConfig(
+            "`--stream` can only write Markdown and Org-mode tables".to_string(),
+        ));
+    }
+    let files = args.input_files();
+
+    #[cfg(feature = "rayon")]
+    if let Some(jobs) = args.jobs.filter(|_| files.len() > 1) {
+        let tables = convert_in_parallel(args, &files, &config, format, jobs.get())?;
+        let mut rows = 0;
+        for (table, table_rows) in tables {
+            output.write_all(&table)?;
+            rows += table_rows;
+        }
+        output.flush()?;
+        return Ok(rows);
+    }
+
+    let mut rows = 0;
+    for (i, path) in files.into_iter().enumerate() {
+        // The limit is for every table together
+        let config = Config {
+            tty_row_limit: config.tty_row_limit.map(|limit| limit.saturating_sub(rows)),
+            ..config.clone()
+        };
+        rows += convert_table(args, i, path, &config, format, &mut output)?;
+    }
 
+    Ok(rows)
+}
+
+/// Convert the `index`th input, from the file at `path` or from stdin, and
+/// write the result to `output`, under a heading when using
+/// `--separate-tables`, returning the number of data rows written.
+fn convert_table<W: Write>(
+    args: &Args,
+    index: usize,
+    path: Option<&Path>,
+    config: &Config,
+    format: OutputFormat,
+    mut output: W,
+) -> Result<usize> {
+    if args.separate_tables {
+        if index > 0 {
+            writeln!(output)?;
+        }
+        writeln!(output, "## {}\n", table_heading(path))?;
+    }
+    let source_name = path
+        .and_then(Path::file_name)
+        .map(|name| name.to_string_lossy().into_owned());
+    let input_config = Config {
+        source_name,
+        ..config.clone()
+    };
+    if let Some(path) = path.filter(|_| args.explain) {
+        explain_header(&table_heading(Some(path)), open_input(path)?, config)?;
+    }
+    let rows = convert_input(args, path, input_config, format, &mut output)?;
+
+    if let Some(path) = path {
+        if args.suggest_delimiter || (!args.plain && io::stderr().is_terminal()) {
+            warn_about_delimiter(path, config)?;
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Convert `files` on up to `jobs` threads, returning the output for each in
+/// the same order as `files`, along with its number of data rows.
+///
+/// If any files fail, the error is from the first of them, naming the file.
+#[cfg(feature = "rayon")]
+fn convert_in_parallel(
+    args: &Args,
+    files: &[Option<&Path>],
+    config: &Config,
+    format: OutputFormat,
+    jobs: usize,
+) -> Result<Vec<(Vec<u8>, usize)>> {
+    use rayon::prelude::*;
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build()
+        .map_err(io::Error::other)?;
+
+    let tables: Vec<Result<(Vec<u8>, usize)>> = pool.install(|| {
+        files
+            .par_iter()
+            .enumerate()
+            .map(|(i, &path)| {
+                let mut table = Vec::new();
+                let rows =
+                    convert_table(args, i, path, config, format, &mut table).map_err(|err| {
+                        CsvMdError::InFile {
+                            file: table_heading(path),
+                            source: Box::new(err),
+                        }
+                    })?;
+                Ok((table, rows))
+            })
+            .collect()
+    });
+
+    tables.into_iter().collect()
+}
+
+/// The format to write, from `--format` or else the `--output` file's
+/// extension, falling back to Markdown.
+fn output_format(args: &Args) -> OutputFormat {
+    if let Some(format) = args.format {
+        return format.into();
+    }
+
+    let extension = args
+        .output
+        .as_deref()
+        .and_then(Path::extension)
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+
+    match extension.as_deref() {
+        Some("org") => OutputFormat::OrgMode,
+        Some("rst") => OutputFormat::Rst,
+        Some("html" | "htm") => OutputFormat::Html,
+        Some("json") => OutputFormat::Json,
+        Some("jsonl" | "ndjson") => OutputFormat::JsonLines,
+        Some("mmd" | "mermaid") => OutputFormat::Mermaid,
+        _ => OutputFormat::Markdown,
+    }
+}
+
+/// Print a warning to stderr if the file at `path` looks like it uses a
+/// different delimiter to the one configured.
+///
+/// The file is read again, so anything other than a regular file, like a
+/// named pipe which has already been read to the end, is skipped.
+fn warn_about_delimiter(path: &Path, config: &Config) -> Result<()> {
+    const SAMPLE_BYTES: u64 = 64 * 1024;
+
+    if !path.metadata().is_ok_and(|metadata| metadata.is_file()) {
+        return Ok(());
+    }
+
+    let mut sample = Vec::new();
+    File::open(path)?
+        .take(SAMPLE_BYTES)
+        .read_to_end(&mut sample)?;
+
+    // Leave out a line cut off by the end of the sample
+    if sample.len() as u64 == SAMPLE_BYTES {
+        if let Some(end) = sample.iter().rposition(|&byte| byte == b'\n') {
+            sample.truncate(end + 1);
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    if csvmd::compression::Compression::detect(&sample).is_some() {
+        return Ok(());
+    }
+
+    if let Some(delimiter) = csvmd::suggest_delimiter(&sample, config) {
+        let name = match delimiter {
+            b';' => "semicolon",
+            b'\t' => "tab",
+            b'|' => "pipe",
+            _ => "comma",
+        };
+        eprintln!(
+            "csvmd: the table only has one column, but every line of {} contains {:?}. Try `--delimiter {}`.",
+            path.display(),
+            delimiter as char,
+            name
+        );
+    }
+
+    Ok(())
+}
+
+/// The heading for the table converted from `path`, when using `--separate-tables`.
+fn table_heading(path: Option<&Path>) -> String {
+    match path {
+        Some(path) => path
+            .file_name()
+            .unwrap_or(path.as_os_str())
+            .to_string_lossy()
+            .into_owned(),
+        None => "stdin".to_string(),
+    }
+}
+
+/// Convert a single input, from the file at `path` or from stdin, and write
+/// the result to `output`, returning the number of data rows written.
+///
+/// Rows aren't counted when streaming, so this returns 0 then.
+fn convert_input<W: Write>(
+    args: &Args,
+    path: Option<&Path>,
+    config: Config,
+    format: OutputFormat,
+    mut output: W,
+) -> Result<usize> {
     if args.stream {
-        // Streaming mode
-        match args.file {
+        // Streaming mode, where Org-mode tables are written by the Markdown renderer
+        let config = Config {
+            org_mode: config.org_mode || format == OutputFormat::OrgMode,
+            ..config
+        };
+        match path {
             // For files, use seekable streaming to avoid buffering the entire input
             Some(path) => {
                 let file = File::open(path)?;
-                csvmd::csv_to_markdown_streaming_seekable(file, io::stdout(), config)?;
+
+                // Compressed files can't be rewound, so they're buffered instead
+                #[cfg(feature = "compression")]
+                if is_compressed(&file)? {
+                    let input = csvmd::compression::decompress(file)?;
+                    csv_to_markdown_streaming(input, output, config)?;
+                    return Ok(0);
+                }
+
+                csvmd::csv_to_markdown_streaming_seekable(file, output, config)?;
             }
             // For stdin or non-seekable, fall back to buffered streaming
             None => {
-                let input: Box<dyn Read> = Box::new(InteractiveStdin::new());
-                csv_to_markdown_streaming(input, io::stdout(), config)?;
+                let input = open_stdin(args, &config)?;
+                csv_to_markdown_streaming(input, output, config)?;
             }
         }
+        Ok(0)
     } else {
         // Standard mode: load all into memory then output
-        let input: Box<dyn Read> = match args.file {
-            Some(path) => Box::new(File::open(path)?),
-            None => Box::new(InteractiveStdin::new()),
+        let input: Box<dyn Read> = match path {
+            Some(path) => open_input(path)?,
+            None => open_stdin(args, &config)?,
         };
 
-        let output = csvmd::csv_to_markdown(input, config)?;
-        print!("{}", output);
+        let (converted, stats) = csvmd::formats::convert_to_with_stats(input, config, format)?;
+        output.write_all(converted.as_bytes())?;
+        output.flush()?;
+        Ok(stats.rows)
+    }
+}
+
+/// Open stdin to read, first explaining whether it has a header with `--explain`.
+fn open_stdin(args: &Args, config: &Config) -> Result<Box<dyn Read>> {
+    let mut stdin = InteractiveStdin::new(args.plain);
+    if !args.explain {
+        return Ok(Box::new(stdin));
     }
 
+    // Put the sample back in front of the rest of stdin once it's been read
+    let mut sample = Vec::new();
+    (&mut stdin)
+        .take(config.sniffed_bytes())
+        .read_to_end(&mut sample)?;
+    explain_header("stdin", sample.as_slice(), config)?;
+    Ok(Box::new(io::Cursor::new(sample).chain(stdin)))
+}
+
+/// Print to stderr whether the first row of `input`, named `name`, is taken
+/// as a header with `--detect-header`, deciding from the same prefix of it
+/// as the conversion does.
+fn explain_header<R: Read>(name: &str, input: R, config: &Config) -> Result<()> {
+    let mut prefix = Vec::new();
+    input
+        .take(config.sniffed_bytes())
+        .read_to_end(&mut prefix)?;
+    if csvmd::sniff_header(&prefix, config) {
+        eprintln!(
+            "csvmd: treating the first row of {} as a header, since its cells are all different and none are empty or numbers",
+            name
+        );
+    } else {
+        eprintln!(
+            "csvmd: treating the first row of {} as data, since some of its cells are empty, numbers or repeated",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Open the file at `path` to read, decompressing it if it's compressed.
+fn open_input(path: &Path) -> Result<Box<dyn Read>> {
+    #[cfg(feature = "compression")]
+    return Ok(csvmd::compression::decompress(File::open(path)?)?);
+
+    #[cfg(not(feature = "compression"))]
+    Ok(Box::new(File::open(path)?))
+}
+
+/// Check whether a file starts with the magic bytes of a compressed format,
+/// rewinding it afterwards.
+#[cfg(feature = "compression")]
+fn is_compressed(mut file: &File) -> Result<bool> {
+    use std::io::{Seek, SeekFrom};
+
+    let mut prefix = Vec::with_capacity(4);
+    file.take(4).read_to_end(&mut prefix)?;
+    file.seek(SeekFrom::Start(0))?;
+
+    Ok(csvmd::compression::Compression::detect(&prefix).is_some())
+}
+
+/// Copy text to the system clipboard.
+#[cfg(feature = "clipboard")]
+fn copy_to_clipboard(text: String) -> Result<()> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|err| io::Error::other(format!("Failed to copy to the clipboard: {}", err)))?;
     Ok(())
 }
+
+#[cfg(all(test, feature = "rayon"))]
+mod parallel_tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_convert_with_jobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("a.csv");
+        std::fs::write(&first, "A\n1").unwrap();
+        let second = dir.path().join("b.csv");
+        std::fs::write(&second, "B\n2\n3").unwrap();
+        let third = dir.path().join("c.csv");
+        std::fs::write(&third, "C\n4").unwrap();
+
+        let args = Args::parse_from([
+            "csvmd".as_ref(),
+            "--separate-tables".as_ref(),
+            "--jobs".as_ref(),
+            "2".as_ref(),
+            first.as_os_str(),
+            second.as_os_str(),
+            third.as_os_str(),
+        ]);
+        let mut output = Vec::new();
+        convert(&args, Config::from_args(&args), &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "## a.csv\n\n| A |\n| --- |\n| 1 |\n\n\
+             ## b.csv\n\n| B |\n| --- |\n| 2 |\n| 3 |\n\n\
+             ## c.csv\n\n| C |\n| --- |\n| 4 |\n"
+        );
+    }
+
+    #[test]
+    fn test_convert_with_jobs_names_failing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let good = dir.path().join("good.csv");
+        std::fs::write(&good, "A\n1").unwrap();
+        let missing = dir.path().join("missing.csv");
+
+        let args = Args::parse_from([
+            "csvmd".as_ref(),
+            "--separate-tables".as_ref(),
+            "--jobs".as_ref(),
+            "2".as_ref(),
+            good.as_os_str(),
+            missing.as_os_str(),
+        ]);
+        let err = convert(&args, Config::from_args(&args), Vec::new()).unwrap_err();
+
+        assert!(matches!(&err, CsvMdError::InFile { file, .. } if file == "missing.csv"));
+        assert!(err.to_string().starts_with("missing.csv: IO error"));
+    }
+}
+
+#[cfg(all(test, feature = "clipboard"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_to_clipboard() {
+        // Headless environments like CI have no clipboard to copy to
+        if cfg!(target_os = "linux")
+            && std::env::var_os("DISPLAY").is_none()
+            && std::env::var_os("WAYLAND_DISPLAY").is_none()
+        {
+            return;
+        }
+
+        copy_to_clipboard("| A |\n| --- |\n".to_string()).unwrap();
+        let copied = arboard::Clipboard::new().unwrap().get_text().unwrap();
+        assert_eq!(copied, "| A |\n| --- |\n");
+    }
+}
+
+/// Print a one-line summary of the time taken and, where available, peak memory use.
+fn report_measurements(elapsed: Duration) {
+    let elapsed_ms = elapsed.as_secs_f64() * 1000.0;
+
+    match peak_memory_kb() {
+        Some(kb) => eprintln!(
+            "csvmd: converted in {:.2}ms, peak memory {:.1}MB",
+            elapsed_ms,
+            kb as f64 / 1024.0
+        ),
+        None => eprintln!("csvmd: converted in {:.2}ms", elapsed_ms),
+    }
+}
+
+/// Read the peak resident set size of this process in kilobytes.
+#[cfg(target_os = "linux")]
+fn peak_memory_kb() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+/// Peak memory isn't available on this platform.
+#[cfg(not(target_os = "linux"))]
+fn peak_memory_kb() -> Option<u64> {
+    None
+}
@@ -104,6 +104,84 @@ fn test_cli_help_flag() {
     insta::assert_snapshot!(normalized_result);
 }
 
+#[test]
+fn test_cli_with_named_delimiters() {
+    for (name, delimiter) in [("tab", '\t'), ("pipe", '|')] {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "Name{0}Age\nJohn{0}25", delimiter).unwrap();
+
+        let output = Command::new("cargo")
+            .args(["run", "--", "--delimiter", name])
+            .arg(temp_file.path())
+            .output()
+            .expect("Failed to execute command");
+
+        assert!(output.status.success());
+        let result = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(result, "| Name | Age |\n| --- | --- |\n| John | 25 |\n");
+    }
+}
+
+#[test]
+fn test_cli_with_sep_directive() {
+    let mut temp_file = NamedTempFile::new().unwrap();
+    write!(temp_file, "sep=;\r\nName;Age\r\nJohn;25\r\n").unwrap();
+
+    for (args, expected) in [
+        (vec![], "| Name | Age |\n| --- | --- |\n| John | 25 |\n"),
+        (
+            vec!["--stream"],
+            "| Name | Age |\n| --- | --- |\n| John | 25 |\n",
+        ),
+        // An explicit delimiter wins over the file's `sep=` line
+        (
+            vec!["--delimiter", ","],
+            "| sep=; |\n| --- |\n| Name;Age |\n| John;25 |\n",
+        ),
+    ] {
+        let output = Command::new("cargo")
+            .args(["run", "--"])
+            .args(args)
+            .arg(temp_file.path())
+            .output()
+            .expect("Failed to execute command");
+
+        assert!(output.status.success());
+        let result = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(result, expected);
+    }
+}
+
+#[test]
+fn test_cli_with_invalid_delimiter() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "--delimiter", "tabs"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("expected a single ASCII character"));
+}
+
+#[test]
+fn test_cli_with_multi_byte_delimiter() {
+    let mut temp_file = NamedTempFile::new().unwrap();
+    write!(temp_file, "Name、Age\nJohn、25").unwrap();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "--delimiter", "、"])
+        .arg(temp_file.path())
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("`、` takes 3 bytes"));
+    assert!(stderr.contains("only single-byte ASCII delimiters are supported"));
+}
+
 #[test]
 fn test_cli_nonexistent_file() {
     let output = Command::new("cargo")
@@ -409,6 +487,44 @@ fn test_cli_with_invalid_utf8_stdin() {
     assert!(stderr.contains("invalid utf-8"));
 }
 
+#[cfg(unix)]
+#[test]
+fn test_cli_exits_cleanly_when_output_pipe_closes() {
+    let mut temp_file = NamedTempFile::new().unwrap();
+    writeln!(temp_file, "ID,Name,Description").unwrap();
+    for i in 0..100_000 {
+        writeln!(
+            temp_file,
+            "{},Name {},A description long enough to fill the pipe",
+            i, i
+        )
+        .unwrap();
+    }
+
+    for mode in [None, Some("--stream")] {
+        let mut child = Command::new("cargo")
+            .args(["run", "--"])
+            .args(mode)
+            .arg(temp_file.path())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        // Read a little of the output, then close the pipe like `head` would
+        let mut stdout = child.stdout.take().unwrap();
+        let mut start = [0; 16];
+        std::io::Read::read_exact(&mut stdout, &mut start).unwrap();
+        drop(stdout);
+
+        let result = child.wait_with_output().unwrap();
+        let stderr = String::from_utf8(result.stderr).unwrap();
+        assert!(result.status.success(), "stderr: {}", stderr);
+        assert!(!stderr.contains("Error"));
+        assert_eq!(&start, b"| ID | Name | De");
+    }
+}
+
 #[test]
 fn test_cli_with_permission_denied_file() {
     // This test only works on Unix-like systems where we can control file permissions
@@ -492,3 +608,609 @@ fn test_cli_with_mixed_valid_and_invalid_utf8() {
     // Should provide location information
     assert!(stderr.contains("line") || stderr.contains("record"));
 }
+
+#[test]
+fn test_cli_with_measure() {
+    let mut temp_file = NamedTempFile::new().unwrap();
+    writeln!(temp_file, "Name,Age").unwrap();
+    writeln!(temp_file, "John,25").unwrap();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "--measure", temp_file.path().to_str().unwrap()])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let result = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(result, "| Name | Age |\n| --- | --- |\n| John | 25 |\n");
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let summary = stderr
+        .lines()
+        .find(|line| line.starts_with("csvmd: converted in"))
+        .expect("measurement summary should be printed");
+    if cfg!(target_os = "linux") {
+        assert!(summary.contains("peak memory"));
+    }
+}
+
+#[test]
+fn test_cli_self_benchmark() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "--self-benchmark"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let result = String::from_utf8(output.stdout).unwrap();
+    assert!(result.starts_with("| Rows | Input Size | Standard Mode | Streaming Mode |"));
+    // One row per benchmarked size, each with throughput for both modes
+    let runs: Vec<&str> = result.lines().skip(2).collect();
+    assert_eq!(runs.len(), 3);
+    assert!(runs.iter().all(|line| line.matches("MB/s").count() == 2));
+}
+
+#[test]
+fn test_cli_pretty_print_config() {
+    // No input is provided, so this would wait on stdin if it tried to read it
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--pretty-print-config",
+            "--delimiter",
+            ";",
+            "--align",
+            "right",
+            "/nonexistent/file.csv",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Config {"));
+    assert!(stderr.contains(&format!("delimiter: {},", b';')));
+    assert!(stderr.contains("header_alignment: Right,"));
+}
+
+#[test]
+fn test_cli_with_dash_reads_stdin() {
+    let csv_data = "Product,Price\nLaptop,$999";
+
+    for mode in [None, Some("--stream")] {
+        let mut child = Command::new("cargo")
+            .args(["run", "--"])
+            .args(mode)
+            .arg("-")
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(csv_data.as_bytes())
+            .unwrap();
+        let result = child.wait_with_output().unwrap();
+
+        assert!(result.status.success());
+        let stdout = String::from_utf8(result.stdout).unwrap();
+        assert_eq!(
+            stdout,
+            "| Product | Price |\n| --- | --- |\n| Laptop | $999 |\n"
+        );
+    }
+}
+
+#[test]
+fn test_cli_detect_header_explain() {
+    let mut temp_file = NamedTempFile::new().unwrap();
+    write!(temp_file, "info|200|started\nwarn|503|slow").unwrap();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "-d", "pipe", "--detect-header", "--explain"])
+        .arg(temp_file.path())
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "| info | 200 | started |\n| warn | 503 | slow |\n"
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("as data"), "{}", stderr);
+
+    // A first row too long to be read whole is judged by its start, both
+    // when converting and when explaining
+    let cells: Vec<String> = (0..2000).map(|i| format!("c{}x", i)).collect();
+    let mut temp_file = NamedTempFile::new().unwrap();
+    write!(temp_file, "{}|5\na|b", cells.join("|")).unwrap();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "-d", "pipe", "--detect-header", "--explain"])
+        .arg(temp_file.path())
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("| c0x | c1x |"), "{}", stdout);
+    assert!(stdout.lines().nth(1).unwrap().starts_with("| --- |"));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("as a header"), "{}", stderr);
+}
+
+#[test]
+fn test_cli_suggest_delimiter() {
+    let mut temp_file = NamedTempFile::new().unwrap();
+    write!(temp_file, "Name;Age\nJohn;25\nJane;30").unwrap();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "--suggest-delimiter"])
+        .arg(temp_file.path())
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Try `--delimiter semicolon`"), "{}", stderr);
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "--suggest-delimiter", "--delimiter", ";"])
+        .arg(temp_file.path())
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    assert!(!String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Try `--delimiter"));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_cli_suggest_delimiter_with_named_pipe() {
+    use std::time::{Duration, Instant};
+
+    let dir = tempfile::tempdir().unwrap();
+    let fifo = dir.path().join("input.csv");
+    let Ok(status) = Command::new("mkfifo").arg(&fifo).status() else {
+        return;
+    };
+    assert!(status.success());
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_csvmd"))
+        .arg("--suggest-delimiter")
+        .arg(&fifo)
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .expect("Failed to execute command");
+    std::fs::write(&fifo, "Name;Age\nJohn;25\n").unwrap();
+
+    // The pipe can't be read again to check the delimiter, so that's skipped
+    // rather than waiting for another writer
+    let start = Instant::now();
+    while child.try_wait().unwrap().is_none() {
+        if start.elapsed() > Duration::from_secs(10) {
+            child.kill().unwrap();
+            panic!("csvmd didn't finish after reading a named pipe");
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "| Name;Age |\n| --- |\n| John;25 |\n"
+    );
+}
+
+#[test]
+fn test_cli_separate_tables() {
+    let dir = tempfile::tempdir().unwrap();
+    let first = dir.path().join("people.csv");
+    std::fs::write(&first, "Name,Age\nJohn,25").unwrap();
+    let second = dir.path().join("cities.csv");
+    std::fs::write(&second, "City\nLondon\nParis").unwrap();
+
+    for mode in [None, Some("--stream")] {
+        let output = Command::new("cargo")
+            .args(["run", "--", "--separate-tables"])
+            .args(mode)
+            .arg(&first)
+            .arg(&second)
+            .output()
+            .expect("Failed to execute command");
+
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            "## people.csv\n\n| Name | Age |\n| --- | --- |\n| John | 25 |\n\n\
+             ## cities.csv\n\n| City |\n| --- |\n| London |\n| Paris |\n"
+        );
+    }
+}
+
+#[test]
+fn test_cli_multiple_files_require_separate_tables() {
+    let mut temp_file = NamedTempFile::new().unwrap();
+    write!(temp_file, "A\n1").unwrap();
+
+    let output = Command::new("cargo")
+        .args(["run", "--"])
+        .arg(temp_file.path())
+        .arg(temp_file.path())
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("--separate-tables"));
+}
+
+#[test]
+fn test_cli_output_append() {
+    let mut first = NamedTempFile::new().unwrap();
+    write!(first, "A,B\n1,2").unwrap();
+    let mut second = NamedTempFile::new().unwrap();
+    write!(second, "C\n3").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let output_path = dir.path().join("tables.md");
+
+    for (input, mode) in [(&first, None), (&second, Some("--stream"))] {
+        let output = Command::new("cargo")
+            .args(["run", "--", "--output"])
+            .arg(&output_path)
+            .arg("--append")
+            .args(mode)
+            .arg(input.path())
+            .output()
+            .expect("Failed to execute command");
+
+        assert!(output.status.success());
+        assert!(output.stdout.is_empty());
+    }
+
+    let result = std::fs::read_to_string(&output_path).unwrap();
+    assert_eq!(
+        result,
+        "| A | B |\n| --- | --- |\n| 1 | 2 |\n\n| C |\n| --- |\n| 3 |\n"
+    );
+}
+
+#[test]
+fn test_cli_output_overwrites_without_append() {
+    let mut temp_file = NamedTempFile::new().unwrap();
+    write!(temp_file, "A\n1").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let output_path = dir.path().join("table.md");
+    std::fs::write(&output_path, "old content that is longer than the table\n").unwrap();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "-o"])
+        .arg(&output_path)
+        .arg(temp_file.path())
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let result = std::fs::read_to_string(&output_path).unwrap();
+    assert_eq!(result, "| A |\n| --- |\n| 1 |\n");
+}
+
+#[test]
+fn test_cli_output_refuses_to_overwrite_input() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.csv");
+    std::fs::write(&path, "A\n1\n").unwrap();
+
+    for append in [false, true] {
+        let output = Command::new(env!("CARGO_BIN_EXE_csvmd"))
+            .arg("-o")
+            .arg(&path)
+            .args(append.then_some("--append"))
+            .arg(&path)
+            .output()
+            .expect("Failed to execute command");
+
+        assert!(!output.status.success());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("is also an input"), "{}", stderr);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "A\n1\n");
+    }
+}
+
+#[test]
+fn test_cli_output_untouched_when_conversion_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let missing = dir.path().join("missing.csv");
+
+    for (name, append) in [("table.md", false), ("tables.md", true)] {
+        let output_path = dir.path().join(name);
+        std::fs::write(&output_path, "| Old |\n").unwrap();
+
+        let output = Command::new(env!("CARGO_BIN_EXE_csvmd"))
+            .arg("-o")
+            .arg(&output_path)
+            .args(append.then_some("--append"))
+            .arg(&missing)
+            .output()
+            .expect("Failed to execute command");
+
+        assert!(!output.status.success());
+        assert_eq!(std::fs::read_to_string(&output_path).unwrap(), "| Old |\n");
+    }
+
+    // Nothing is left behind next to the output
+    let mut names: Vec<_> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["table.md", "tables.md"]);
+}
+
+#[test]
+fn test_cli_output_format_from_extension() {
+    let mut temp_file = NamedTempFile::new().unwrap();
+    write!(temp_file, "A\n1").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+
+    for (name, args, expected) in [
+        (
+            "table.html",
+            vec![],
+            "<table>\n<thead>\n<tr><th>A</th></tr>\n</thead>\n<tbody>\n<tr><td>1</td></tr>\n</tbody>\n</table>\n",
+        ),
+        ("table.md", vec![], "| A |\n| --- |\n| 1 |\n"),
+        ("notes.org", vec![], "| A |\n|-----|\n| 1 |\n"),
+        ("table.html", vec!["--format", "markdown"], "| A |\n| --- |\n| 1 |\n"),
+    ] {
+        let output_path = dir.path().join(name);
+        let output = Command::new("cargo")
+            .args(["run", "--", "--output"])
+            .arg(&output_path)
+            .args(args)
+            .arg(temp_file.path())
+            .output()
+            .expect("Failed to execute command");
+
+        assert!(output.status.success());
+        let result = std::fs::read_to_string(&output_path).unwrap();
+        assert_eq!(result, expected);
+    }
+}
+
+#[test]
+fn test_cli_output_format_row_options() {
+    let mut temp_file = NamedTempFile::new().unwrap();
+    write!(temp_file, "A,B\n1,x\n2,y").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let output_path = dir.path().join("table.json");
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--columns",
+            "B",
+            "--filter",
+            "A==2",
+            "--output",
+        ])
+        .arg(&output_path)
+        .arg(temp_file.path())
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let result = std::fs::read_to_string(&output_path).unwrap();
+    assert_eq!(result, "[\n  {\"B\":\"y\"}\n]\n");
+
+    // Only the formats the Markdown renderer writes can be streamed
+    let output = Command::new("cargo")
+        .args(["run", "--", "--stream", "--format", "html"])
+        .arg(temp_file.path())
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("`--stream` can only write Markdown and Org-mode tables"));
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "--stream", "--format", "org"])
+        .arg(temp_file.path())
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let result = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(result, "| A | B |\n|-----+-----|\n| 1 | x |\n| 2 | y |\n");
+}
+
+#[test]
+fn test_cli_config_file() {
+    let mut temp_file = NamedTempFile::new().unwrap();
+    write!(temp_file, "Name;Age\nJohn;25").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("options.toml");
+    std::fs::write(
+        &config_path,
+        "delimiter = \"semicolon\"\nalign = \"center\"\n",
+    )
+    .unwrap();
+
+    for (args, separator) in [
+        (vec![], "| :---: | :---: |"),
+        (vec!["--align", "right"], "| ---: | ---: |"),
+    ] {
+        let output = Command::new("cargo")
+            .args(["run", "--", "--config"])
+            .arg(&config_path)
+            .args(args)
+            .arg(temp_file.path())
+            .output()
+            .expect("Failed to execute command");
+
+        assert!(output.status.success());
+        let result = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(
+            result,
+            format!("| Name | Age |\n{}\n| John | 25 |\n", separator)
+        );
+    }
+}
+
+#[test]
+fn test_cli_config_file_in_current_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("csvmd.toml"), "bold-headers = true\n").unwrap();
+    std::fs::write(dir.path().join("data.csv"), "Name\nJohn\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_csvmd"))
+        .arg("data.csv")
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let result = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(result, "| **Name** |\n| --- |\n| John |\n");
+}
+
+#[test]
+fn test_cli_plain() {
+    let mut temp_file = NamedTempFile::new().unwrap();
+    write!(temp_file, "Name;Age\nJohn;25").unwrap();
+
+    for (args, env) in [(vec!["--plain"], None), (vec![], Some("1"))] {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_csvmd"));
+        command.args(args).arg(temp_file.path());
+        if let Some(value) = env {
+            command.env("CSVMD_PLAIN", value);
+        }
+        let output = command.output().expect("Failed to execute command");
+
+        assert!(output.status.success());
+        assert!(output.stderr.is_empty());
+        let result = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(result, "| Name;Age |\n| --- |\n| John;25 |\n");
+    }
+}
+
+#[test]
+fn test_cli_invalid_config_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("csvmd.toml");
+    std::fs::write(&config_path, "align = \"sideways\"\n").unwrap();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "--pretty-print-config", "--config"])
+        .arg(&config_path)
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("invalid `align` in config file"));
+}
+
+/// Run csvmd with `args` and stdout on a pseudo-terminal, using `script`,
+/// returning whether it succeeded and everything it wrote to the terminal, or
+/// `None` where `script` isn't available.
+#[cfg(target_os = "linux")]
+fn run_on_terminal(args: &str) -> Option<(bool, String)> {
+    let command = format!("{} {}", env!("CARGO_BIN_EXE_csvmd"), args);
+    let output = Command::new("script")
+        .args(["-qec", &command, "/dev/null"])
+        .output()
+        .ok()?;
+    Some((
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+    ))
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_cli_tty_row_limit_counts_data_rows() {
+    let mut temp_file = NamedTempFile::new().unwrap();
+    writeln!(temp_file, "A,B\n1,2\n3,4\n5,6\n7,8").unwrap();
+    let path = temp_file.path().display();
+
+    // The header and separator don't count towards the limit
+    for mode in ["", "--stream"] {
+        let args = format!("{} --tty-row-limit 4 {}", mode, path);
+        let Some((success, output)) = run_on_terminal(&args) else {
+            return;
+        };
+        assert!(success, "output: {}", output);
+        assert!(output.contains("| 7 | 8 |"));
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_cli_tty_row_limit_error_shows_hint() {
+    let mut temp_file = NamedTempFile::new().unwrap();
+    writeln!(temp_file, "A,B\n1,2\n3,4\n5,6\n7,8\n9,0").unwrap();
+    let path = temp_file.path().display();
+
+    // Streaming holds the rows back too, until it's clear there are too many
+    for mode in ["", "--stream"] {
+        let args = format!("{} --tty-row-limit 4 {}", mode, path);
+        let Some((success, output)) = run_on_terminal(&args) else {
+            return;
+        };
+        assert!(!success);
+        assert!(!output.contains("| 1 | 2 |"));
+        assert!(output.contains(
+            "Error: The output has more than 4 rows, too many to write to a terminal; \
+             redirect it to a file, see the first and last rows with `--preview 10,10`, \
+             or pass `--force` to write it anyway"
+        ));
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_cli_tty_row_limit_ignored_with_plain() {
+    let mut temp_file = NamedTempFile::new().unwrap();
+    writeln!(temp_file, "A,B\n1,2\n3,4\n5,6").unwrap();
+    let path = temp_file.path().display();
+
+    let Some((success, output)) = run_on_terminal(&format!("--plain --tty-row-limit 1 {}", path))
+    else {
+        return;
+    };
+    assert!(success, "output: {}", output);
+    assert!(output.contains("| 5 | 6 |"));
+}
+
+#[test]
+fn test_cli_tty_row_limit_ignored_when_not_a_terminal() {
+    let mut temp_file = NamedTempFile::new().unwrap();
+    writeln!(temp_file, "N").unwrap();
+    for i in 0..20 {
+        writeln!(temp_file, "{}", i).unwrap();
+    }
+
+    // stdout is a pipe, so the whole table is written
+    let output = Command::new(env!("CARGO_BIN_EXE_csvmd"))
+        .args(["--tty-row-limit", "5"])
+        .arg(temp_file.path())
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let result = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(result.lines().count(), 22);
+    assert!(result.ends_with("| 19 |\n"));
+}